@@ -4,6 +4,9 @@
 //! Each event has a UUID and optionally links to a parent via `parent_tool_use_id`,
 //! forming chains that can be stored as edges in a graph database.
 
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -143,6 +146,10 @@ pub struct ClaudeEvent {
     /// Raw JSON value for the complete event.
     #[serde(flatten)]
     pub raw: Value,
+
+    /// When this event was parsed, used when the raw event has no timestamp.
+    #[serde(skip, default = "Utc::now")]
+    pub received_at: DateTime<Utc>,
 }
 
 impl ClaudeEvent {
@@ -276,6 +283,7 @@ impl ClaudeEvent {
             num_turns,
             tool_use_ids,
             raw: value,
+            received_at: Utc::now(),
         })
     }
 
@@ -289,6 +297,43 @@ impl ClaudeEvent {
         self.event_type == Some(EventType::Assistant)
     }
 
+    /// Check if this is an error event.
+    pub fn is_error(&self) -> bool {
+        self.event_type == Some(EventType::Error)
+    }
+
+    /// Get a readable description of an error event, or `None` for other events.
+    pub fn error_message(&self) -> Option<String> {
+        if !self.is_error() {
+            return None;
+        }
+
+        let detail = self
+            .get_text()
+            .map(String::from)
+            .or_else(|| match self.raw.get("error") {
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(error) => error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(String::from),
+                None => None,
+            })
+            .or_else(|| {
+                self.raw
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            });
+
+        Some(match (self.subtype.as_deref(), detail) {
+            (Some(subtype), Some(detail)) => format!("{subtype}: {detail}"),
+            (Some(subtype), None) => subtype.to_string(),
+            (None, Some(detail)) => detail,
+            (None, None) => "unknown error".to_string(),
+        })
+    }
+
     /// Check if this is a system init event.
     pub fn is_init(&self) -> bool {
         self.event_type == Some(EventType::System) && self.subtype.as_deref() == Some("init")
@@ -304,6 +349,20 @@ impl ClaudeEvent {
         self.message.as_deref().or(self.result.as_deref())
     }
 
+    /// Get the time this event happened.
+    ///
+    /// Prefers the `timestamp` carried in the raw event (present in Claude
+    /// transcripts) so replayed imports keep their original ordering, and
+    /// falls back to the time the event was parsed. Repeated calls return the
+    /// same value, so what is stored matches what is broadcast.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.raw
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map_or(self.received_at, |ts| ts.with_timezone(&Utc))
+    }
+
     /// Get the event type as a string for labeling.
     pub fn type_label(&self) -> &str {
         match &self.event_type {
@@ -318,6 +377,97 @@ impl ClaudeEvent {
     }
 }
 
+/// A partially streamed assistant message.
+#[derive(Debug, Default)]
+struct PartialMessage {
+    message_id: Option<String>,
+    model: Option<String>,
+    session_id: Option<String>,
+    text: String,
+}
+
+/// Reassembles `stream_event` deltas (from `--include-partial-messages`)
+/// into complete assistant events.
+///
+/// Partial messages are keyed by `parent_tool_use_id`, so interleaved
+/// deltas from sub-agents are never merged into each other.
+#[derive(Debug, Default)]
+pub struct StreamAssembler {
+    partials: HashMap<Option<String>, PartialMessage>,
+}
+
+impl StreamAssembler {
+    /// Create an empty assembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed an event into the assembler.
+    ///
+    /// Returns a synthesized assistant event when a `message_stop` completes
+    /// a streamed message. Non-stream events are ignored.
+    pub fn push(&mut self, event: &ClaudeEvent) -> Option<ClaudeEvent> {
+        if event.event_type != Some(EventType::StreamEvent) {
+            return None;
+        }
+
+        let inner = event.raw.get("event")?;
+        let key = event.parent_tool_use_id.clone();
+
+        match inner.get("type").and_then(Value::as_str)? {
+            "message_start" => {
+                let message = inner.get("message");
+                let field = |name: &str| {
+                    message
+                        .and_then(|m| m.get(name))
+                        .and_then(Value::as_str)
+                        .map(String::from)
+                };
+                self.partials.insert(
+                    key,
+                    PartialMessage {
+                        message_id: field("id"),
+                        model: field("model"),
+                        session_id: event.session_id.clone(),
+                        text: String::new(),
+                    },
+                );
+                None
+            }
+            "content_block_delta" => {
+                let delta = inner.get("delta")?;
+                if delta.get("type").and_then(Value::as_str) == Some("text_delta") {
+                    if let Some(text) = delta.get("text").and_then(Value::as_str) {
+                        self.partials.entry(key).or_default().text.push_str(text);
+                    }
+                }
+                None
+            }
+            "message_stop" => {
+                let partial = self.partials.remove(&key)?;
+                let raw = serde_json::json!({
+                    "type": "assistant",
+                    "session_id": partial.session_id.or_else(|| event.session_id.clone()),
+                    "parent_tool_use_id": key,
+                    "message": {
+                        "id": partial.message_id,
+                        "role": "assistant",
+                        "model": partial.model,
+                        "content": [{ "type": "text", "text": partial.text }],
+                    },
+                });
+                ClaudeEvent::parse(&raw.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Cut `s` to at most `max_chars` characters without splitting a codepoint.
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    s.char_indices().nth(max_chars).map_or(s, |(i, _)| &s[..i])
+}
+
 /// Strip system reminders and other injected noise from content.
 fn strip_noise(text: &str) -> String {
     use regex::Regex;
@@ -489,8 +639,9 @@ fn extract_tool_results(value: &Value) -> Vec<ToolResult> {
                         let content_summary = match block.get("content") {
                             Some(Value::String(s)) => {
                                 let cleaned = strip_noise(s);
-                                if cleaned.len() > 200 {
-                                    Some(format!("{}...", &cleaned[..200]))
+                                let short = truncate_chars(&cleaned, 200);
+                                if short.len() < cleaned.len() {
+                                    Some(format!("{short}..."))
                                 } else if !cleaned.is_empty() {
                                     Some(cleaned)
                                 } else {
@@ -504,8 +655,9 @@ fn extract_tool_results(value: &Value) -> Vec<ToolResult> {
                                     .next()
                                     .map(|s| {
                                         let cleaned = strip_noise(s);
-                                        if cleaned.len() > 200 {
-                                            format!("{}...", &cleaned[..200])
+                                        let short = truncate_chars(&cleaned, 200);
+                                        if short.len() < cleaned.len() {
+                                            format!("{short}...")
                                         } else {
                                             cleaned
                                         }
@@ -532,6 +684,31 @@ fn extract_tool_results(value: &Value) -> Vec<ToolResult> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn truncate_chars_respects_boundaries() {
+        assert_eq!(truncate_chars("hello", 10), "hello");
+        assert_eq!(truncate_chars("hello", 3), "hel");
+        // "日" is three bytes; a byte slice at 2 would panic
+        assert_eq!(truncate_chars("a日本", 2), "a日");
+        assert_eq!(truncate_chars("", 5), "");
+    }
+
+    #[test]
+    fn tool_result_summary_with_multibyte_boundary() {
+        // 199 ASCII bytes put the 200-byte cut inside the first "日"
+        let content = format!("{}日本語のテキスト", "x".repeat(199));
+        let raw = serde_json::json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": [{"type": "tool_result", "tool_use_id": "toolu_1", "content": content}],
+            },
+        });
+        let event = ClaudeEvent::parse(&raw.to_string()).unwrap();
+        let summary = event.tool_results[0].content_summary.as_deref().unwrap();
+        assert_eq!(summary, format!("{}日...", "x".repeat(199)));
+    }
+
     #[test]
     fn parse_system_init() {
         let json = r#"{"type":"system","subtype":"init","uuid":"abc-123","session_id":"sess-1"}"#;
@@ -568,6 +745,39 @@ mod tests {
         assert_eq!(event.message.as_deref(), Some("Hello world"));
     }
 
+    #[test]
+    fn created_at_prefers_raw_timestamp() {
+        let json = r#"{"type":"user","uuid":"u-1","timestamp":"2025-01-02T03:04:05Z"}"#;
+        let event = ClaudeEvent::parse(json).unwrap();
+        assert_eq!(event.created_at().to_rfc3339(), "2025-01-02T03:04:05+00:00");
+    }
+
+    #[test]
+    fn created_at_is_stable_without_timestamp() {
+        let event = ClaudeEvent::parse(r#"{"type":"user","uuid":"u-1"}"#).unwrap();
+        let first = event.created_at();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(event.created_at(), first);
+        assert_eq!(first, event.received_at);
+    }
+
+    #[test]
+    fn parse_error_message() {
+        let json = r#"{"type":"error","subtype":"api_error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let event = ClaudeEvent::parse(json).unwrap();
+        assert!(event.is_error());
+        assert_eq!(
+            event.error_message().as_deref(),
+            Some("api_error: Overloaded")
+        );
+
+        let bare = ClaudeEvent::parse(r#"{"type":"error","message":"boom"}"#).unwrap();
+        assert_eq!(bare.error_message().as_deref(), Some("boom"));
+
+        let not_error = ClaudeEvent::parse(r#"{"type":"result"}"#).unwrap();
+        assert!(not_error.error_message().is_none());
+    }
+
     #[test]
     fn parse_result() {
         let json = r#"{"type":"result","uuid":"res-1","session_id":"sess-1","total_cost_usd":0.05,"duration_ms":1234,"num_turns":5}"#;
@@ -577,4 +787,31 @@ mod tests {
         assert_eq!(event.duration_ms, Some(1234));
         assert_eq!(event.num_turns, Some(5));
     }
+
+    #[test]
+    fn assembler_joins_deltas_per_parent() {
+        let lines = [
+            r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","model":"m"}}}"#,
+            r#"{"type":"stream_event","parent_tool_use_id":"tool-1","event":{"type":"message_start","message":{"id":"msg_2"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","delta":{"type":"text_delta","text":"Hello "}}}"#,
+            r#"{"type":"stream_event","parent_tool_use_id":"tool-1","event":{"type":"content_block_delta","delta":{"type":"text_delta","text":"nested"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","delta":{"type":"text_delta","text":"world"}}}"#,
+            r#"{"type":"stream_event","parent_tool_use_id":"tool-1","event":{"type":"message_stop"}}"#,
+            r#"{"type":"stream_event","event":{"type":"message_stop"}}"#,
+        ];
+
+        let mut assembler = StreamAssembler::new();
+        let messages: Vec<ClaudeEvent> = lines
+            .iter()
+            .filter_map(|line| assembler.push(&ClaudeEvent::parse(line).unwrap()))
+            .collect();
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].is_assistant());
+        assert_eq!(messages[0].parent_tool_use_id.as_deref(), Some("tool-1"));
+        assert_eq!(messages[0].get_text(), Some("nested"));
+        assert_eq!(messages[1].message_id.as_deref(), Some("msg_1"));
+        assert_eq!(messages[1].model.as_deref(), Some("m"));
+        assert_eq!(messages[1].get_text(), Some("Hello world"));
+    }
 }
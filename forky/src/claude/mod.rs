@@ -1,7 +1,11 @@
 //! Claude CLI interaction module.
 
 mod events;
+mod pricing;
+mod provider;
 mod spawn;
+mod transcript;
 
-pub use events::ClaudeEvent;
-pub use spawn::{spawn_claude, ClaudeOptions};
+pub use events::{truncate_chars, ClaudeEvent, ToolResult};
+pub use provider::ProviderKind;
+pub use spawn::{run_claude_interactive, shell_quote, spawn_claude, ClaudeOptions, ClaudeResult};
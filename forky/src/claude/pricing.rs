@@ -0,0 +1,200 @@
+//! Estimated cost of a run from the token usage on its events.
+//!
+//! Claude only reports a cost on the final `result` event, which is too late
+//! for `--max-cost` to stop a run that is still spending. The estimate here
+//! prices each assistant message's usage as it arrives.
+
+use std::collections::HashMap;
+
+use super::events::{ClaudeEvent, TokenUsage};
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ModelPrices {
+    input: f64,
+    output: f64,
+    cache_read: f64,
+    cache_write: f64,
+}
+
+const OPUS_4_1: ModelPrices = ModelPrices {
+    input: 15.0,
+    output: 75.0,
+    cache_read: 1.5,
+    cache_write: 18.75,
+};
+
+const OPUS_4_5: ModelPrices = ModelPrices {
+    input: 5.0,
+    output: 25.0,
+    cache_read: 0.5,
+    cache_write: 6.25,
+};
+
+const SONNET: ModelPrices = ModelPrices {
+    input: 3.0,
+    output: 15.0,
+    cache_read: 0.3,
+    cache_write: 3.75,
+};
+
+const HAIKU_4_5: ModelPrices = ModelPrices {
+    input: 1.0,
+    output: 5.0,
+    cache_read: 0.1,
+    cache_write: 1.25,
+};
+
+const HAIKU_3_5: ModelPrices = ModelPrices {
+    input: 0.8,
+    output: 4.0,
+    cache_read: 0.08,
+    cache_write: 1.0,
+};
+
+/// Prices for a model ID such as `claude-sonnet-4-5-20250929`.
+///
+/// Unknown models get the most expensive prices so a budget errs on the
+/// side of stopping early.
+fn prices(model: &str) -> ModelPrices {
+    if model.contains("haiku") {
+        if model.contains("3-5") {
+            HAIKU_3_5
+        } else {
+            HAIKU_4_5
+        }
+    } else if model.contains("sonnet") {
+        SONNET
+    } else if model.contains("opus-4-1") || model.contains("opus-4-2025") {
+        OPUS_4_1
+    } else if model.contains("opus") {
+        OPUS_4_5
+    } else {
+        OPUS_4_1
+    }
+}
+
+/// Cost of `tokens` at `per_million` USD per million tokens.
+fn cost(tokens: u64, per_million: f64) -> f64 {
+    // A single message never gets near 2^32 tokens
+    f64::from(u32::try_from(tokens).unwrap_or(u32::MAX)) * per_million / 1_000_000.0
+}
+
+/// Estimated USD cost of one message's usage.
+fn usage_cost(model: &str, usage: &TokenUsage) -> f64 {
+    let prices = prices(model);
+    cost(usage.input_tokens, prices.input)
+        + cost(usage.output_tokens, prices.output)
+        + cost(usage.cache_read_tokens, prices.cache_read)
+        + cost(usage.cache_creation_tokens, prices.cache_write)
+}
+
+/// Running cost of a run, updated event by event.
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    /// Estimated cost of each message seen so far. Claude repeats a
+    /// message's usage on every content-block event, so later events for the
+    /// same message replace its estimate instead of adding to it.
+    by_message: HashMap<String, f64>,
+    /// Estimated cost of events without a message ID.
+    unkeyed: f64,
+    /// Cost reported by Claude itself, once a result arrives.
+    reported: Option<f64>,
+}
+
+impl CostTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an event's usage to the running cost.
+    pub fn push(&mut self, event: &ClaudeEvent) {
+        if let Some(reported) = event.total_cost_usd.or(event.cost_usd) {
+            self.reported = Some(reported);
+        }
+
+        let Some(ref usage) = event.usage else {
+            return;
+        };
+        let estimate = usage_cost(event.model.as_deref().unwrap_or_default(), usage);
+        match event.message_id {
+            Some(ref id) => {
+                let seen = self.by_message.entry(id.clone()).or_default();
+                *seen = seen.max(estimate);
+            }
+            None => self.unkeyed += estimate,
+        }
+    }
+
+    /// Best known cost so far in USD: the larger of Claude's own figure and
+    /// the estimate.
+    pub fn spent(&self) -> f64 {
+        let estimate = self.unkeyed + self.by_message.values().sum::<f64>();
+        self.reported
+            .map_or(estimate, |reported| reported.max(estimate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assistant(message_id: &str, model: &str, input: u64, output: u64) -> ClaudeEvent {
+        let json = serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "id": message_id,
+                "model": model,
+                "role": "assistant",
+                "content": [{"type": "text", "text": "working"}],
+                "usage": {"input_tokens": input, "output_tokens": output},
+            },
+        });
+        ClaudeEvent::parse(&json.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_prices() {
+        assert_eq!(prices("claude-opus-4-1-20250805"), OPUS_4_1);
+        assert_eq!(prices("claude-opus-4-20250514"), OPUS_4_1);
+        assert_eq!(prices("claude-opus-4-5-20251101"), OPUS_4_5);
+        assert_eq!(prices("claude-sonnet-4-5-20250929"), SONNET);
+        assert_eq!(prices("claude-haiku-4-5-20251001"), HAIKU_4_5);
+        assert_eq!(prices("claude-3-5-haiku-20241022"), HAIKU_3_5);
+        assert_eq!(prices("some-new-model"), OPUS_4_1);
+    }
+
+    #[test]
+    fn test_cost_tracker_counts_each_message_once() {
+        let mut tracker = CostTracker::new();
+        let model = "claude-sonnet-4-5-20250929";
+
+        // $3 of input plus $15 of output
+        tracker.push(&assistant("msg_1", model, 1_000_000, 1_000_000));
+        assert!((tracker.spent() - 18.0).abs() < 1e-9);
+
+        // The same message again, as another content block
+        tracker.push(&assistant("msg_1", model, 1_000_000, 1_000_000));
+        assert!((tracker.spent() - 18.0).abs() < 1e-9);
+
+        tracker.push(&assistant("msg_2", model, 1_000_000, 0));
+        assert!((tracker.spent() - 21.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_tracker_prefers_larger_reported_cost() {
+        let mut tracker = CostTracker::new();
+        tracker.push(&assistant(
+            "msg_1",
+            "claude-sonnet-4-5-20250929",
+            1_000_000,
+            0,
+        ));
+
+        let result =
+            ClaudeEvent::parse(r#"{"type":"result","subtype":"success","total_cost_usd":4.5}"#)
+                .unwrap();
+        tracker.push(&result);
+        assert!((tracker.spent() - 4.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,238 @@
+//! AI CLI providers that forks can be run with.
+//!
+//! A provider knows how to build the command line for its CLI and how to turn
+//! each line it prints into a [`ClaudeEvent`]. The rest of forky (streaming,
+//! storage, the UI) only ever sees `ClaudeEvent`s.
+
+use clap::ValueEnum;
+use tokio::process::Command;
+
+use super::events::ClaudeEvent;
+use super::spawn::ClaudeOptions;
+
+/// An AI CLI that forky can drive.
+pub trait Provider: Send + Sync {
+    /// Name stored as the fork's `ai_provider`.
+    fn name(&self) -> &'static str;
+
+    /// Build the command for one non-interactive run.
+    ///
+    /// Stdio and the working directory are set up by the caller.
+    fn command(&self, options: &ClaudeOptions) -> Command;
+
+    /// Build the command that resumes `options.session_id` in the CLI's own
+    /// interactive UI, with `options.message` as the first prompt if set.
+    fn interactive_command(&self, options: &ClaudeOptions) -> Command;
+
+    /// Parse one line of stdout, returning `None` for lines that aren't events.
+    fn parse_line(&self, line: &str) -> Option<ClaudeEvent>;
+}
+
+/// Available providers, selectable with `--provider`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProviderKind {
+    /// Claude Code (`claude`)
+    #[default]
+    Claude,
+}
+
+impl ProviderKind {
+    /// Name stored as the fork's `ai_provider`.
+    pub fn as_str(self) -> &'static str {
+        self.provider().name()
+    }
+
+    /// The provider implementation for this kind.
+    pub fn provider(self) -> &'static dyn Provider {
+        match self {
+            Self::Claude => &ClaudeProvider,
+        }
+    }
+}
+
+/// Runs `claude --output-format stream-json` in print mode.
+pub struct ClaudeProvider;
+
+impl Provider for ClaudeProvider {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn command(&self, options: &ClaudeOptions) -> Command {
+        let mut cmd = Command::new(options.claude_path.as_deref().unwrap_or("claude"));
+
+        // Always use these flags
+        cmd.arg("--dangerously-skip-permissions");
+        cmd.arg("--output-format").arg("stream-json");
+        cmd.arg("--verbose");
+
+        // Session ID handling - IMPORTANT: --fork-session and --session-id conflict!
+        // When --fork-session is used, Claude Code ignores --session-id and creates its own UUID.
+        // This caused a cascade bug where forky lost track of sessions.
+        //
+        // Our strategy:
+        // - If we have an explicit_session_id (UUIDv7), use ONLY --session-id (no forking)
+        // - If we want to fork, DON'T use explicit_session_id (let Claude generate)
+        // - Never use both --fork-session and --session-id together
+        if let Some(ref session_id) = options.explicit_session_id {
+            // Use our controlled session ID - do NOT fork (it would override our ID)
+            cmd.arg("--session-id").arg(session_id);
+            // Note: We intentionally skip --fork-session here to preserve our session ID
+        } else if options.fork_session {
+            // No explicit ID requested, allow Claude to fork and generate its own ID
+            if let Some(ref session_id) = options.session_id {
+                cmd.arg("-r").arg(session_id);
+            }
+            cmd.arg("--fork-session");
+        } else if let Some(ref session_id) = options.session_id {
+            // Resume existing session without forking
+            cmd.arg("-r").arg(session_id);
+        }
+
+        // Model if specified
+        if let Some(ref model) = options.model {
+            cmd.arg("--model").arg(model);
+        }
+
+        // === System Prompt Options ===
+        if let Some(ref prompt) = options.system_prompt {
+            cmd.arg("--system-prompt").arg(prompt);
+        } else if let Some(ref prompt) = options.append_system_prompt {
+            cmd.arg("--append-system-prompt").arg(prompt);
+        }
+
+        // === Chrome Options ===
+        if options.chrome {
+            cmd.arg("--chrome");
+        } else if options.no_chrome {
+            cmd.arg("--no-chrome");
+        }
+
+        // === Additional Directories ===
+        for dir in &options.add_dirs {
+            cmd.arg("--add-dir").arg(dir);
+        }
+
+        // === Advanced Options ===
+        if let Some(ref agents) = options.agents {
+            cmd.arg("--agents").arg(agents);
+        }
+
+        if let Some(ref mcp_config) = options.mcp_config {
+            cmd.arg("--mcp-config").arg(mcp_config);
+        }
+
+        if let Some(ref settings) = options.settings {
+            cmd.arg("--settings").arg(settings);
+        }
+
+        if let Some(max_turns) = options.max_turns {
+            cmd.arg("--max-turns").arg(max_turns.to_string());
+        }
+
+        if let Some(ref tools) = options.tools {
+            cmd.arg("--tools").arg(tools);
+        }
+
+        if let Some(ref allowed_tools) = options.allowed_tools {
+            cmd.arg("--allowedTools").arg(allowed_tools);
+        }
+
+        if options.include_partial_messages {
+            cmd.arg("--include-partial-messages");
+        }
+
+        // Message as print mode; with stdin input and no message, claude
+        // takes the prompt from stdin
+        cmd.arg("-p");
+        if options.stdin_input.is_none() || !options.message.is_empty() {
+            cmd.arg(&options.message);
+        }
+
+        cmd
+    }
+
+    fn interactive_command(&self, options: &ClaudeOptions) -> Command {
+        let mut cmd = Command::new(options.claude_path.as_deref().unwrap_or("claude"));
+
+        if let Some(ref session_id) = options.session_id {
+            cmd.arg("-r").arg(session_id);
+        }
+        if let Some(ref model) = options.model {
+            cmd.arg("--model").arg(model);
+        }
+        for dir in &options.add_dirs {
+            cmd.arg("--add-dir").arg(dir);
+        }
+        if !options.message.is_empty() {
+            cmd.arg(&options.message);
+        }
+
+        cmd
+    }
+
+    fn parse_line(&self, line: &str) -> Option<ClaudeEvent> {
+        ClaudeEvent::parse(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claude_command_args() {
+        let options = ClaudeOptions {
+            explicit_session_id: Some("sess-1".to_string()),
+            fork_session: true,
+            model: Some("haiku".to_string()),
+            message: "hello".to_string(),
+            ..Default::default()
+        };
+        let cmd = ClaudeProvider.command(&options);
+        let args: Vec<_> = cmd
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(cmd.as_std().get_program(), "claude");
+        assert!(args.windows(2).any(|w| w == ["--session-id", "sess-1"]));
+        assert!(!args.contains(&"--fork-session".to_string()));
+        assert!(args.windows(2).any(|w| w == ["--model", "haiku"]));
+        assert_eq!(args[args.len() - 2..], ["-p", "hello"]);
+    }
+
+    #[test]
+    fn claude_reads_prompt_from_stdin() {
+        let options = ClaudeOptions {
+            stdin_input: Some("a very long prompt".to_string()),
+            ..Default::default()
+        };
+        let cmd = ClaudeProvider.command(&options);
+        assert_eq!(cmd.as_std().get_args().last().unwrap(), "-p");
+    }
+
+    #[test]
+    fn claude_interactive_args() {
+        let options = ClaudeOptions {
+            session_id: Some("sess-1".to_string()),
+            message: "take it from here".to_string(),
+            ..Default::default()
+        };
+        let cmd = ClaudeProvider.interactive_command(&options);
+        let args: Vec<_> = cmd
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(args, ["-r", "sess-1", "take it from here"]);
+    }
+
+    #[test]
+    fn provider_names() {
+        assert_eq!(ProviderKind::default().as_str(), "claude");
+        assert_eq!(ProviderKind::Claude.provider().name(), "claude");
+    }
+}
@@ -1,11 +1,31 @@
 //! Claude CLI process spawning.
 
 use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
 
-use super::events::ClaudeEvent;
+use super::events::{ClaudeEvent, StreamAssembler};
+use super::pricing::CostTracker;
+use super::provider::ProviderKind;
+use super::transcript::TranscriptLog;
+
+/// Flush streamed events once this many are pending.
+const STREAM_BATCH_SIZE: usize = 10;
+
+/// Flush streamed events at least this often.
+const STREAM_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a finished fork waits for its last events to reach the server.
+const STREAM_FINISH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Give up on reassembling a split line once the fragment grows past this.
+const MAX_PARTIAL_LINE_BYTES: usize = 16 * 1024 * 1024;
 
 /// Options for spawning Claude.
 #[derive(Debug, Clone, Default)]
@@ -20,10 +40,20 @@ pub struct ClaudeOptions {
     pub model: Option<String>,
     /// Message to send.
     pub message: String,
+    /// Content piped to the CLI's stdin, for prompts too large for argv.
+    pub stdin_input: Option<String>,
     /// Working directory.
     pub working_dir: Option<String>,
     /// Additional directories to add.
     pub add_dirs: Vec<String>,
+    /// Path to the claude binary (defaults to `claude` on PATH).
+    pub claude_path: Option<String>,
+    /// Which AI CLI to run.
+    pub provider: ProviderKind,
+    /// Environment variables to set (merged with current env).
+    pub env: HashMap<String, String>,
+    /// Clear the environment and copy back only these variables (if any).
+    pub copy_env: Option<Vec<String>>,
 
     // === System Prompt Options ===
     /// Text to append to system prompt (if any).
@@ -52,6 +82,14 @@ pub struct ClaudeOptions {
     pub allowed_tools: Option<String>,
     /// Include partial streaming messages.
     pub include_partial_messages: bool,
+    /// Kill the process if it runs longer than this.
+    pub timeout: Option<Duration>,
+    /// Kill the process once its running cost passes this many USD.
+    pub max_cost: Option<f64>,
+    /// Print the command instead of running it.
+    pub dry_run: bool,
+    /// Print assistant and thinking text to stdout as it arrives.
+    pub attach: bool,
 
     // === Streaming Options ===
     /// Server URL for real-time event streaming.
@@ -60,10 +98,16 @@ pub struct ClaudeOptions {
     pub fork_id: Option<String>,
     /// Project path for database routing.
     pub project_path: Option<String>,
+
+    // === Transcript Log ===
+    /// Plain-text transcript written as events arrive (if any).
+    pub log_file: Option<PathBuf>,
+    /// Stop writing the transcript past this many bytes.
+    pub log_max_bytes: u64,
 }
 
 /// Result from a Claude session.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ClaudeResult {
     /// The session ID (may be new if forked).
     pub session_id: Option<String>,
@@ -77,211 +121,703 @@ pub struct ClaudeResult {
     pub cost_usd: Option<f64>,
     /// All parsed events (for graph storage).
     pub events: Vec<ClaudeEvent>,
+    /// Whether the process was killed due to timeout.
+    pub timed_out: bool,
+    /// Messages from error events, in the order they arrived.
+    pub errors: Vec<String>,
+    /// Subtype of the result event (`success`, `error_max_turns`, ...).
+    pub result_subtype: Option<String>,
+    /// Whether the process was killed for passing `max_cost`.
+    pub cost_limit_exceeded: bool,
 }
 
-/// Spawn a Claude CLI process and stream events.
+/// Batches raw events and posts them to the server's ingest endpoint.
 ///
-/// This runs the claude CLI with the following arguments:
+/// Batches are posted one at a time, in order, from a background task, so a
+/// slow or dead server never blocks the fork. `finish` waits (briefly) for
+/// the last of them to go out.
+struct EventStreamer {
+    project_path: Option<String>,
+    fork_id: Option<String>,
+    pending: Vec<serde_json::Value>,
+    batches: mpsc::UnboundedSender<serde_json::Value>,
+    poster: JoinHandle<()>,
+}
+
+impl EventStreamer {
+    fn new(url: String, project_path: Option<String>, fork_id: Option<String>) -> Self {
+        let client = crate::config::http_client().clone();
+        let (batches, mut queued) = mpsc::unbounded_channel::<serde_json::Value>();
+        let poster = tokio::spawn(
+            async move {
+                while let Some(body) = queued.recv().await {
+                    match client.post(&url).json(&body).send().await {
+                        Ok(resp) if !resp.status().is_success() => {
+                            tracing::warn!(status = %resp.status(), "Server rejected streamed events");
+                        }
+                        Ok(_) => {}
+                        Err(e) => tracing::warn!("Failed to stream events: {e}"),
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+        Self {
+            project_path,
+            fork_id,
+            pending: Vec::new(),
+            batches,
+            poster,
+        }
+    }
+
+    /// Queue an event, flushing if the batch is full.
+    fn push(&mut self, raw: serde_json::Value) {
+        self.pending.push(raw);
+        if self.pending.len() >= STREAM_BATCH_SIZE {
+            self.flush();
+        }
+    }
+
+    /// Queue all pending events to be posted after any earlier batches.
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let body = serde_json::json!({
+            "project_path": self.project_path,
+            "fork_id": self.fork_id,
+            "events": std::mem::take(&mut self.pending),
+        });
+        // The poster only stops once this sender is dropped
+        let _ = self.batches.send(body);
+    }
+
+    /// Flush and wait for every queued batch to be posted, up to a timeout.
+    async fn finish(mut self) {
+        self.flush();
+        let Self {
+            batches, poster, ..
+        } = self;
+        drop(batches);
+        if tokio::time::timeout(STREAM_FINISH_TIMEOUT, poster)
+            .await
+            .is_err()
+        {
+            tracing::warn!("Gave up waiting for streamed events to reach the server");
+        }
+    }
+}
+
+/// Spawn the provider's CLI process and stream events.
+///
+/// For Claude this runs:
 /// `claude --dangerously-skip-permissions --output-format stream-json --verbose [options] -p <message>`
+#[tracing::instrument(skip_all, fields(provider = options.provider.as_str(), fork_id = options.fork_id.as_deref()))]
 pub async fn spawn_claude(options: ClaudeOptions) -> Result<ClaudeResult> {
-    let mut cmd = Command::new("claude");
-
-    // Always use these flags
-    cmd.arg("--dangerously-skip-permissions");
-    cmd.arg("--output-format").arg("stream-json");
-    cmd.arg("--verbose");
-
-    // Session ID handling - IMPORTANT: --fork-session and --session-id conflict!
-    // When --fork-session is used, Claude Code ignores --session-id and creates its own UUID.
-    // This caused a cascade bug where forky lost track of sessions.
-    //
-    // Our strategy:
-    // - If we have an explicit_session_id (UUIDv7), use ONLY --session-id (no forking)
-    // - If we want to fork, DON'T use explicit_session_id (let Claude generate)
-    // - Never use both --fork-session and --session-id together
-    if let Some(ref session_id) = options.explicit_session_id {
-        // Use our controlled session ID - do NOT fork (it would override our ID)
-        cmd.arg("--session-id").arg(session_id);
-        // Note: We intentionally skip --fork-session here to preserve our session ID
-    } else if options.fork_session {
-        // No explicit ID requested, allow Claude to fork and generate its own ID
-        if let Some(ref session_id) = options.session_id {
-            cmd.arg("-r").arg(session_id);
+    let provider = options.provider.provider();
+    let mut cmd = provider.command(&options);
+
+    // Working directory
+    if let Some(ref dir) = options.working_dir {
+        cmd.current_dir(dir);
+    }
+    apply_env(&mut cmd, &options);
+
+    if options.dry_run {
+        println!("{}", format_command(cmd.as_std()));
+        if let Some(ref input) = options.stdin_input {
+            println!("(plus {} bytes on stdin)", input.len());
+        }
+        return Ok(ClaudeResult {
+            session_id: options
+                .explicit_session_id
+                .clone()
+                .or_else(|| options.session_id.clone()),
+            success: true,
+            ..ClaudeResult::default()
+        });
+    }
+
+    let mut child = start_process(&mut cmd, &options, provider.name())?;
+    let stdout = child.stdout.take().context("Failed to capture stdout")?;
+    let stderr = child.stderr.take().context("Failed to capture stderr")?;
+
+    let mut stdout_reader = BufReader::new(stdout).lines();
+    let mut stderr_reader = BufReader::new(stderr).lines();
+
+    let mut flush_interval = tokio::time::interval(STREAM_FLUSH_INTERVAL);
+    let mut collector = RunCollector::new(&options);
+    let mut line_reassembler = LineReassembler::default();
+
+    // Process stdout (NDJSON events)
+    let event_loop = async {
+        loop {
+            tokio::select! {
+                line = stdout_reader.next_line() => {
+                    match line {
+                        Ok(Some(line)) => {
+                            let parsed = line_reassembler.push(&line, |l| provider.parse_line(l));
+                            if let Some(event) = parsed {
+                                tracing::debug!(event_type = event.type_label(), "Event");
+                                collector.record(event);
+                                if collector.result.cost_limit_exceeded {
+                                    break;
+                                }
+                            }
+                        }
+                        Ok(None) => {
+                            line_reassembler.finish();
+                            break;
+                        }
+                        Err(e) => {
+                            tracing::error!("Error reading stdout: {e}");
+                            break;
+                        }
+                    }
+                }
+                line = stderr_reader.next_line() => {
+                    match line {
+                        Ok(Some(line)) => collector.stderr(&line),
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::error!("Error reading stderr: {e}");
+                        }
+                    }
+                }
+                _ = flush_interval.tick() => collector.flush(),
+            }
+        }
+    };
+
+    if let Some(duration) = options.timeout {
+        if tokio::time::timeout(duration, event_loop).await.is_err() {
+            tracing::warn!(?duration, "Timed out, killing the process");
+            collector.result.timed_out = true;
+            // Kill the process on timeout
+            let _ = child.kill().await;
         }
-        cmd.arg("--fork-session");
-    } else if let Some(ref session_id) = options.session_id {
-        // Resume existing session without forking
-        cmd.arg("-r").arg(session_id);
+    } else {
+        event_loop.await;
     }
 
-    // Model if specified
-    if let Some(ref model) = options.model {
-        cmd.arg("--model").arg(model);
+    // Everything read so far is kept and streamed below; only the process goes
+    if collector.result.cost_limit_exceeded {
+        let _ = child.kill().await;
     }
 
-    // === System Prompt Options ===
-    if let Some(ref prompt) = options.system_prompt {
-        cmd.arg("--system-prompt").arg(prompt);
-    } else if let Some(ref prompt) = options.append_system_prompt {
-        cmd.arg("--append-system-prompt").arg(prompt);
+    // Send whatever is still buffered before the fork reports back
+    let mut result = collector.finish().await;
+
+    // Wait for the process to finish
+    let status = child
+        .wait()
+        .await
+        .with_context(|| format!("Failed to wait for {} CLI", provider.name()))?;
+    tracing::debug!(%status, events = result.events.len(), "Process exited");
+    result.success = status.success()
+        && result.success
+        && !result.timed_out
+        && !result.cost_limit_exceeded
+        && result.errors.is_empty();
+
+    Ok(result)
+}
+
+/// Pipe stdout and stderr and start the CLI, writing any stdin input.
+fn start_process(
+    cmd: &mut tokio::process::Command,
+    options: &ClaudeOptions,
+    name: &str,
+) -> Result<tokio::process::Child> {
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    if options.stdin_input.is_some() {
+        cmd.stdin(Stdio::piped());
+    } else {
+        cmd.stdin(Stdio::null());
     }
 
-    // === Chrome Options ===
-    if options.chrome {
-        cmd.arg("--chrome");
-    } else if options.no_chrome {
-        cmd.arg("--no-chrome");
+    tracing::debug!(command = %format_command(cmd.as_std()), "Spawning");
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to spawn {name} CLI"))?;
+
+    // Write stdin from its own task so a large input can't deadlock against
+    // a full stdout pipe; dropping the handle closes stdin
+    if let Some(input) = options.stdin_input.clone() {
+        let mut stdin = child.stdin.take().context("Failed to capture stdin")?;
+        tokio::spawn(async move {
+            if let Err(e) = stdin.write_all(input.as_bytes()).await {
+                tracing::error!("Error writing stdin: {e}");
+            }
+        });
     }
+    Ok(child)
+}
+
+/// Handles a run's events as they arrive: folds them into its
+/// [`ClaudeResult`], writes the transcript and streams them to the server.
+struct RunCollector<'a> {
+    options: &'a ClaudeOptions,
+    result: ClaudeResult,
+    /// Batched real-time streaming to the server.
+    streamer: Option<EventStreamer>,
+    transcript: Option<TranscriptLog>,
+    /// Partial-message mode: rebuilds assistant text from `stream_event`
+    /// deltas, skipping messages already captured from a complete assistant
+    /// event.
+    assembler: StreamAssembler,
+    captured_message_ids: HashSet<String>,
+    cost: CostTracker,
+}
 
-    // === Additional Directories ===
-    for dir in &options.add_dirs {
-        cmd.arg("--add-dir").arg(dir);
+impl<'a> RunCollector<'a> {
+    fn new(options: &'a ClaudeOptions) -> Self {
+        let streamer = options.stream_url.as_ref().map(|url| {
+            EventStreamer::new(
+                url.clone(),
+                options.project_path.clone(),
+                options.fork_id.clone(),
+            )
+        });
+
+        // A broken log file is worth a warning, not a failed fork
+        let transcript = options.log_file.as_ref().and_then(|path| {
+            TranscriptLog::open(path, options.log_max_bytes)
+                .map_err(|e| tracing::warn!("Transcript log disabled: {e:#}"))
+                .ok()
+        });
+
+        Self {
+            options,
+            result: ClaudeResult::default(),
+            streamer,
+            transcript,
+            assembler: StreamAssembler::new(),
+            captured_message_ids: HashSet::new(),
+            cost: CostTracker::new(),
+        }
     }
 
-    // === Advanced Options ===
-    if let Some(ref agents) = options.agents {
-        cmd.arg("--agents").arg(agents);
+    /// Capture, log and stream one event, keeping it for graph storage.
+    fn record(&mut self, event: ClaudeEvent) {
+        self.capture(&event);
+        if let Some(ref mut transcript) = self.transcript {
+            transcript.event(&event);
+        }
+        if let Some(ref mut streamer) = self.streamer {
+            streamer.push(event.raw.clone());
+        }
+        self.result.events.push(event);
+    }
+
+    /// Log a line of stderr; it doesn't fail the run.
+    fn stderr(&mut self, line: &str) {
+        tracing::warn!(target: "forky::claude::stderr", "{line}");
+        if let Some(ref mut transcript) = self.transcript {
+            transcript.stderr(line);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(ref mut streamer) = self.streamer {
+            streamer.flush();
+        }
     }
 
-    if let Some(ref mcp_config) = options.mcp_config {
-        cmd.arg("--mcp-config").arg(mcp_config);
+    /// Send any events still buffered and hand back the result.
+    async fn finish(self) -> ClaudeResult {
+        if let Some(streamer) = self.streamer {
+            streamer.finish().await;
+        }
+        self.result
     }
 
-    if let Some(ref settings) = options.settings {
-        cmd.arg("--settings").arg(settings);
+    /// Capture what the result needs from one event.
+    fn capture(&mut self, event: &ClaudeEvent) {
+        // Capture session ID
+        if event.session_id.is_some() && self.result.session_id.is_none() {
+            self.result.session_id.clone_from(&event.session_id);
+        }
+
+        // Capture assistant messages
+        if event.is_assistant() {
+            if self.options.attach {
+                print_attached(event.thinking.as_deref(), event.get_text());
+            }
+            if let Some(text) = event.get_text() {
+                self.result.messages.push(text.to_string());
+                if let Some(ref id) = event.message_id {
+                    self.captured_message_ids.insert(id.clone());
+                }
+            }
+        }
+
+        if let Some(assembled) = self.assembler.push(event) {
+            let captured = assembled
+                .message_id
+                .as_ref()
+                .is_some_and(|id| self.captured_message_ids.contains(id));
+            if !captured {
+                if self.options.attach {
+                    print_attached(assembled.thinking.as_deref(), assembled.get_text());
+                }
+                if let Some(text) = assembled.get_text() {
+                    self.result.messages.push(text.to_string());
+                }
+            }
+        }
+
+        // Capture result
+        // Only a `success` result counts; a missing subtype (older CLIs) is
+        // taken as success too
+        if event.is_result() {
+            self.result.success = matches!(event.subtype.as_deref(), None | Some("success"));
+            self.result.result_subtype.clone_from(&event.subtype);
+            if let Some(text) = event.get_text() {
+                self.result.result = Some(text.to_string());
+            }
+            if event.cost_usd.is_some() {
+                self.result.cost_usd = event.cost_usd;
+            }
+            // Use total_cost_usd if available (more accurate)
+            if event.total_cost_usd.is_some() {
+                self.result.cost_usd = event.total_cost_usd;
+            }
+        }
+
+        // Stop after this event once the run costs too much
+        self.cost.push(event);
+        if self
+            .options
+            .max_cost
+            .is_some_and(|max_cost| self.cost.spent() > max_cost)
+        {
+            self.result.cost_limit_exceeded = true;
+        }
+
+        // Any error fails the run, even if a result follows
+        if let Some(message) = event.error_message() {
+            self.result.errors.push(message);
+        }
     }
+}
 
-    if let Some(max_turns) = options.max_turns {
-        cmd.arg("--max-turns").arg(max_turns.to_string());
+/// Print an assistant message for `--attach`, prefixed like `forky logs`.
+fn print_attached(thinking: Option<&str>, text: Option<&str>) {
+    if let Some(thinking) = thinking {
+        println!("[ASSISTANT THINKING] {thinking}");
+    }
+    if let Some(text) = text {
+        println!("[ASSISTANT] {text}");
     }
+}
 
-    if let Some(ref tools) = options.tools {
-        cmd.arg("--tools").arg(tools);
+/// Rejoins NDJSON events that arrive split across several stdout lines.
+///
+/// A line that looks like the start of a JSON object but doesn't parse is
+/// held back and prepended to the following lines until the whole thing
+/// parses, instead of being silently dropped.
+#[derive(Default)]
+struct LineReassembler {
+    pending: String,
+}
+
+impl LineReassembler {
+    /// Feed one stdout line, returning an event once one is complete.
+    fn push(
+        &mut self,
+        line: &str,
+        parse: impl Fn(&str) -> Option<ClaudeEvent>,
+    ) -> Option<ClaudeEvent> {
+        if self.pending.is_empty() {
+            let event = parse(line);
+            if event.is_none() && line.trim_start().starts_with('{') {
+                self.pending.push_str(line);
+            }
+            return event;
+        }
+
+        self.pending.push_str(line);
+        if let Some(event) = parse(&self.pending) {
+            self.pending.clear();
+            return Some(event);
+        }
+
+        // A complete event after a fragment means the fragment was junk
+        if let Some(event) = parse(line) {
+            self.discard();
+            return Some(event);
+        }
+
+        if self.pending.len() > MAX_PARTIAL_LINE_BYTES {
+            self.discard();
+        }
+        None
     }
 
-    if let Some(ref allowed_tools) = options.allowed_tools {
-        cmd.arg("--allowedTools").arg(allowed_tools);
+    /// Report any fragment still pending when stdout closes.
+    fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            self.discard();
+        }
     }
 
-    if options.include_partial_messages {
-        cmd.arg("--include-partial-messages");
+    fn discard(&mut self) {
+        tracing::warn!("Dropped {} bytes of unparseable output", self.pending.len());
+        self.pending.clear();
     }
+}
+
+/// Resume a session in the provider's interactive UI, attached to this terminal.
+///
+/// Nothing is streamed or stored; the user takes over the conversation and
+/// this returns once they quit.
+pub async fn run_claude_interactive(options: ClaudeOptions) -> Result<()> {
+    let provider = options.provider.provider();
+    let mut cmd = provider.interactive_command(&options);
 
-    // Working directory
     if let Some(ref dir) = options.working_dir {
         cmd.current_dir(dir);
     }
+    apply_env(&mut cmd, &options);
 
-    // Message as print mode
-    cmd.arg("-p").arg(&options.message);
-
-    // Set up stdio
-    cmd.stdout(Stdio::piped());
-    cmd.stderr(Stdio::piped());
-    cmd.stdin(Stdio::null());
+    if options.dry_run {
+        println!("{}", format_command(cmd.as_std()));
+        return Ok(());
+    }
 
-    // Spawn the process
-    let mut child = cmd.spawn().context("Failed to spawn claude CLI")?;
+    tracing::debug!(command = %format_command(cmd.as_std()), "Handing off to interactive session");
+    let status = cmd
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", provider.name()))?;
 
-    let stdout = child.stdout.take().context("Failed to capture stdout")?;
-    let stderr = child.stderr.take().context("Failed to capture stderr")?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {status}", provider.name());
+    }
+    Ok(())
+}
 
-    let mut stdout_reader = BufReader::new(stdout).lines();
-    let mut stderr_reader = BufReader::new(stderr).lines();
+/// Apply `--copy-env` and `--env` to a command.
+fn apply_env(cmd: &mut tokio::process::Command, options: &ClaudeOptions) {
+    if let Some(ref allowlist) = options.copy_env {
+        cmd.env_clear();
+        for (key, value) in std::env::vars_os() {
+            if key.to_str().is_some_and(|key| env_allowed(key, allowlist)) {
+                cmd.env(key, value);
+            }
+        }
+    }
+    for (key, value) in &options.env {
+        cmd.env(key, value);
+    }
+}
 
-    let mut result = ClaudeResult {
-        session_id: None,
-        messages: Vec::new(),
-        result: None,
-        success: false,
-        cost_usd: None,
-        events: Vec::new(),
-    };
+/// Whether `--copy-env` lets `name` through; a trailing `*` matches a prefix.
+fn env_allowed(name: &str, allowlist: &[String]) -> bool {
+    allowlist.iter().any(|pattern| {
+        pattern
+            .strip_suffix('*')
+            .map_or(name == pattern, |prefix| name.starts_with(prefix))
+    })
+}
 
-    // HTTP client for real-time streaming (reuse for efficiency)
-    let http_client = options.stream_url.as_ref().map(|_| reqwest::Client::new());
+/// Render a command as a line that can be pasted into a shell.
+fn format_command(cmd: &std::process::Command) -> String {
+    let mut parts = Vec::new();
+    if let Some(dir) = cmd.get_current_dir() {
+        parts.push(format!("cd {} &&", shell_quote(&dir.to_string_lossy())));
+    }
+    parts.push(shell_quote(&cmd.get_program().to_string_lossy()));
+    parts.extend(
+        cmd.get_args()
+            .map(|arg| shell_quote(&arg.to_string_lossy())),
+    );
+    parts.join(" ")
+}
 
-    // Process stdout (NDJSON events)
-    loop {
-        tokio::select! {
-            line = stdout_reader.next_line() => {
-                match line {
-                    Ok(Some(line)) => {
-                        if let Some(event) = ClaudeEvent::parse(&line) {
-                            // Capture session ID
-                            if event.session_id.is_some() && result.session_id.is_none() {
-                                result.session_id.clone_from(&event.session_id);
-                            }
+/// Quote an argument for a POSIX shell, leaving plain words alone.
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
 
-                            // Capture assistant messages
-                            if event.is_assistant() {
-                                if let Some(text) = event.get_text() {
-                                    result.messages.push(text.to_string());
-                                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::AsyncReadExt;
+
+    /// Answer HTTP posts on a local port, recording each JSON body in the
+    /// order it arrived. Responses are slow, so overlapping posts would show.
+    async fn recording_server() -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/events", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(stream);
+                    loop {
+                        let mut length = 0;
+                        loop {
+                            let mut line = String::new();
+                            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                                return;
                             }
-
-                            // Capture result
-                            if event.is_result() {
-                                result.success = true;
-                                if let Some(text) = event.get_text() {
-                                    result.result = Some(text.to_string());
-                                }
-                                if event.cost_usd.is_some() {
-                                    result.cost_usd = event.cost_usd;
-                                }
-                                // Use total_cost_usd if available (more accurate)
-                                if event.total_cost_usd.is_some() {
-                                    result.cost_usd = event.total_cost_usd;
-                                }
+                            if line == "\r\n" {
+                                break;
                             }
-
-                            // Stream event to server in real-time
-                            if let (Some(client), Some(url)) = (&http_client, &options.stream_url) {
-                                let body = serde_json::json!({
-                                    "project_path": options.project_path,
-                                    "fork_id": options.fork_id,
-                                    "events": [event.raw],
-                                });
-                                // Fire and forget - don't block on response
-                                let client = client.clone();
-                                let url = url.clone();
-                                tokio::spawn(async move {
-                                    let _ = client.post(&url).json(&body).send().await;
-                                });
+                            let lower = line.to_ascii_lowercase();
+                            if let Some(value) = lower.strip_prefix("content-length:") {
+                                length = value.trim().parse().unwrap();
                             }
-
-                            // Store event for graph database
-                            result.events.push(event);
                         }
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).await.unwrap();
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        recorded
+                            .lock()
+                            .unwrap()
+                            .push(serde_json::from_slice(&body).unwrap());
+                        let ok = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+                        reader.get_mut().write_all(ok).await.unwrap();
                     }
-                    Ok(None) => break,
-                    Err(e) => {
-                        eprintln!("Error reading stdout: {e}");
-                        break;
-                    }
-                }
-            }
-            line = stderr_reader.next_line() => {
-                match line {
-                    Ok(Some(line)) => {
-                        // Log stderr but don't fail
-                        eprintln!("[claude stderr] {line}");
-                    }
-                    Ok(None) => {}
-                    Err(e) => {
-                        eprintln!("Error reading stderr: {e}");
-                    }
-                }
+                });
             }
+        });
+        (url, bodies)
+    }
+
+    #[tokio::test]
+    async fn streamer_posts_every_batch_in_order() {
+        let (url, bodies) = recording_server().await;
+        let mut streamer = EventStreamer::new(url, Some("/p".to_string()), Some("f".to_string()));
+        let total = STREAM_BATCH_SIZE * 2 + 1;
+        for i in 0..total {
+            streamer.push(serde_json::json!({ "uuid": i }));
         }
+        streamer.finish().await;
+
+        let bodies = std::mem::take(&mut *bodies.lock().unwrap());
+        assert_eq!(bodies.len(), 3);
+        let uuids: Vec<u64> = bodies
+            .iter()
+            .flat_map(|body| body["events"].as_array().unwrap().clone())
+            .map(|event| event["uuid"].as_u64().unwrap())
+            .collect();
+        assert_eq!(uuids, (0..total as u64).collect::<Vec<_>>());
     }
 
-    // Wait for the process to finish
-    let status = child
-        .wait()
-        .await
-        .context("Failed to wait for claude CLI")?;
-    result.success = status.success() && result.success;
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn max_cost_stops_run_before_result() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A fake CLI that spends $18 on one message and then hangs without
+        // ever sending a result
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("claude");
+        let message = serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "id": "msg_1",
+                "model": "claude-sonnet-4-5-20250929",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "working"}],
+                "usage": {"input_tokens": 1_000_000, "output_tokens": 1_000_000},
+            },
+        });
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho '{message}'\nexec sleep 30\n"),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let options = ClaudeOptions {
+            message: "hello".to_string(),
+            claude_path: Some(script.display().to_string()),
+            max_cost: Some(1.0),
+            ..Default::default()
+        };
+        let result = tokio::time::timeout(Duration::from_secs(10), spawn_claude(options))
+            .await
+            .expect("run should stop once over budget")
+            .unwrap();
+        assert!(result.cost_limit_exceeded);
+        assert!(!result.success);
+    }
 
-    Ok(result)
+    #[test]
+    fn reassembles_split_lines() {
+        let mut reassembler = LineReassembler::default();
+        let event = r#"{"type":"assistant","uuid":"a-1","message":{"role":"assistant","content":[{"type":"text","text":"long"}]}}"#;
+        let (head, tail) = event.split_at(40);
+
+        assert!(reassembler.push(head, ClaudeEvent::parse).is_none());
+        let parsed = reassembler.push(tail, ClaudeEvent::parse).unwrap();
+        assert_eq!(parsed.uuid.as_deref(), Some("a-1"));
+        assert!(reassembler.pending.is_empty());
+
+        // Plain text is not held back
+        assert!(reassembler.push("not json", ClaudeEvent::parse).is_none());
+        assert!(reassembler.pending.is_empty());
+    }
+
+    #[test]
+    fn drops_fragment_before_complete_line() {
+        let mut reassembler = LineReassembler::default();
+        assert!(reassembler
+            .push(r#"{"type":"ass"#, ClaudeEvent::parse)
+            .is_none());
+        let parsed = reassembler
+            .push(r#"{"type":"result","uuid":"r-1"}"#, ClaudeEvent::parse)
+            .unwrap();
+        assert_eq!(parsed.uuid.as_deref(), Some("r-1"));
+        assert!(reassembler.pending.is_empty());
+    }
+
+    #[test]
+    fn shell_quote_args() {
+        assert_eq!(shell_quote("--model"), "--model");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("fix the build"), "'fix the build'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn env_allowlist_patterns() {
+        let allowlist = vec!["PATH".to_string(), "ANTHROPIC_*".to_string()];
+        assert!(env_allowed("PATH", &allowlist));
+        assert!(env_allowed("ANTHROPIC_BASE_URL", &allowlist));
+        assert!(!env_allowed("PATHEXT", &allowlist));
+        assert!(!env_allowed("AWS_SECRET_ACCESS_KEY", &allowlist));
+        assert!(!env_allowed("PATH", &[]));
+    }
+
+    #[test]
+    fn format_command_includes_dir() {
+        let mut cmd = std::process::Command::new("claude");
+        cmd.arg("-p").arg("hello world").current_dir("/tmp/work");
+        assert_eq!(
+            format_command(&cmd),
+            "cd /tmp/work && claude -p 'hello world'"
+        );
+    }
 }
@@ -0,0 +1,152 @@
+//! Plain-text transcript log written alongside each fork run.
+//!
+//! Lines look like `[2025-01-01T12:00:00.000Z] [assistant/assistant] text`
+//! and are written straight to disk, so the log survives a dead server or
+//! a corrupted database.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use super::events::{ClaudeEvent, EventType};
+
+/// Appends timestamped lines to a fork's log file, stopping at a size cap.
+pub struct TranscriptLog {
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    capped: bool,
+}
+
+impl TranscriptLog {
+    /// Open the log at `path`, moving an existing one aside to `<path>.1`.
+    pub fn open(path: &Path, max_bytes: u64) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        if path.exists() {
+            let mut rotated = path.as_os_str().to_owned();
+            rotated.push(".1");
+            std::fs::rename(path, &rotated)
+                .with_context(|| format!("Failed to rotate {}", path.display()))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        Ok(Self {
+            file,
+            written: 0,
+            max_bytes,
+            capped: false,
+        })
+    }
+
+    /// Log the readable parts of an event: thinking, assistant text, results and errors.
+    pub fn event(&mut self, event: &ClaudeEvent) {
+        let tag = event.role.as_deref().map_or_else(
+            || event.type_label().to_string(),
+            |role| format!("{}/{role}", event.type_label()),
+        );
+
+        if let Some(ref thinking) = event.thinking {
+            self.line(&format!("{}/thinking", event.type_label()), thinking);
+        }
+
+        if let Some(message) = event.error_message() {
+            self.line(&tag, &message);
+            return;
+        }
+
+        let logged = matches!(
+            event.event_type,
+            Some(EventType::Assistant | EventType::Result)
+        );
+        if logged {
+            if let Some(text) = event.get_text() {
+                self.line(&tag, text);
+            }
+        }
+    }
+
+    /// Log a line the CLI wrote to stderr.
+    pub fn stderr(&mut self, line: &str) {
+        self.line("stderr", line);
+    }
+
+    /// Write one line, or a truncation notice once the cap is reached.
+    ///
+    /// Write errors are ignored: the log must never fail the fork.
+    fn line(&mut self, tag: &str, text: &str) {
+        if self.capped {
+            return;
+        }
+
+        let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ");
+        let mut line = format!("[{timestamp}] [{tag}] {text}\n");
+        if self.written + line.len() as u64 > self.max_bytes {
+            self.capped = true;
+            line = format!(
+                "[{timestamp}] [forky] log truncated at {} bytes\n",
+                self.max_bytes
+            );
+        }
+
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.written += line.len() as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn logs_events_and_stderr() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("logs").join("fork.log");
+        let mut log = TranscriptLog::open(&path, 1024 * 1024).unwrap();
+
+        let assistant = ClaudeEvent::parse(
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"hello"}]}}"#,
+        )
+        .unwrap();
+        log.event(&assistant);
+        log.stderr("warning: something");
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("[assistant/assistant] hello"));
+        assert!(lines[1].ends_with("[stderr] warning: something"));
+    }
+
+    #[test]
+    fn stops_at_cap_and_rotates() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("fork.log");
+        let mut log = TranscriptLog::open(&path, 100).unwrap();
+        for _ in 0..10 {
+            log.stderr("0123456789");
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.ends_with("log truncated at 100 bytes\n"));
+        assert_eq!(contents.matches("truncated").count(), 1);
+
+        TranscriptLog::open(&path, 100).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("fork.log.1")).unwrap(),
+            contents
+        );
+    }
+}
@@ -1,7 +1,12 @@
 //! CLI argument definitions.
 
+use std::net::IpAddr;
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand, ValueEnum};
 
+use crate::claude::ProviderKind;
+
 /// Forky - Fork Claude sessions to handle side tasks in parallel
 #[derive(Parser, Debug)]
 #[command(name = "forky")]
@@ -12,19 +17,50 @@ pub struct Cli {
     pub message_last: bool,
 
     /// Model to use for Claude (opus, sonnet, haiku).
-    /// Defaults to opus—always use opus unless explicitly told otherwise.
-    #[arg(short, long, default_value = "opus")]
-    pub model: String,
+    /// Defaults to opus (or `default_model` in ~/.forky/config.toml)—always
+    /// use opus unless explicitly told otherwise.
+    #[arg(short, long)]
+    pub model: Option<String>,
+
+    /// AI CLI to run the fork with
+    #[arg(long, value_enum, default_value_t = ProviderKind::Claude)]
+    pub provider: ProviderKind,
+
+    /// Tag the fork for filtering with `list forks --tag` (repeatable)
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
 
     // === Directory / Worktree Options ===
-    /// Run in a git worktree (creates branch forky/<fork-id>)
+    /// Run in a git worktree (creates branch forky/<fork-id>).
+    /// Can be made the default with `worktree = true` in config.toml
     #[arg(long)]
     pub worktree: bool,
 
+    /// Run in the current checkout even if config.toml sets `worktree = true`
+    #[arg(long, conflicts_with_all = ["worktree", "from"])]
+    pub no_worktree: bool,
+
+    /// Branch, tag or commit the worktree starts from (implies --worktree; default HEAD)
+    #[arg(long, value_name = "REF")]
+    pub from: Option<String>,
+
     /// Directory to run the fork in (auto-adds as working directory)
     #[arg(long)]
     pub dir: Option<String>,
 
+    /// Set an environment variable for the fork's claude process (repeatable)
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    pub env: Vec<(String, String)>,
+
+    /// Start the fork from an empty environment plus these variables
+    /// (repeatable; `ANTHROPIC_*` matches a prefix). Overrides `copy_env` in config.toml
+    #[arg(long = "copy-env", value_name = "NAME")]
+    pub copy_env: Vec<String>,
+
+    /// Write the fork's final answer to `<PATH>/<fork-id>.txt` when it finishes
+    #[arg(long, value_name = "PATH")]
+    pub output_dir: Option<PathBuf>,
+
     // === Chrome Browser Options ===
     /// Enable Chrome browser integration
     #[arg(long)]
@@ -39,10 +75,23 @@ pub struct Cli {
     #[arg(long)]
     pub append_system_prompt: Option<String>,
 
+    /// Don't tell the fork to run `forky done` when it finishes
+    #[arg(long)]
+    pub no_callback: bool,
+
     /// Replace the entire system prompt
     #[arg(long, conflicts_with = "append_system_prompt")]
     pub system_prompt: Option<String>,
 
+    // === Execution Limits ===
+    /// Kill the fork if it runs longer than this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Kill the fork once it has cost more than this many dollars
+    #[arg(long, value_name = "USD")]
+    pub max_cost: Option<f64>,
+
     // === Advanced Options (wired up but not prominently exposed) ===
     /// Custom subagents as JSON
     #[arg(long, hide = true)]
@@ -72,6 +121,26 @@ pub struct Cli {
     #[arg(long, hide = true)]
     pub include_partial_messages: bool,
 
+    /// Print the claude command instead of running it (the fork is still recorded, as cancelled)
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print the fork's thinking and replies live instead of only at the end
+    #[arg(long)]
+    pub attach: bool,
+
+    /// Send the message even if it looks like a forky command
+    #[arg(long)]
+    pub force: bool,
+
+    /// Print list output as JSON instead of a table
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Diagnostic log filter, e.g. `debug` or `forky::server=trace` (overrides `RUST_LOG`)
+    #[arg(long, global = true, value_name = "LEVEL")]
+    pub log_level: Option<String>,
+
     /// Message to send (used with default fork behavior)
     #[arg(trailing_var_arg = true)]
     pub message: Vec<String>,
@@ -81,6 +150,14 @@ pub struct Cli {
     pub command: Option<Commands>,
 }
 
+/// Parse a `KEY=VALUE` pair for `--env`.
+fn parse_env_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{s}`")),
+    }
+}
+
 /// Available subcommands
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -116,6 +193,20 @@ pub enum Commands {
         /// Session ID to resume
         id: String,
 
+        /// Open the session in Claude's interactive UI instead of running one turn
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Message to send (optional with --interactive)
+        #[arg(trailing_var_arg = true)]
+        message: Vec<String>,
+    },
+
+    /// Send a follow-up message to a finished fork, continuing its session
+    Say {
+        /// Fork ID to send the message to
+        fork_id: String,
+
         /// Message to send
         #[arg(trailing_var_arg = true)]
         message: Vec<String>,
@@ -126,6 +217,10 @@ pub enum Commands {
         /// Entity type to list
         #[arg(value_enum)]
         entity: ListEntity,
+
+        /// Only forks carrying this tag (repeatable; all must match)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
     },
 
     /// View messages for a fork
@@ -134,6 +229,107 @@ pub enum Commands {
         fork_id: String,
     },
 
+    /// Show a fork's events, optionally following them live
+    Logs {
+        /// Fork ID to show events for
+        fork_id: String,
+
+        /// Keep streaming new events until the fork finishes
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of stored events to show first
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+    },
+
+    /// Block until forks finish; exits non-zero if any failed
+    Wait {
+        /// Fork IDs to wait for
+        #[arg(required_unless_present = "all")]
+        fork_ids: Vec<String>,
+
+        /// Wait for every running fork in the current project
+        #[arg(long, conflicts_with = "fork_ids")]
+        all: bool,
+
+        /// Give up after this many seconds
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+    },
+
+    /// Show the git changes made in a worktree fork
+    Diff {
+        /// Fork ID (or its 8-character short ID)
+        fork_id: String,
+    },
+
+    /// Merge a worktree fork's branch into the current branch
+    Merge {
+        /// Fork ID (or its 8-character short ID)
+        fork_id: String,
+
+        /// Squash the fork's commits into staged changes instead of merging
+        #[arg(long)]
+        squash: bool,
+
+        /// Merge even if the fork has not completed
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove worktrees and branches of finished forks
+    Prune {
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Only prune forks created more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+
+    /// Shrink this project's database file after forks have been deleted
+    Compact,
+
+    /// Open the dashboard in a browser with a fork selected
+    Open {
+        /// Fork ID to show
+        fork_id: String,
+    },
+
+    /// Export a fork's conversation as JSON or Markdown
+    Export {
+        /// Fork ID to export
+        fork_id: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+    },
+
+    /// Import a Claude NDJSON transcript (.jsonl) as a fork
+    Import {
+        /// Transcript file to import
+        file: PathBuf,
+
+        /// Fork ID to store the events under (generated if omitted)
+        #[arg(long)]
+        fork_id: Option<String>,
+    },
+
+    /// Re-run a failed fork with its original message and settings
+    Retry {
+        /// Fork ID to retry
+        fork_id: String,
+    },
+
+    /// Remove a fork and all of its stored events
+    Rm {
+        /// Fork ID to remove
+        fork_id: String,
+    },
+
     /// Mark a fork as read
     Read {
         /// Fork ID to mark as read (or --all for all forks)
@@ -157,34 +353,99 @@ pub enum Commands {
         /// Fork ID that completed
         fork_id: String,
 
+        /// Show a desktop notification (or set `notify = true` in config.toml)
+        #[arg(long)]
+        notify: bool,
+
         /// Summary of what was accomplished
         #[arg(trailing_var_arg = true)]
         summary: Vec<String>,
     },
 
-    /// Start the streaming server for fork observability
+    /// Start the streaming server for fork observability (in the foreground)
     Serve {
-        /// Port to listen on
-        #[arg(short, long, default_value = "58231")]
-        port: u16,
+        /// Port to listen on (default: `server_port` from config.toml, or 58231)
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// Address to bind (use 0.0.0.0 to expose the dashboard on the network)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: IpAddr,
+
+        /// Require this bearer token on API requests
+        /// (default: `FORKY_AUTH_TOKEN` or `auth_token` in config.toml)
+        #[arg(long, value_name = "TOKEN")]
+        auth_token: Option<String>,
 
         /// Open browser automatically
         #[arg(long)]
         open: bool,
     },
 
+    /// Check on or stop the background server
+    Server {
+        #[command(subcommand)]
+        action: ServerAction,
+    },
+
+    /// Show cost, turn and duration totals for the current project
+    Stats,
+
+    /// Show a fork's cost per turn and its token usage per model
+    Cost {
+        /// Fork ID to break down
+        fork_id: String,
+    },
+
+    /// Summarize forks, today's cost and whether the server is up
+    Status,
+
+    /// Show forks that finished (via `forky done`) since notifications were last cleared
+    Notifications {
+        /// Remove the notifications after showing them
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Search event content across all forks
+    Search {
+        /// Text to search for (case-insensitive)
+        query: String,
+
+        /// Maximum number of matches to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
     /// Debug: show events stored in the graph database
     Events {
-        /// Session ID to filter by (optional)
+        /// Only events whose session ID starts with this
         #[arg(short, long)]
         session: Option<String>,
 
+        /// Match --session exactly instead of as a prefix
+        #[arg(long, requires = "session")]
+        exact: bool,
+
+        /// Only events created at or after this RFC3339 time (e.g. 2025-01-31T09:00:00Z)
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+
         /// Maximum number of events to show
         #[arg(short, long, default_value = "20")]
         limit: usize,
     },
 }
 
+/// Ways to manage the background server
+#[derive(Subcommand, Debug)]
+pub enum ServerAction {
+    /// Show the server's port, PID and whether it is answering
+    Status,
+    /// Stop the server and remove its PID and port files
+    Stop,
+}
+
 /// Entity types that can be listed
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum ListEntity {
@@ -195,3 +456,12 @@ pub enum ListEntity {
     /// List jobs
     Jobs,
 }
+
+/// Formats a fork can be exported in
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// JSON array of stored events
+    Json,
+    /// Readable Markdown transcript
+    Markdown,
+}
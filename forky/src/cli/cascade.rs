@@ -0,0 +1,167 @@
+//! Cascade prevention.
+//!
+//! A fork handed a message like "spawn --model haiku -m hello" might run it
+//! and start another fork, which does the same, creating sessions forever.
+//! Messages are rejected only when they follow forky's own command grammar,
+//! so ordinary tasks that happen to start with "fork" or mention a flag like
+//! `--release` still go through. `forky --force` skips the check.
+
+use anyhow::{bail, Result};
+use clap::CommandFactory;
+
+use super::args::Cli;
+use crate::claude::truncate_chars;
+
+/// Validate that a message doesn't look like a forky command.
+///
+/// Rejected messages either contain the forky binary path, invoke `forky`
+/// with a subcommand or flag, or start with a session-starting subcommand
+/// (spawn, fork, resume, ...) followed by one of forky's own flags.
+pub fn validate_message_not_forky_command(message: &str) -> Result<()> {
+    let preview = truncate_chars(message, 50);
+
+    // The installed binary path (/Users/xxx/.forky/bin/forky, ~/.forky/bin/forky)
+    let msg_lower = message.to_lowercase();
+    if msg_lower.contains(".forky/bin/forky") || msg_lower.contains(".forky\\bin\\forky") {
+        bail!(
+            "CASCADE PREVENTION: Message contains forky binary path: '{preview}'\n\
+             This would cause infinite session creation.\n\
+             If this is a legitimate message, pass --force to send it anyway."
+        );
+    }
+
+    let words: Vec<&str> = message.split_whitespace().collect();
+    let Some((&first, rest)) = words.split_first() else {
+        return Ok(());
+    };
+    let grammar = Grammar::new();
+
+    let looks_like_command = if first.eq_ignore_ascii_case("forky") {
+        rest.first()
+            .is_some_and(|word| grammar.is_subcommand(word) || grammar.is_flag(word))
+    } else {
+        grammar.is_session_subcommand(first) && rest.iter().any(|word| grammar.is_flag(word))
+    };
+
+    if looks_like_command {
+        bail!(
+            "CASCADE PREVENTION: Message looks like a forky command: '{preview}'\n\
+             Did you mean to run: forky {message} ?\n\
+             This safeguard prevents infinite session creation.\n\
+             If this is a legitimate message, pass --force to send it anyway."
+        );
+    }
+
+    Ok(())
+}
+
+/// Subcommand and flag names taken from the CLI definition.
+struct Grammar {
+    subcommands: Vec<String>,
+    /// Subcommands that start a session from a message.
+    session_subcommands: Vec<String>,
+    long_flags: Vec<String>,
+    short_flags: Vec<char>,
+}
+
+impl Grammar {
+    fn new() -> Self {
+        let cli = Cli::command();
+
+        let mut subcommands = Vec::new();
+        let mut session_subcommands = Vec::new();
+        for sub in cli.get_subcommands() {
+            let names: Vec<String> = std::iter::once(sub.get_name())
+                .chain(sub.get_all_aliases())
+                .map(String::from)
+                .collect();
+            if sub.get_arguments().any(|arg| arg.get_id() == "message") {
+                session_subcommands.extend(names.iter().cloned());
+            }
+            subcommands.extend(names);
+        }
+
+        // Only the fork options; output flags like --json are common in tasks
+        let fork_args = cli.get_arguments().filter(|arg| !arg.is_global_set());
+        let mut long_flags = Vec::new();
+        let mut short_flags = Vec::new();
+        for arg in fork_args {
+            if let Some(long) = arg.get_long() {
+                long_flags.push(long.to_string());
+            }
+            short_flags.extend(arg.get_short());
+        }
+
+        Self {
+            subcommands,
+            session_subcommands,
+            long_flags,
+            short_flags,
+        }
+    }
+
+    fn is_subcommand(&self, word: &str) -> bool {
+        self.subcommands
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(word))
+    }
+
+    fn is_session_subcommand(&self, word: &str) -> bool {
+        self.session_subcommands
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(word))
+    }
+
+    /// Whether `word` is one of forky's flags (`--model`, `--model=x`, `-m`).
+    fn is_flag(&self, word: &str) -> bool {
+        if let Some(long) = word.strip_prefix("--") {
+            let name = long.split('=').next().unwrap_or_default();
+            return self.long_flags.iter().any(|flag| flag == name);
+        }
+        let mut chars = word.strip_prefix('-').unwrap_or_default().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.short_flags.contains(&c),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_forky_commands() {
+        for message in [
+            "spawn --model haiku -m hello",
+            "fork-me --worktree fix the build",
+            "resume abc --model=sonnet keep going",
+            "forky spawn do the thing",
+            "forky --worktree fix it",
+            "run ~/.forky/bin/forky done 123 finished",
+        ] {
+            assert!(
+                validate_message_not_forky_command(message).is_err(),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn allows_ordinary_tasks() {
+        for message in [
+            "fork the repo and run the tests with --release",
+            "spawn a background thread per connection",
+            "new feature: add a --json flag to the exporter",
+            "resume the refactor, then cargo build --release",
+            "forky is slow to start, profile it",
+            "list the TODOs in src/",
+            "",
+        ] {
+            assert!(
+                validate_message_not_forky_command(message).is_ok(),
+                "{message}"
+            );
+        }
+    }
+}
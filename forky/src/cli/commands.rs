@@ -2,91 +2,36 @@
 //!
 //! This is a thin client - all database operations go through the server.
 
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::claude::{spawn_claude, ClaudeEvent, ClaudeOptions};
+use crate::claude::{
+    run_claude_interactive, shell_quote, spawn_claude, truncate_chars, ClaudeEvent, ClaudeOptions,
+    ClaudeResult, ProviderKind,
+};
+use crate::config::{http_client, Config};
+use crate::db::ForkStatus;
+use crate::notifications::{self, Notification};
 use crate::server;
 use crate::session::detect_session_id;
 
-use super::args::{Cli, Commands, ListEntity};
+use super::args::{Cli, Commands, ExportFormat, ListEntity, ServerAction};
+use super::cascade::validate_message_not_forky_command;
 
 /// Generate a UUIDv7 (time-ordered, globally unique).
 fn generate_uuid() -> String {
     Uuid::now_v7().to_string()
 }
 
-/// Patterns that indicate a message is likely a forky command being re-executed.
-/// This prevents cascade bugs where forked sessions re-run forky commands.
-/// NOTE: All patterns must be lowercase since we compare against lowercased input.
-const FORKY_COMMAND_PATTERNS: &[&str] = &[
-    "spawn ",
-    "spawn\t",
-    "fork ",
-    "fork\t",
-    "forky ",
-    "forky\t",
-    ".forky/bin/forky",  // Matches both /Users/xxx/.forky/bin/forky and ~/.forky/bin/forky
-    "fork-me ",
-    "fork-me\t",
-];
-
-/// Validate that a message doesn't look like a forky command.
-/// This prevents cascade bugs where a forked session receives a message like
-/// "spawn --model haiku -m hello" and re-executes it, creating infinite sessions.
-fn validate_message_not_forky_command(message: &str) -> Result<()> {
-    let msg_lower = message.to_lowercase();
-    let msg_trimmed = msg_lower.trim();
-
-    // Check for patterns that indicate this is a forky command
-    for pattern in FORKY_COMMAND_PATTERNS {
-        if msg_trimmed.starts_with(pattern) {
-            bail!(
-                "CASCADE PREVENTION: Message looks like a forky command: '{}'\n\
-                 This would cause infinite session creation.\n\
-                 If you meant to send this as a task, wrap it differently.\n\
-                 If this is a legitimate message, please rephrase it.",
-                &message[..message.len().min(50)]
-            );
-        }
-    }
-
-    // Check for forky binary path anywhere in the message (catches full paths)
-    if msg_lower.contains(".forky/bin/forky") || msg_lower.contains(".forky\\bin\\forky") {
-        bail!(
-            "CASCADE PREVENTION: Message contains forky binary path: '{}'\n\
-             This would cause infinite session creation.\n\
-             If you meant to send this as a task, wrap it differently.",
-            &message[..message.len().min(50)]
-        );
-    }
-
-    // Also check if message starts with common forky subcommands without the "forky" prefix
-    // These could be captured as messages if typed wrong
-    let dangerous_starts = ["spawn", "fork", "resume", "new", "fork-me"];
-    for cmd in dangerous_starts {
-        if msg_trimmed == cmd || msg_trimmed.starts_with(&format!("{cmd} ")) {
-            // Check if this looks like a command with flags (has --)
-            if message.contains("--") || message.contains(" -m ") || message.contains(" -l") {
-                bail!(
-                    "CASCADE PREVENTION: Message looks like a forky command: '{}'\n\
-                     Did you mean to run: forky {} ?\n\
-                     This safeguard prevents infinite session creation.",
-                    &message[..message.len().min(50)],
-                    message
-                );
-            }
-        }
-    }
-
-    Ok(())
-}
-
 /// Get the current project path.
 fn get_project_path() -> Result<PathBuf> {
     let mut current = std::env::current_dir().context("Failed to get current directory")?;
@@ -106,10 +51,59 @@ fn get_project_path() -> Result<PathBuf> {
 struct WorktreeInfo {
     path: PathBuf,
     branch: String,
+    /// Commit the branch started from.
+    base: String,
 }
 
-/// Set up a git worktree for the fork.
-fn setup_worktree(fork_id: &str) -> Result<WorktreeInfo> {
+/// Check that `git_ref` names a commit, returning a readable error if not.
+fn verify_ref(git_ref: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{git_ref}^{{commit}}"))
+        .output()
+        .context("Failed to run git rev-parse")?;
+
+    if !output.status.success() {
+        bail!("Unknown git ref '{git_ref}': no branch, tag or commit by that name");
+    }
+    Ok(())
+}
+
+/// Get the directory new fork worktrees go in.
+///
+/// `FORKY_WORKTREE_ROOT` or `worktree_root` in config.toml, defaulting to
+/// `~/.forky/worktrees`.
+fn worktrees_dir() -> Result<PathBuf> {
+    if let Some(root) = crate::config::worktree_root() {
+        if !root.is_absolute() {
+            bail!("Worktree root must be an absolute path: {}", root.display());
+        }
+        return Ok(root);
+    }
+
+    Ok(dirs::home_dir()
+        .context("Could not find home directory")?
+        .join(".forky")
+        .join("worktrees"))
+}
+
+/// Get the transcript log for a fork (`~/.forky/logs/<fork_id>.log`).
+fn log_path(fork_id: &str) -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .context("Could not find home directory")?
+        .join(".forky")
+        .join("logs")
+        .join(format!("{fork_id}.log")))
+}
+
+/// Get the default worktree directory for a fork (`<worktree root>/<short_id>`).
+fn worktree_path(fork_id: &str) -> Result<PathBuf> {
+    let short_id = &fork_id[..8.min(fork_id.len())];
+    Ok(worktrees_dir()?.join(short_id))
+}
+
+/// Get the root of the git repository containing the current directory.
+fn repo_root() -> Result<PathBuf> {
     let output = Command::new("git")
         .args(["rev-parse", "--show-toplevel"])
         .output()
@@ -122,18 +116,28 @@ fn setup_worktree(fork_id: &str) -> Result<WorktreeInfo> {
         );
     }
 
-    let repo_root = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(PathBuf::from(root))
+}
 
-    let worktrees_dir = dirs::home_dir()
-        .context("Could not find home directory")?
-        .join(".forky")
-        .join("worktrees");
+/// Set up a git worktree for the fork.
+fn setup_worktree(fork_id: &str, from: Option<&str>) -> Result<WorktreeInfo> {
+    let repo_root = repo_root()?;
+
+    let worktrees_dir = worktrees_dir()?;
     std::fs::create_dir_all(&worktrees_dir)
         .with_context(|| format!("Failed to create {}", worktrees_dir.display()))?;
 
+    // Fail before touching git if the root can't actually hold a worktree
+    let probe = worktrees_dir.join(".forky-write-test");
+    std::fs::write(&probe, b"")
+        .with_context(|| format!("Worktree root {} is not writable", worktrees_dir.display()))?;
+    let _ = std::fs::remove_file(&probe);
+
+    let worktree_path = worktree_path(fork_id)?;
+
     let short_id = &fork_id[..8.min(fork_id.len())];
     let branch_name = format!("forky/{short_id}");
-    let worktree_path = worktrees_dir.join(short_id);
 
     if worktree_path.exists() {
         let _ = Command::new("git")
@@ -147,11 +151,14 @@ fn setup_worktree(fork_id: &str) -> Result<WorktreeInfo> {
             .output();
     }
 
-    let output = Command::new("git")
+    let mut branch = Command::new("git");
+    branch
         .current_dir(&repo_root)
-        .args(["branch", &branch_name])
-        .output()
-        .context("Failed to create git branch")?;
+        .args(["branch", &branch_name]);
+    if let Some(from) = from {
+        branch.arg(from);
+    }
+    let output = branch.output().context("Failed to create git branch")?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -176,16 +183,70 @@ fn setup_worktree(fork_id: &str) -> Result<WorktreeInfo> {
         );
     }
 
+    let output = Command::new("git")
+        .current_dir(&worktree_path)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .context("Failed to run git rev-parse")?;
+    let base = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
     Ok(WorktreeInfo {
         path: worktree_path,
         branch: branch_name,
+        base,
     })
 }
 
 // === HTTP Client for Server Communication ===
 
+/// Attempts made for each request before giving up.
+const HTTP_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry; doubled after each failed attempt.
+const HTTP_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Send a request, retrying connection errors and 5xx responses with backoff.
+///
+/// This absorbs a server that is still starting up or mid-restart. 4xx
+/// responses come straight back: sending the same request again won't help.
+async fn send_with_retry(request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let mut delay = HTTP_RETRY_DELAY;
+    for _ in 1..HTTP_ATTEMPTS {
+        // Streaming bodies can't be cloned, so they only get one shot
+        let Some(attempt) = request.try_clone() else {
+            break;
+        };
+        match attempt.send().await {
+            Ok(resp) if resp.status().is_server_error() => {
+                tracing::debug!(url = %resp.url(), status = %resp.status(), "Retrying request");
+            }
+            Err(e) if e.is_connect() || e.is_timeout() => {
+                tracing::debug!("Retrying request: {e}");
+            }
+            result => return result,
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+    request.send().await
+}
+
+/// Error for an unsuccessful response, with the message from its JSON body.
+async fn server_error(resp: reqwest::Response) -> anyhow::Error {
+    let status = resp.status();
+    let message = resp
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|body| body.get("error")?.as_str().map(String::from));
+    message.map_or_else(
+        || anyhow!("Server returned {status}"),
+        |message| anyhow!("Server returned {status}: {message}"),
+    )
+}
+
 /// Fork summary from server.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ForkSummary {
     project_path: String,
     fork_id: String,
@@ -193,8 +254,48 @@ struct ForkSummary {
     session_id: Option<String>,
     parent_session_id: Option<String>,
     status: String,
+    reason: Option<String>,
     event_count: usize,
     created_at: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    worktree: bool,
+    #[serde(default)]
+    worktree_path: Option<String>,
+    #[serde(default)]
+    worktree_branch: Option<String>,
+    #[serde(default)]
+    worktree_base: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl ForkSummary {
+    /// The fork's worktree directory and branch.
+    ///
+    /// Uses the location recorded at launch, falling back to the default
+    /// layout for forks created before it was recorded.
+    fn worktree(&self) -> Result<(PathBuf, String)> {
+        let path = match self.worktree_path {
+            Some(ref path) => PathBuf::from(path),
+            None => worktree_path(&self.fork_id)?,
+        };
+        let branch = self.worktree_branch.clone().unwrap_or_else(|| {
+            let short_id = &self.fork_id[..8.min(self.fork_id.len())];
+            format!("forky/{short_id}")
+        });
+        Ok((path, branch))
+    }
+
+    /// Whether the fork has reached a terminal status.
+    fn is_finished(&self) -> bool {
+        self.status
+            .parse::<ForkStatus>()
+            .is_ok_and(ForkStatus::is_finished)
+    }
 }
 
 /// Response from creating a fork.
@@ -205,627 +306,2942 @@ struct CreateForkResponse {
     success: bool,
 }
 
+/// Launch parameters recorded with a new fork so it can be retried.
+#[derive(Debug, Default)]
+struct ForkLaunch<'a> {
+    description: Option<&'a str>,
+    model: Option<&'a str>,
+    worktree: bool,
+    worktree_path: Option<&'a str>,
+    worktree_branch: Option<&'a str>,
+    worktree_base: Option<&'a str>,
+    ai_provider: Option<&'a str>,
+    retry_of: Option<&'a str>,
+    tags: &'a [String],
+}
+
 /// Create a fork via the server. Returns the generated fork name.
 async fn create_fork_on_server(
     port: u16,
     project_path: &str,
     fork_id: &str,
     parent_session_id: Option<&str>,
+    launch: &ForkLaunch<'_>,
 ) -> Result<String> {
     let url = format!("http://127.0.0.1:{port}/api/forks");
     let body = serde_json::json!({
         "project_path": project_path,
         "fork_id": fork_id,
         "parent_session_id": parent_session_id,
+        "job_description": launch.description,
+        "model": launch.model,
+        "worktree": launch.worktree,
+        "worktree_path": launch.worktree_path,
+        "worktree_branch": launch.worktree_branch,
+        "worktree_base": launch.worktree_base,
+        "ai_provider": launch.ai_provider,
+        "retry_of": launch.retry_of,
+        "tags": launch.tags,
     });
 
-    let resp = reqwest::Client::new()
-        .post(&url)
-        .json(&body)
-        .send()
+    let resp = send_with_retry(http_client().post(&url).json(&body))
         .await
         .context("Failed to create fork on server")?;
 
     if !resp.status().is_success() {
-        bail!("Server returned {}", resp.status());
+        return Err(server_error(resp).await);
     }
 
     let response: CreateForkResponse = resp.json().await.context("Failed to parse response")?;
     Ok(response.fork_name)
 }
 
+/// Details sent with a fork status change.
+#[derive(Debug, Default)]
+struct ForkUpdate<'a> {
+    session_id: Option<&'a str>,
+    reason: Option<&'a str>,
+    result_subtype: Option<&'a str>,
+    model: Option<&'a str>,
+    summary: Option<&'a str>,
+}
+
 /// Update fork status via the server.
 async fn update_fork_status_on_server(
     port: u16,
     project_path: &str,
     fork_id: &str,
-    status: &str,
-    session_id: Option<&str>,
+    status: ForkStatus,
+    update: &ForkUpdate<'_>,
 ) -> Result<()> {
     let url = format!("http://127.0.0.1:{port}/api/forks/{fork_id}");
     let body = serde_json::json!({
         "project_path": project_path,
         "status": status,
-        "session_id": session_id,
+        "session_id": update.session_id,
+        "reason": update.reason,
+        "result_subtype": update.result_subtype,
+        "model": update.model,
+        "summary": update.summary,
     });
 
-    let resp = reqwest::Client::new()
-        .patch(&url)
-        .json(&body)
-        .send()
+    let resp = send_with_retry(http_client().patch(&url).json(&body))
         .await
         .context("Failed to update fork on server")?;
 
     if !resp.status().is_success() {
-        bail!("Server returned {}", resp.status());
+        return Err(server_error(resp).await);
     }
 
     Ok(())
 }
 
-/// Get forks from the server.
-async fn get_forks_from_server(port: u16, project_path: Option<&str>) -> Result<Vec<ForkSummary>> {
-    let mut url = format!("http://127.0.0.1:{port}/api/forks");
+/// Get forks from the server, keeping only those carrying every tag in `tags`.
+async fn get_forks_from_server(
+    port: u16,
+    project_path: Option<&str>,
+    tags: &[String],
+) -> Result<Vec<ForkSummary>> {
+    let mut query = Vec::new();
     if let Some(p) = project_path {
-        url = format!("{url}?project_path={}", urlencoding::encode(p));
+        query.push(format!("project_path={}", urlencoding::encode(p)));
+    }
+    if !tags.is_empty() {
+        query.push(format!("tag={}", urlencoding::encode(&tags.join(","))));
+    }
+    let mut url = format!("http://127.0.0.1:{port}/api/forks");
+    if !query.is_empty() {
+        url = format!("{url}?{}", query.join("&"));
     }
 
-    let resp = reqwest::Client::new()
-        .get(&url)
-        .send()
+    let resp = send_with_retry(http_client().get(&url))
         .await
         .context("Failed to get forks from server")?;
 
     if !resp.status().is_success() {
-        bail!("Server returned {}", resp.status());
+        return Err(server_error(resp).await);
     }
 
     let forks: Vec<ForkSummary> = resp.json().await.context("Failed to parse forks")?;
     Ok(forks)
 }
 
-/// Send events to the server for storage.
-async fn send_events_to_server(
+/// Get a single fork from the server.
+async fn get_fork_from_server(
     port: u16,
     project_path: &str,
-    events: &[ClaudeEvent],
-    fork_id: Option<&str>,
-) -> Result<()> {
-    if events.is_empty() {
-        return Ok(());
-    }
-
-    let url = format!("http://127.0.0.1:{port}/api/events");
-    let events_json: Vec<_> = events.iter().map(|e| &e.raw).collect();
-
-    let body = serde_json::json!({
-        "project_path": project_path,
-        "fork_id": fork_id,
-        "events": events_json,
-    });
+    fork_id: &str,
+) -> Result<Option<ForkSummary>> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/forks/{fork_id}?project_path={}",
+        urlencoding::encode(project_path)
+    );
 
-    let resp = reqwest::Client::new()
-        .post(&url)
-        .json(&body)
-        .send()
+    let resp = send_with_retry(http_client().get(&url))
         .await
-        .context("Failed to send events to server")?;
+        .context("Failed to get fork from server")?;
 
     if !resp.status().is_success() {
-        bail!("Server returned {}", resp.status());
+        return Err(server_error(resp).await);
     }
 
-    Ok(())
+    let fork: Option<ForkSummary> = resp.json().await.context("Failed to parse fork")?;
+    Ok(fork)
 }
 
-/// Get events from the server.
-#[derive(Debug, Deserialize)]
-struct StoredEvent {
-    fork_id: Option<String>,
-    uuid: Option<String>,
-    session_id: Option<String>,
-    event_type: String,
-    message: Option<String>,
-    thinking: Option<String>,
-    role: Option<String>,
+/// Turn what was typed on the command line into a fork ID.
+///
+/// Takes a full fork ID, a unique prefix of one, or a unique prefix of a
+/// fork's name (ignoring case), so forks can be addressed as `gandalf`.
+async fn resolve_fork_id(port: u16, project_path: &str, reference: &str) -> Result<String> {
+    if Uuid::parse_str(reference).is_ok() {
+        return Ok(reference.to_string());
+    }
+    // The server expands a unique ID prefix itself
+    if let Some(fork) = get_fork_from_server(port, project_path, reference).await? {
+        return Ok(fork.fork_id);
+    }
+    let forks = get_forks_from_server(port, Some(project_path), &[]).await?;
+    match_fork(&forks, reference).map(|fork| fork.fork_id.clone())
 }
 
-async fn get_events_from_server(
-    port: u16,
-    project_path: &str,
-    fork_id: Option<&str>,
-    limit: usize,
-) -> Result<Vec<StoredEvent>> {
-    let mut url = format!(
-        "http://127.0.0.1:{port}/api/events?project_path={}&limit={limit}",
+/// Find the one fork `reference` points to, by ID then by name.
+fn match_fork<'a>(forks: &'a [ForkSummary], reference: &str) -> Result<&'a ForkSummary> {
+    if let Some(fork) = forks.iter().find(|fork| fork.fork_id == reference) {
+        return Ok(fork);
+    }
+
+    let needle = reference.to_lowercase();
+    let name = |fork: &ForkSummary| fork.fork_name.as_deref().unwrap_or_default().to_lowercase();
+    let mut candidates: Vec<_> = forks
+        .iter()
+        .filter(|fork| fork.fork_id.starts_with(reference))
+        .collect();
+    if candidates.is_empty() {
+        candidates = forks
+            .iter()
+            .filter(|fork| name(fork).starts_with(&needle))
+            .collect();
+    }
+    // A name typed in full wins over longer names that start with it
+    if candidates.len() > 1 {
+        let exact: Vec<_> = candidates
+            .iter()
+            .filter(|fork| name(fork) == needle)
+            .collect();
+        if let [fork] = exact[..] {
+            return Ok(fork);
+        }
+    }
+
+    match candidates[..] {
+        [fork] => Ok(fork),
+        [] => bail!("Fork not found: {reference}"),
+        _ => {
+            let listed: Vec<_> = candidates
+                .iter()
+                .map(|fork| {
+                    format!(
+                        "  {} ({}, {})",
+                        fork.fork_name.as_deref().unwrap_or("-"),
+                        &fork.fork_id[..8.min(fork.fork_id.len())],
+                        fork.status
+                    )
+                })
+                .collect();
+            bail!(
+                "`{reference}` matches {} forks; use more of the name or ID:\n{}",
+                candidates.len(),
+                listed.join("\n")
+            )
+        }
+    }
+}
+
+/// Delete a fork via the server. Returns the number of events removed.
+async fn delete_fork_on_server(port: u16, project_path: &str, fork_id: &str) -> Result<usize> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/forks/{fork_id}?project_path={}",
         urlencoding::encode(project_path)
     );
-    if let Some(fid) = fork_id {
-        url = format!("{url}&fork_id={fid}");
-    }
 
-    let resp = reqwest::Client::new()
-        .get(&url)
-        .send()
+    let resp = send_with_retry(http_client().delete(&url))
         .await
-        .context("Failed to get events from server")?;
+        .context("Failed to delete fork on server")?;
 
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        bail!("Fork {fork_id} not found");
+    }
     if !resp.status().is_success() {
-        bail!("Server returned {}", resp.status());
+        return Err(server_error(resp).await);
     }
 
-    let events: Vec<StoredEvent> = resp.json().await.context("Failed to parse events")?;
-    Ok(events)
+    let body: serde_json::Value = resp.json().await.context("Failed to parse response")?;
+    let events_deleted = body
+        .get("events_deleted")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0);
+    Ok(usize::try_from(events_deleted).unwrap_or(usize::MAX))
 }
 
-// === CLI Options ===
+/// Mark a fork as read via the server.
+async fn mark_fork_read_on_server(port: u16, project_path: &str, fork_id: &str) -> Result<()> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/forks/{fork_id}/read?project_path={}",
+        urlencoding::encode(project_path)
+    );
 
-#[derive(Debug, Clone, Default)]
-pub struct ForkOptions {
-    pub model: Option<String>,
-    pub worktree: bool,
-    pub dir: Option<String>,
-    pub chrome: bool,
-    pub no_chrome: bool,
-    pub append_system_prompt: Option<String>,
-    pub system_prompt: Option<String>,
-    pub agents: Option<String>,
-    pub mcp_config: Option<String>,
-    pub settings: Option<String>,
-    pub max_turns: Option<u32>,
-    pub tools: Option<String>,
-    pub allowed_tools: Option<String>,
-    pub include_partial_messages: bool,
-}
+    let resp = send_with_retry(http_client().post(&url))
+        .await
+        .context("Failed to mark fork read on server")?;
 
-impl From<&Cli> for ForkOptions {
-    fn from(cli: &Cli) -> Self {
-        Self {
-            model: Some(cli.model.clone()),
-            worktree: cli.worktree,
-            dir: cli.dir.clone(),
-            chrome: cli.chrome,
-            no_chrome: cli.no_chrome,
-            append_system_prompt: cli.append_system_prompt.clone(),
-            system_prompt: cli.system_prompt.clone(),
-            agents: cli.agents.clone(),
-            mcp_config: cli.mcp_config.clone(),
-            settings: cli.settings.clone(),
-            max_turns: cli.max_turns,
-            tools: cli.tools.clone(),
-            allowed_tools: cli.allowed_tools.clone(),
-            include_partial_messages: cli.include_partial_messages,
-        }
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        bail!("Fork {fork_id} not found");
+    }
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
     }
+
+    Ok(())
 }
 
-// === Command Execution ===
+/// Mark every fork in the project as read. Returns how many were unread.
+async fn mark_all_forks_read_on_server(port: u16, project_path: &str) -> Result<usize> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/forks/read-all?project_path={}",
+        urlencoding::encode(project_path)
+    );
 
-pub async fn execute(cli: Cli) -> Result<()> {
-    let opts = ForkOptions::from(&cli);
+    let resp = send_with_retry(http_client().post(&url))
+        .await
+        .context("Failed to mark forks read on server")?;
 
-    // Handle -l flag (message last fork)
-    if cli.message_last {
-        let message = cli.message.join(" ");
-        if message.is_empty() {
-            bail!("Message is required when using -l flag");
-        }
-        return message_last_fork(&message, &opts).await;
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
     }
 
-    match cli.command {
-        Some(Commands::Spawn { message }) => {
-            let message = message.join(" ");
-            if message.is_empty() {
-                bail!("Message is required for spawn command");
-            }
-            validate_message_not_forky_command(&message)?;
-            fork_current_session(&message, &opts).await
-        }
-        Some(Commands::ForkMe { message }) => {
-            let message = message.join(" ");
-            if message.is_empty() {
-                bail!("Message is required for fork-me command");
-            }
-            validate_message_not_forky_command(&message)?;
-            fork_current_session(&message, &opts).await
-        }
-        Some(Commands::Fork { id, message }) => {
-            let message = message.join(" ");
-            if message.is_empty() {
-                bail!("Message is required for fork command");
-            }
-            validate_message_not_forky_command(&message)?;
-            fork_specific_session(&id, &message, &opts).await
-        }
-        Some(Commands::Resume { id, message }) => {
-            let message = message.join(" ");
-            if message.is_empty() {
-                bail!("Message is required for resume command");
-            }
-            validate_message_not_forky_command(&message)?;
-            resume_session(&id, &message, &opts).await
-        }
-        Some(Commands::List { entity }) => list_entities(entity).await,
-        Some(Commands::Messages { fork_id }) => list_messages(&fork_id).await,
-        Some(Commands::Read { id, all }) => {
-            if all {
-                println!("Mark all read not yet implemented via server");
-                Ok(())
-            } else if let Some(id) = id {
-                println!("Mark {id} read not yet implemented via server");
-                Ok(())
-            } else {
-                bail!("Either --all or an ID is required for read command");
-            }
-        }
-        Some(Commands::New { message }) => {
-            let message = message.join(" ");
-            if message.is_empty() {
-                bail!("Message is required for new command");
-            }
-            validate_message_not_forky_command(&message)?;
-            start_new_session(&message, &opts).await
-        }
-        Some(Commands::Done { fork_id, summary }) => {
-            let summary = summary.join(" ");
-            fork_done(&fork_id, &summary).await
-        }
-        Some(Commands::Serve { port, open }) => serve_ui(port, open).await,
-        Some(Commands::Events { session, limit }) => list_events(session.as_deref(), limit).await,
-        None => {
-            let message = cli.message.join(" ");
-            if message.is_empty() {
-                println!("Forky - Fork Claude sessions to handle side tasks in parallel");
-                println!();
-                println!("Usage: forky [OPTIONS] [MESSAGE]...");
-                println!("       forky <COMMAND>");
-                println!();
-                println!("Commands:");
-                println!("  spawn          Spawn a new forked Claude session (recommended)");
-                println!("  fork-me        Fork the current session");
-                println!("  fork <ID>      Fork a specific session");
-                println!("  resume <ID>    Resume a specific session");
-                println!("  list <TYPE>    List forks, sessions, or jobs");
-                println!("  messages <ID>  View messages for a fork");
-                println!("  new            Start a fresh Claude session");
-                println!("  serve          Start the observability UI server");
-                println!();
-                println!("Options:");
-                println!("  -l, --last       Message the last fork");
-                println!("  -m, --model      Model to use for Claude");
-                println!("  --worktree       Run in a git worktree");
-                println!("  --dir <PATH>     Directory to run in");
-                println!("  -h, --help       Print help");
-                return Ok(());
-            }
-            validate_message_not_forky_command(&message)?;
-            fork_current_session(&message, &opts).await
-        }
-    }
+    let body: serde_json::Value = resp.json().await.context("Failed to parse response")?;
+    let marked = body
+        .get("marked")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0);
+    Ok(usize::try_from(marked).unwrap_or(usize::MAX))
 }
 
-async fn fork_current_session(message: &str, opts: &ForkOptions) -> Result<()> {
-    let parent_session_id = detect_session_id()?;
+/// Compact the project's database. Returns how many bytes were reclaimed.
+async fn compact_on_server(port: u16, project_path: &str) -> Result<u64> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/maintenance/compact?project_path={}",
+        urlencoding::encode(project_path)
+    );
 
-    if parent_session_id.is_none() {
-        println!("Warning: Could not detect current session ID. Starting fresh session.");
+    let resp = send_with_retry(http_client().post(&url))
+        .await
+        .context("Failed to compact database on server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
     }
 
-    run_fork(parent_session_id.as_deref(), message, opts, true).await
+    let body: serde_json::Value = resp.json().await.context("Failed to parse response")?;
+    Ok(body
+        .get("reclaimed_bytes")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0))
 }
 
-async fn fork_specific_session(session_id: &str, message: &str, opts: &ForkOptions) -> Result<()> {
-    run_fork(Some(session_id), message, opts, true).await
+/// Session summary from server.
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionSummary {
+    session_id: Option<String>,
+    fork_id: Option<String>,
+    created_at: Option<String>,
 }
 
-async fn message_last_fork(message: &str, opts: &ForkOptions) -> Result<()> {
-    let port = server::ensure_server_running()?;
-    let project_path = get_project_path()?;
-    let project_str = project_path.to_string_lossy();
+/// Job summary from server.
+#[derive(Debug, Serialize, Deserialize)]
+struct JobSummary {
+    job_id: Option<String>,
+    description: Option<String>,
+    status: Option<String>,
+    fork_id: Option<String>,
+    created_at: Option<String>,
+}
 
-    let forks = get_forks_from_server(port, Some(&project_str)).await?;
+/// List sessions in a project via the server.
+async fn get_sessions_from_server(port: u16, project_path: &str) -> Result<Vec<SessionSummary>> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/sessions?project_path={}",
+        urlencoding::encode(project_path)
+    );
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to get sessions from server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    resp.json().await.context("Failed to parse sessions")
+}
+
+/// List jobs in a project via the server.
+async fn get_jobs_from_server(port: u16, project_path: &str) -> Result<Vec<JobSummary>> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/jobs?project_path={}",
+        urlencoding::encode(project_path)
+    );
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to get jobs from server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    resp.json().await.context("Failed to parse jobs")
+}
+
+/// Project statistics from server.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProjectStats {
+    project_path: String,
+    fork_count: usize,
+    forks_by_status: HashMap<String, usize>,
+    event_count: usize,
+    total_cost_usd: f64,
+    #[serde(default)]
+    cost_today_usd: f64,
+    total_turns: u64,
+    total_duration_ms: u64,
+    avg_cost_per_fork: f64,
+    tokens: TokenTotals,
+    tokens_by_fork: HashMap<String, TokenTotals>,
+}
+
+/// Run slot usage from the server, shared by every project.
+#[derive(Debug, Serialize, Deserialize)]
+struct PoolStatus {
+    active: usize,
+    queued: usize,
+}
+
+async fn get_pool_from_server(port: u16) -> Result<PoolStatus> {
+    let url = format!("http://127.0.0.1:{port}/api/pool");
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to get slot usage from server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    resp.json().await.context("Failed to parse slot usage")
+}
+
+/// Token usage totals from server.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TokenTotals {
+    input: u64,
+    output: u64,
+    cache_read: u64,
+    cache_creation: u64,
+}
+
+/// One turn of a fork's cost breakdown.
+#[derive(Debug, Serialize, Deserialize)]
+struct TurnCost {
+    turn: usize,
+    started_at: Option<String>,
+    cost_usd: f64,
+    cumulative_cost_usd: f64,
+    tokens: TokenTotals,
+    subagent_events: usize,
+}
+
+/// Where a fork's cost went, from the server.
+#[derive(Debug, Serialize, Deserialize)]
+struct CostBreakdown {
+    fork_id: String,
+    turns: Vec<TurnCost>,
+    tokens_by_model: HashMap<String, TokenTotals>,
+    total_cost_usd: f64,
+}
+
+/// Get aggregated project statistics from the server.
+async fn get_stats_from_server(port: u16, project_path: &str) -> Result<ProjectStats> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/stats?project_path={}",
+        urlencoding::encode(project_path)
+    );
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to get stats from server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    let stats: ProjectStats = resp.json().await.context("Failed to parse stats")?;
+    Ok(stats)
+}
+
+/// Get the per-turn cost breakdown of a fork from the server.
+async fn get_fork_cost_from_server(
+    port: u16,
+    project_path: &str,
+    fork_id: &str,
+) -> Result<CostBreakdown> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/forks/{fork_id}/cost?project_path={}",
+        urlencoding::encode(project_path)
+    );
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to get fork cost from server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    let breakdown: CostBreakdown = resp.json().await.context("Failed to parse fork cost")?;
+    Ok(breakdown)
+}
+
+/// Response to a slot request.
+#[derive(Debug, Deserialize)]
+struct SlotStatus {
+    granted: bool,
+    position: usize,
+}
+
+/// How often a queued fork asks the server for a slot again.
+const SLOT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often a running fork renews its slot; the server takes it back after
+/// three missed renewals.
+const SLOT_RENEW_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Wait for one of the server's `max_concurrent` run slots.
+///
+/// The slot is released when the fork's finished status is reported, or once
+/// `renew_slot` stops renewing it.
+async fn wait_for_slot(port: u16, fork_id: &str) -> Result<()> {
+    let url = format!("http://127.0.0.1:{port}/api/forks/{fork_id}/slot");
+    let mut last_position = None;
+
+    loop {
+        let resp = send_with_retry(http_client().post(&url))
+            .await
+            .context("Failed to request a fork slot")?;
+
+        if !resp.status().is_success() {
+            return Err(server_error(resp).await);
+        }
+
+        let slot: SlotStatus = resp.json().await.context("Failed to parse slot status")?;
+        if slot.granted {
+            if last_position.is_some() {
+                println!("Slot available, starting.");
+            }
+            return Ok(());
+        }
+
+        if last_position != Some(slot.position) {
+            println!(
+                "Waiting for a free slot ({} fork(s) ahead in the queue)...",
+                slot.position
+            );
+            last_position = Some(slot.position);
+        }
+        tokio::time::sleep(SLOT_POLL_INTERVAL).await;
+    }
+}
+
+/// Keep renewing a granted slot until the task is aborted.
+async fn renew_slot(port: u16, fork_id: String) {
+    let url = format!("http://127.0.0.1:{port}/api/forks/{fork_id}/slot");
+    loop {
+        tokio::time::sleep(SLOT_RENEW_INTERVAL).await;
+        if let Err(e) = send_with_retry(http_client().post(&url)).await {
+            tracing::warn!("Failed to renew fork slot: {e}");
+        }
+    }
+}
+
+/// Response from ingesting events.
+#[derive(Debug, Deserialize)]
+struct IngestResponse {
+    stored: usize,
+}
+
+/// Send events to the server. Returns how many were stored.
+async fn send_events_to_server(
+    port: u16,
+    project_path: &str,
+    events: &[ClaudeEvent],
+    fork_id: Option<&str>,
+) -> Result<usize> {
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let url = format!("http://127.0.0.1:{port}/api/events");
+    let events_json: Vec<_> = events.iter().map(|e| &e.raw).collect();
+
+    let body = serde_json::json!({
+        "project_path": project_path,
+        "fork_id": fork_id,
+        "events": events_json,
+    });
+
+    let resp = send_with_retry(http_client().post(&url).json(&body))
+        .await
+        .context("Failed to send events to server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    let response: IngestResponse = resp.json().await.context("Failed to parse response")?;
+    Ok(response.stored)
+}
+
+/// Get events from the server.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredEvent {
+    fork_id: Option<String>,
+    uuid: Option<String>,
+    session_id: Option<String>,
+    parent_tool_use_id: Option<String>,
+    event_type: String,
+    subtype: Option<String>,
+    message: Option<String>,
+    thinking: Option<String>,
+    result: Option<String>,
+    model: Option<String>,
+    role: Option<String>,
+    tool_uses: Option<serde_json::Value>,
+    tool_results: Option<serde_json::Value>,
+    cost_usd: Option<f64>,
+    total_cost_usd: Option<f64>,
+    duration_ms: Option<u64>,
+    num_turns: Option<u32>,
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+    cache_read_tokens: Option<u64>,
+    cache_creation_tokens: Option<u64>,
+    created_at: Option<String>,
+}
+
+/// A page of events from the server.
+#[derive(Debug, Deserialize)]
+struct EventsPage {
+    events: Vec<StoredEvent>,
+    /// Offset of the next page, if more events exist.
+    #[serde(default)]
+    next_offset: Option<usize>,
+}
+
+/// Event broadcast received over the server WebSocket.
+#[derive(Debug, Deserialize)]
+struct EventBroadcast {
+    fork_id: Option<String>,
+    event: StoredEvent,
+}
+
+/// An event matched by a search.
+#[derive(Debug, Serialize, Deserialize)]
+struct SearchHit {
+    field: String,
+    snippet: String,
+    event: StoredEvent,
+}
+
+/// Search results from the server.
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    results: Vec<SearchHit>,
+}
+
+async fn search_on_server(
+    port: u16,
+    project_path: &str,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchHit>> {
+    let url = format!(
+        "http://127.0.0.1:{port}/api/search?project_path={}&q={}&limit={limit}",
+        urlencoding::encode(project_path),
+        urlencoding::encode(query)
+    );
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to search events on server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    let results: SearchResults = resp.json().await.context("Failed to parse results")?;
+    Ok(results.results)
+}
+
+/// Filters for an events query. The server applies `limit` after filtering.
+#[derive(Debug, Default)]
+struct EventQuery<'a> {
+    fork_id: Option<&'a str>,
+    /// Session ID, matched as a prefix unless `exact_session` is set.
+    session: Option<&'a str>,
+    exact_session: bool,
+    /// Only events created at or after this RFC3339 time.
+    since: Option<&'a str>,
+    limit: usize,
+    /// Number of matching events to skip.
+    offset: usize,
+    /// Return the newest events first instead of the oldest.
+    newest_first: bool,
+}
+
+impl<'a> EventQuery<'a> {
+    fn for_fork(fork_id: &'a str, limit: usize) -> Self {
+        Self {
+            fork_id: Some(fork_id),
+            limit,
+            ..Self::default()
+        }
+    }
+}
+
+async fn get_events_from_server(
+    port: u16,
+    project_path: &str,
+    query: &EventQuery<'_>,
+) -> Result<Vec<StoredEvent>> {
+    Ok(get_events_page_from_server(port, project_path, query)
+        .await?
+        .events)
+}
+
+/// Get every event matching `query`, following pages until the last one.
+async fn get_all_events_from_server(
+    port: u16,
+    project_path: &str,
+    mut query: EventQuery<'_>,
+) -> Result<Vec<StoredEvent>> {
+    let mut events = Vec::new();
+    loop {
+        let page = get_events_page_from_server(port, project_path, &query).await?;
+        events.extend(page.events);
+        match page.next_offset {
+            Some(offset) => query.offset = offset,
+            None => return Ok(events),
+        }
+    }
+}
+
+async fn get_events_page_from_server(
+    port: u16,
+    project_path: &str,
+    query: &EventQuery<'_>,
+) -> Result<EventsPage> {
+    let mut url = format!(
+        "http://127.0.0.1:{port}/api/events?project_path={}&limit={}&offset={}&order={}",
+        urlencoding::encode(project_path),
+        query.limit,
+        query.offset,
+        if query.newest_first { "desc" } else { "asc" }
+    );
+    let filters = [
+        ("fork_id", query.fork_id),
+        ("session", query.session),
+        ("since", query.since),
+    ];
+    for (name, value) in filters {
+        if let Some(value) = value {
+            url = format!("{url}&{name}={}", urlencoding::encode(value));
+        }
+    }
+    if query.exact_session {
+        url.push_str("&session_match=exact");
+    }
+
+    let resp = send_with_retry(http_client().get(&url))
+        .await
+        .context("Failed to get events from server")?;
+
+    if !resp.status().is_success() {
+        return Err(server_error(resp).await);
+    }
+
+    resp.json().await.context("Failed to parse events")
+}
+
+// === CLI Options ===
+
+#[derive(Debug, Clone, Default)]
+pub struct ForkOptions {
+    pub model: Option<String>,
+    pub worktree: bool,
+    /// Git ref the worktree branches from (HEAD if unset).
+    pub from: Option<String>,
+    pub dir: Option<String>,
+    /// Extra environment variables for the claude process.
+    pub env: HashMap<String, String>,
+    /// Variables copied into an otherwise empty environment (if any).
+    pub copy_env: Option<Vec<String>>,
+    /// Directory the fork's final answer is written to.
+    pub output_dir: Option<PathBuf>,
+    pub chrome: bool,
+    pub no_chrome: bool,
+    pub append_system_prompt: Option<String>,
+    /// Leave out the instruction to run `forky done`.
+    pub no_callback: bool,
+    pub system_prompt: Option<String>,
+    pub agents: Option<String>,
+    pub mcp_config: Option<String>,
+    pub settings: Option<String>,
+    pub max_turns: Option<u32>,
+    pub tools: Option<String>,
+    pub allowed_tools: Option<String>,
+    pub include_partial_messages: bool,
+    pub timeout: Option<u64>,
+    pub max_cost: Option<f64>,
+    /// Project-wide spend allowed per day, from the config file.
+    pub daily_budget_usd: Option<f64>,
+    pub dry_run: bool,
+    /// Print the fork's output live.
+    pub attach: bool,
+    pub log_max_bytes: u64,
+    pub claude_path: Option<String>,
+    pub provider: ProviderKind,
+    /// `fork_id` of the fork being retried, if any.
+    pub retry_of: Option<String>,
+    pub tags: Vec<String>,
+    /// Existing fork this run adds a turn to, instead of creating one.
+    pub continue_fork: Option<ContinuedFork>,
+}
+
+/// A finished fork that `forky say` sends a follow-up message to.
+#[derive(Debug, Clone)]
+pub struct ContinuedFork {
+    pub fork_id: String,
+    pub fork_name: String,
+}
+
+/// Model used when neither `--model` nor the config file picks one.
+const DEFAULT_MODEL: &str = "opus";
+
+/// Messages longer than this are piped to claude's stdin instead of argv.
+const STDIN_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Transcript log cap used when the config file doesn't set `log_max_bytes`.
+const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+impl ForkOptions {
+    /// Build fork options from CLI args, filling gaps from the config file.
+    pub fn new(cli: &Cli, config: &Config) -> Self {
+        Self {
+            model: Some(
+                cli.model
+                    .clone()
+                    .or_else(|| config.default_model.clone())
+                    .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            ),
+            worktree: !cli.no_worktree && (cli.worktree || config.worktree || cli.from.is_some()),
+            from: cli.from.clone(),
+            dir: cli.dir.clone(),
+            env: cli.env.iter().cloned().collect(),
+            copy_env: if cli.copy_env.is_empty() {
+                config.copy_env.clone()
+            } else {
+                Some(cli.copy_env.clone())
+            },
+            output_dir: cli.output_dir.clone(),
+            chrome: cli.chrome,
+            no_chrome: cli.no_chrome,
+            append_system_prompt: cli.append_system_prompt.clone(),
+            no_callback: cli.no_callback,
+            system_prompt: cli.system_prompt.clone(),
+            agents: cli.agents.clone(),
+            mcp_config: cli.mcp_config.clone(),
+            settings: cli.settings.clone(),
+            max_turns: cli.max_turns,
+            tools: cli.tools.clone(),
+            allowed_tools: cli.allowed_tools.clone(),
+            include_partial_messages: cli.include_partial_messages,
+            timeout: cli.timeout,
+            max_cost: cli.max_cost,
+            daily_budget_usd: config.daily_budget_usd,
+            dry_run: cli.dry_run,
+            attach: cli.attach,
+            log_max_bytes: config.log_max_bytes.unwrap_or(DEFAULT_LOG_MAX_BYTES),
+            claude_path: config.claude_path.clone(),
+            provider: cli.provider,
+            retry_of: None,
+            tags: cli.tags.clone(),
+            continue_fork: None,
+        }
+    }
+}
+
+// === Command Execution ===
+
+/// Print a value as pretty JSON to stdout.
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Join a command's message words, requiring a message that doesn't look
+/// like a forky command (unless `force` is set).
+fn required_message(words: &[String], command: &str, force: bool) -> Result<String> {
+    let message = words.join(" ");
+    if message.is_empty() {
+        bail!("Message is required for {command} command");
+    }
+    if !force {
+        validate_message_not_forky_command(&message)?;
+    }
+    Ok(message)
+}
+
+/// Print the usage shown when forky runs without a message.
+fn print_usage() {
+    println!("Forky - Fork Claude sessions to handle side tasks in parallel");
+    println!();
+    println!("Usage: forky [OPTIONS] [MESSAGE]...");
+    println!("       forky <COMMAND>");
+    println!();
+    println!("Commands:");
+    println!("  spawn          Spawn a new forked Claude session (recommended)");
+    println!("  fork-me        Fork the current session");
+    println!("  fork <ID>      Fork a specific session");
+    println!("  resume <ID>    Resume a session (-i to take over in Claude)");
+    println!("  say <ID>       Send a follow-up message to a fork");
+    println!("  list <TYPE>    List forks, sessions, or jobs");
+    println!("  messages <ID>  View messages for a fork");
+    println!("  logs <ID>      Show or follow a fork's events");
+    println!("  wait <ID>...   Block until forks finish");
+    println!("  diff <ID>      Show changes in a worktree fork");
+    println!("  open <ID>      Open the dashboard on a fork");
+    println!("  merge <ID>     Merge a worktree fork's branch");
+    println!("  retry <ID>     Re-run a fork with its original message");
+    println!("  prune          Remove worktrees of finished forks");
+    println!("  compact        Reclaim database space after deletes");
+    println!("  export <ID>    Export a fork as JSON or Markdown");
+    println!("  import         Import an NDJSON transcript as a fork");
+    println!("  search <TEXT>  Find forks that mention some text");
+    println!("  status         Summarize forks, cost and the server");
+    println!("  cost <ID>      Break a fork's cost down by turn");
+    println!("  notifications  Show forks that finished while you were away");
+    println!("  new            Start a fresh Claude session");
+    println!("  serve          Start the observability UI server");
+    println!("  server <CMD>   Check on (status) or stop the background server");
+    println!();
+    println!("Options:");
+    println!("  -l, --last       Message the last fork");
+    println!("  -m, --model      Model to use for Claude");
+    println!("  --worktree       Run in a git worktree");
+    println!("  --from <REF>     Start the worktree from a branch, tag or commit");
+    println!("  --attach         Print the fork's output live");
+    println!("  --no-callback    Don't ask the fork to run `forky done`");
+    println!("  --force          Send a message even if it looks like a forky command");
+    println!("  --provider       AI CLI to run (default: claude)");
+    println!("  --dir <PATH>     Directory to run in");
+    println!("  --output-dir <PATH>  Write the final answer to <PATH>/<fork-id>.txt");
+    println!("  -h, --help       Print help");
+}
+
+pub async fn execute(cli: Cli) -> Result<()> {
+    let config = Config::load()?;
+    let opts = ForkOptions::new(&cli, &config);
+    let json = cli.json;
+    let force = cli.force;
+
+    // Handle -l flag (message last fork)
+    if cli.message_last {
+        let message = cli.message.join(" ");
+        if message.is_empty() {
+            bail!("Message is required when using -l flag");
+        }
+        return message_last_fork(&message, &opts).await;
+    }
+
+    match cli.command {
+        Some(Commands::Spawn { message }) => {
+            let message = required_message(&message, "spawn", force)?;
+            fork_current_session(&message, &opts).await
+        }
+        Some(Commands::ForkMe { message }) => {
+            let message = required_message(&message, "fork-me", force)?;
+            fork_current_session(&message, &opts).await
+        }
+        Some(Commands::Fork { id, message }) => {
+            let message = required_message(&message, "fork", force)?;
+            fork_specific_session(&id, &message, &opts).await
+        }
+        Some(Commands::Resume {
+            id,
+            interactive,
+            message,
+        }) => {
+            let message = message.join(" ");
+            if message.is_empty() && !interactive {
+                bail!("Message is required for resume command");
+            }
+            if !force && !message.is_empty() {
+                validate_message_not_forky_command(&message)?;
+            }
+            if interactive {
+                return resume_interactive(&id, &message, &opts).await;
+            }
+            resume_session(&id, &message, &opts).await
+        }
+        Some(Commands::Say { fork_id, message }) => {
+            let message = required_message(&message, "say", force)?;
+            say_to_fork(&fork_id, &message, cli.model.is_some(), &opts).await
+        }
+        Some(Commands::Retry { fork_id }) => retry_fork(&fork_id, cli.model.is_some(), &opts).await,
+        Some(Commands::New { message }) => {
+            let message = required_message(&message, "new", force)?;
+            start_new_session(&message, &opts).await
+        }
+        Some(command) => run_command(command, &config, json).await,
+        None => {
+            let message = cli.message.join(" ");
+            if message.is_empty() {
+                print_usage();
+                return Ok(());
+            }
+            if !force {
+                validate_message_not_forky_command(&message)?;
+            }
+            fork_current_session(&message, &opts).await
+        }
+    }
+}
+
+/// Run a command that doesn't start or continue a session.
+async fn run_command(command: Commands, config: &Config, json: bool) -> Result<()> {
+    match command {
+        Commands::List { entity, tags } => list_entities(entity, &tags, json).await,
+        Commands::Messages { fork_id } => list_messages(&fork_id, json).await,
+        Commands::Logs {
+            fork_id,
+            follow,
+            lines,
+        } => tail_logs(&fork_id, follow, lines).await,
+        Commands::Wait {
+            fork_ids,
+            all,
+            timeout,
+        } => wait_for_forks(fork_ids, all, timeout, json).await,
+        Commands::Diff { fork_id } => show_diff(&fork_id).await,
+        Commands::Merge {
+            fork_id,
+            squash,
+            force,
+        } => merge_fork(&fork_id, squash, force).await,
+        Commands::Prune {
+            dry_run,
+            older_than,
+        } => prune_worktrees(dry_run, older_than).await,
+        Commands::Compact => compact_database(json).await,
+        Commands::Open { fork_id } => open_fork(&fork_id).await,
+        Commands::Export { fork_id, format } => export_fork(&fork_id, format).await,
+        Commands::Import { file, fork_id } => import_transcript(&file, fork_id).await,
+        Commands::Rm { fork_id } => remove_fork(&fork_id).await,
+        Commands::Read { id, all } => mark_read(id.as_deref(), all).await,
+        Commands::Done {
+            fork_id,
+            summary,
+            notify,
+        } => {
+            let summary = summary.join(" ");
+            fork_done(&fork_id, &summary, notify || config.notify, json).await
+        }
+        Commands::Serve {
+            port,
+            host,
+            auth_token,
+            open,
+        } => {
+            // A port asked for on the command line must be used as given
+            let scan_ports = port.is_none();
+            let port = port.or(config.server_port).unwrap_or(server::DEFAULT_PORT);
+            let auth_token = auth_token.or_else(crate::config::auth_token);
+            serve_ui(host, port, scan_ports, open, auth_token).await
+        }
+        Commands::Server { action } => match action {
+            ServerAction::Status => show_server_status(json),
+            ServerAction::Stop => shut_down_server(json),
+        },
+        Commands::Stats => show_stats(json).await,
+        Commands::Cost { fork_id } => show_fork_cost(&fork_id, json).await,
+        Commands::Status => show_status(json).await,
+        Commands::Notifications { clear } => show_notifications(clear, json).await,
+        Commands::Search { query, limit } => search_events(&query, limit, json).await,
+        Commands::Events {
+            session,
+            exact,
+            since,
+            limit,
+        } => {
+            let query = EventQuery {
+                session: session.as_deref(),
+                exact_session: exact,
+                since: since.as_deref(),
+                limit,
+                ..EventQuery::default()
+            };
+            list_events(&query, json).await
+        }
+        Commands::Spawn { .. }
+        | Commands::ForkMe { .. }
+        | Commands::Fork { .. }
+        | Commands::Resume { .. }
+        | Commands::Say { .. }
+        | Commands::Retry { .. }
+        | Commands::New { .. } => unreachable!("sessions are started by execute"),
+    }
+}
+
+async fn fork_current_session(message: &str, opts: &ForkOptions) -> Result<()> {
+    let parent_session_id = detect_session_id()?;
+
+    if parent_session_id.is_none() {
+        println!("Warning: Could not detect current session ID. Starting fresh session.");
+    }
+
+    run_fork(parent_session_id.as_deref(), message, opts, true).await
+}
+
+async fn fork_specific_session(session_id: &str, message: &str, opts: &ForkOptions) -> Result<()> {
+    run_fork(Some(session_id), message, opts, true).await
+}
+
+async fn message_last_fork(message: &str, opts: &ForkOptions) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let forks = get_forks_from_server(port, Some(&project_str), &[]).await?;
     let fork = forks.first().context("No forks found")?;
 
-    let session_id = fork
-        .session_id
-        .as_deref()
-        .or(fork.parent_session_id.as_deref())
-        .context("Fork has no session ID")?;
+    let session_id = fork
+        .session_id
+        .as_deref()
+        .or(fork.parent_session_id.as_deref())
+        .context("Fork has no session ID")?;
+
+    run_fork(Some(session_id), message, opts, false).await
+}
+
+async fn resume_session(session_id: &str, message: &str, opts: &ForkOptions) -> Result<()> {
+    run_fork(Some(session_id), message, opts, false).await
+}
+
+/// Hand a session over to Claude's interactive UI in this terminal.
+///
+/// A session that belongs to a worktree fork is opened in the worktree,
+/// since Claude looks sessions up by directory.
+async fn resume_interactive(session_id: &str, message: &str, opts: &ForkOptions) -> Result<()> {
+    if !opts.dry_run && !std::io::stdin().is_terminal() {
+        bail!("--interactive needs a terminal");
+    }
+
+    let working_dir = match opts.dir {
+        Some(ref dir) => Some(dir.clone()),
+        // Best effort: the session may not belong to any fork
+        None => fork_dir_for_session(session_id).await.ok().flatten(),
+    };
+
+    let claude_opts = ClaudeOptions {
+        session_id: Some(session_id.to_string()),
+        model: opts.model.clone(),
+        message: message.to_string(),
+        working_dir,
+        claude_path: opts.claude_path.clone(),
+        provider: opts.provider,
+        env: opts.env.clone(),
+        copy_env: opts.copy_env.clone(),
+        dry_run: opts.dry_run,
+        ..ClaudeOptions::default()
+    };
+    run_claude_interactive(claude_opts).await
+}
+
+/// The worktree of the fork that ran `session_id`, if it ran in one.
+async fn fork_dir_for_session(session_id: &str) -> Result<Option<String>> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let forks = get_forks_from_server(port, Some(&project_str), &[]).await?;
+    Ok(forks
+        .into_iter()
+        .find(|fork| fork.session_id.as_deref() == Some(session_id))
+        .and_then(|fork| fork.worktree_path))
+}
+
+/// Send a follow-up message to a finished fork.
+///
+/// The fork's session is resumed and the new turn is stored under the same
+/// fork ID, so the conversation stays in one thread. Like `retry`, the fork's
+/// model is kept unless `--model` is given.
+async fn say_to_fork(
+    fork_id: &str,
+    message: &str,
+    model_given: bool,
+    opts: &ForkOptions,
+) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let Some(fork) = get_fork_from_server(port, &project_str, fork_id).await? else {
+        bail!("Fork not found: {fork_id}");
+    };
+    if fork.status == "running" {
+        bail!("Fork {fork_id} is still running");
+    }
+    let Some(ref session_id) = fork.session_id else {
+        bail!("Fork {fork_id} has no recorded session ID yet");
+    };
+
+    let mut opts = opts.clone();
+    if !model_given && fork.model.is_some() {
+        opts.model.clone_from(&fork.model);
+    }
+    // Claude looks sessions up by directory, so resume where the fork ran
+    opts.worktree = false;
+    if let Some(ref path) = fork.worktree_path {
+        opts.dir = Some(path.clone());
+    }
+    let fork_name = fork.fork_name.clone().unwrap_or_else(|| fork_id.clone());
+    opts.continue_fork = Some(ContinuedFork {
+        fork_id: fork.fork_id.clone(),
+        fork_name,
+    });
+
+    run_fork(Some(session_id), message, &opts, false).await
+}
+
+async fn start_new_session(message: &str, opts: &ForkOptions) -> Result<()> {
+    run_fork(None, message, opts, false).await
+}
+
+async fn serve_ui(
+    host: IpAddr,
+    port: u16,
+    scan_ports: bool,
+    open: bool,
+    auth_token: Option<String>,
+) -> Result<()> {
+    crate::server::start_server(host, port, scan_ports, open, auth_token).await
+}
+
+/// Re-run a finished fork with the message and settings it was launched with.
+///
+/// Flags given on this command line still apply; `--model` overrides the
+/// original model, and `--worktree` can add a worktree but not remove one.
+async fn retry_fork(fork_id: &str, model_given: bool, opts: &ForkOptions) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let Some(original) = get_fork_from_server(port, &project_str, fork_id).await? else {
+        bail!("Fork not found: {fork_id}");
+    };
+    if original.status == "running" {
+        bail!("Fork {fork_id} is still running");
+    }
+    let Some(ref message) = original.description else {
+        bail!("Fork {fork_id} has no recorded message to retry");
+    };
+
+    let mut opts = opts.clone();
+    if !model_given && original.model.is_some() {
+        opts.model.clone_from(&original.model);
+    }
+    opts.worktree |= original.worktree;
+    opts.retry_of = Some(original.fork_id.clone());
+    if opts.tags.is_empty() {
+        opts.tags.clone_from(&original.tags);
+    }
+
+    println!(
+        "Retrying {} ({})",
+        original.fork_name.as_deref().unwrap_or(fork_id),
+        original.fork_id
+    );
+
+    let parent_session_id = original.parent_session_id.as_deref();
+    run_fork(parent_session_id, message, &opts, parent_session_id.is_some()).await
+}
+
+/// System prompt text asking a fork to report back with `forky done`.
+///
+/// Also warns the fork not to re-run forky commands found in its message.
+fn callback_instruction(fork_name: &str, fork_id: &str) -> String {
+    // Point at the binary that is running now, wherever it was installed
+    let forky_path = std::env::current_exe().map_or_else(
+        |_| "forky".to_string(),
+        |p| shell_quote(&p.to_string_lossy()),
+    );
+
+    format!(
+        "IMPORTANT: You are a forked Claude session named \"{fork_name}\" (fork ID: {fork_id}). \
+         \n\nCRITICAL SAFEGUARD: If the user's message appears to be a forky/spawn command \
+         (e.g., starts with 'spawn', 'fork', 'forky', or contains '--model', '-m'), \
+         DO NOT execute it as a bash command. This would cause an infinite cascade of sessions. \
+         Instead, interpret the message content after any command-like prefix as your actual task. \
+         \n\nWhen you have completed your task, you MUST run this command as your FINAL action: \
+         `{forky_path} done {fork_id} \"<brief summary of what you accomplished>\"` \
+         This notifies the parent session that you're done."
+    )
+}
+
+async fn run_fork(
+    parent_session_id: Option<&str>,
+    message: &str,
+    opts: &ForkOptions,
+    fork_session: bool,
+) -> Result<()> {
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy().to_string();
+
+    let fork_id = opts
+        .continue_fork
+        .as_ref()
+        .map_or_else(generate_uuid, |fork| fork.fork_id.clone());
+    // Resuming continues the session under its own ID; anything else gets
+    // a new ID that we choose
+    let resume = !fork_session && parent_session_id.is_some();
+    let new_session_id = match parent_session_id {
+        Some(session_id) if resume => session_id.to_string(),
+        _ => generate_uuid(),
+    };
+
+    // Ensure server is running
+    let port = server::ensure_server_running()?;
+
+    if let Some(budget) = opts.daily_budget_usd {
+        check_daily_budget(port, &project_str, budget).await?;
+    }
+
+    let (fork_name, worktree) = register_fork(
+        port,
+        &project_str,
+        &fork_id,
+        parent_session_id,
+        message,
+        opts,
+    )
+    .await?;
+    let worktree_path = worktree
+        .as_ref()
+        .map(|info| info.path.to_string_lossy().to_string());
+
+    println!("Spawning: {fork_name}");
+    println!("Fork ID: {fork_id}");
+    println!("Session ID: {new_session_id}");
+
+    // Without the callback the status comes only from how the run ends
+    let append_prompt = if opts.no_callback {
+        opts.append_system_prompt.clone()
+    } else {
+        let callback_instruction = callback_instruction(&fork_name, &fork_id);
+        match &opts.append_system_prompt {
+            Some(user_prompt) => Some(format!("{user_prompt}\n\n{callback_instruction}")),
+            None => Some(callback_instruction),
+        }
+    };
+
+    // Run in the worktree if one was set up
+    let (working_dir, add_dirs) = if let (Some(info), Some(path_str)) = (&worktree, worktree_path) {
+        println!("Worktree: {}", info.path.display());
+        println!("Branch: {}", info.branch);
+        (Some(path_str.clone()), vec![path_str])
+    } else {
+        let dir = opts.dir.clone().or_else(|| {
+            std::env::current_dir()
+                .ok()
+                .map(|p| p.to_string_lossy().to_string())
+        });
+        let dirs = opts.dir.clone().map_or_else(Vec::new, |d| vec![d]);
+        (dir, dirs)
+    };
+
+    // Build stream URL for real-time events
+    let stream_url = Some(format!("http://127.0.0.1:{port}/api/events"));
+
+    if !opts.dry_run {
+        send_prompt_event(port, &project_str, &fork_id, &new_session_id, message).await;
+    }
+
+    // Spawn Claude
+    // Huge prompts hit "argument list too long", so send them on stdin
+    let large_message = message.len() > STDIN_MESSAGE_BYTES;
+    let claude_opts = ClaudeOptions {
+        session_id: parent_session_id.map(String::from),
+        explicit_session_id: (!resume).then(|| new_session_id.clone()),
+        fork_session,
+        model: opts.model.clone(),
+        message: if large_message {
+            String::new()
+        } else {
+            message.to_string()
+        },
+        stdin_input: large_message.then(|| message.to_string()),
+        working_dir,
+        add_dirs,
+        claude_path: opts.claude_path.clone(),
+        provider: opts.provider,
+        env: opts.env.clone(),
+        copy_env: opts.copy_env.clone(),
+        append_system_prompt: append_prompt,
+        system_prompt: opts.system_prompt.clone(),
+        chrome: opts.chrome,
+        no_chrome: opts.no_chrome,
+        agents: opts.agents.clone(),
+        mcp_config: opts.mcp_config.clone(),
+        settings: opts.settings.clone(),
+        max_turns: opts.max_turns,
+        tools: opts.tools.clone(),
+        allowed_tools: opts.allowed_tools.clone(),
+        include_partial_messages: opts.include_partial_messages,
+        timeout: opts.timeout.map(Duration::from_secs),
+        max_cost: opts.max_cost,
+        dry_run: opts.dry_run,
+        attach: opts.attach,
+        stream_url: stream_url.clone(),
+        fork_id: Some(fork_id.clone()),
+        project_path: Some(project_str.clone()),
+        log_file: Some(log_path(&fork_id)?),
+        log_max_bytes: opts.log_max_bytes,
+    };
+
+    let result = spawn_in_slot(port, &project_str, &fork_id, claude_opts).await?;
+    finish_fork(port, &project_str, &fork_id, &new_session_id, result, opts).await
+}
+
+/// Refuse to start a fork once the project has spent `budget` today.
+async fn check_daily_budget(port: u16, project_str: &str, budget: f64) -> Result<()> {
+    let stats = get_stats_from_server(port, project_str).await?;
+    if stats.cost_today_usd >= budget {
+        bail!(
+            "Daily budget of ${budget:.2} reached (${:.2} spent today)",
+            stats.cost_today_usd
+        );
+    }
+    Ok(())
+}
+
+/// Set up the fork's worktree and create the fork on the server, or mark a
+/// continued fork running again. Returns the fork's name and worktree.
+async fn register_fork(
+    port: u16,
+    project_str: &str,
+    fork_id: &str,
+    parent_session_id: Option<&str>,
+    message: &str,
+    opts: &ForkOptions,
+) -> Result<(String, Option<WorktreeInfo>)> {
+    // A bad --from is a typo, not a reason to silently fork from HEAD
+    if let Some(ref from) = opts.from {
+        verify_ref(from)?;
+    }
+
+    // Set up the worktree first so its location is recorded with the fork.
+    // A dry run records the fork without touching the repository.
+    let worktree = if opts.worktree && !opts.dry_run {
+        setup_worktree(fork_id, opts.from.as_deref())
+            .map_err(|e| {
+                eprintln!("Warning: Failed to create worktree: {e}");
+                eprintln!("Continuing without worktree...");
+            })
+            .ok()
+    } else {
+        None
+    };
+
+    let fork_name = if let Some(ref fork) = opts.continue_fork {
+        // The fork already exists; this turn makes it running again
+        if !opts.dry_run {
+            update_fork_status_on_server(
+                port,
+                project_str,
+                fork_id,
+                ForkStatus::Running,
+                &ForkUpdate::default(),
+            )
+            .await?;
+        }
+        fork.fork_name.clone()
+    } else {
+        // Create fork on server - returns generated name
+        let worktree_path = worktree
+            .as_ref()
+            .map(|info| info.path.to_string_lossy().to_string());
+        let launch = ForkLaunch {
+            description: Some(message),
+            model: opts.model.as_deref(),
+            worktree: opts.worktree,
+            worktree_path: worktree_path.as_deref(),
+            worktree_branch: worktree.as_ref().map(|info| info.branch.as_str()),
+            worktree_base: worktree.as_ref().map(|info| info.base.as_str()),
+            ai_provider: Some(opts.provider.as_str()),
+            retry_of: opts.retry_of.as_deref(),
+            tags: &opts.tags,
+        };
+        create_fork_on_server(port, project_str, fork_id, parent_session_id, &launch).await?
+    };
+    Ok((fork_name, worktree))
+}
+
+/// Store the prompt that starts a fork's turn as a user event.
+async fn send_prompt_event(
+    port: u16,
+    project_str: &str,
+    fork_id: &str,
+    session_id: &str,
+    message: &str,
+) {
+    let prompt_event_json = serde_json::json!({
+        "type": "user",
+        "uuid": generate_uuid(),
+        "session_id": session_id,
+        "message": {
+            "role": "user",
+            "content": [{"type": "text", "text": message}]
+        }
+    });
+    if let Some(prompt_event) = ClaudeEvent::parse(&prompt_event_json.to_string()) {
+        let _ = send_events_to_server(port, project_str, &[prompt_event], Some(fork_id)).await;
+    }
+}
+
+/// Run Claude once the server grants the fork a slot, renewing the slot
+/// until the run ends.
+async fn spawn_in_slot(
+    port: u16,
+    project_str: &str,
+    fork_id: &str,
+    claude_opts: ClaudeOptions,
+) -> Result<ClaudeResult> {
+    // Queue behind other forks once max_concurrent are running
+    let renewal = if claude_opts.dry_run {
+        None
+    } else {
+        wait_for_slot(port, fork_id).await?;
+        Some(tokio::spawn(renew_slot(port, fork_id.to_string())))
+    };
+
+    let result = spawn_claude(claude_opts).await;
+    if let Some(renewal) = renewal {
+        renewal.abort();
+    }
+    if let Err(ref e) = result {
+        // Report the failure so the fork's slot is released
+        let reason = e.to_string();
+        let update = ForkUpdate {
+            reason: Some(&reason),
+            ..ForkUpdate::default()
+        };
+        let _ =
+            update_fork_status_on_server(port, project_str, fork_id, ForkStatus::Failed, &update)
+                .await;
+    }
+    result
+}
+
+/// Record how a fork's run ended, then print the outcome and response.
+async fn finish_fork(
+    port: u16,
+    project_str: &str,
+    fork_id: &str,
+    new_session_id: &str,
+    result: ClaudeResult,
+    opts: &ForkOptions,
+) -> Result<()> {
+    if opts.dry_run {
+        if opts.continue_fork.is_some() {
+            return Ok(());
+        }
+        // Nothing ran, so the new fork must not look completed
+        let update = ForkUpdate {
+            reason: Some("dry run"),
+            ..ForkUpdate::default()
+        };
+        return update_fork_status_on_server(
+            port,
+            project_str,
+            fork_id,
+            ForkStatus::Cancelled,
+            &update,
+        )
+        .await;
+    }
+
+    let status = if result.success {
+        ForkStatus::Completed
+    } else if result.timed_out {
+        ForkStatus::Timeout
+    } else {
+        ForkStatus::Failed
+    };
+    let session_id = result.session_id.as_deref().unwrap_or(new_session_id);
+    let reason = if result.timed_out {
+        opts.timeout.map(|secs| format!("timed out after {secs}s"))
+    } else if result.cost_limit_exceeded {
+        Some("cost limit exceeded".to_string())
+    } else {
+        result.errors.first().cloned().or_else(|| {
+            result
+                .result_subtype
+                .as_deref()
+                .and_then(describe_result_subtype)
+        })
+    };
+
+    let update = ForkUpdate {
+        session_id: Some(session_id),
+        reason: reason.as_deref(),
+        result_subtype: result.result_subtype.as_deref(),
+        // Aliases like "opus" resolve to a dated model; record which one ran
+        model: result.events.iter().find_map(|e| e.model.as_deref()),
+        ..ForkUpdate::default()
+    };
+    let _ = update_fork_status_on_server(port, project_str, fork_id, status, &update).await;
+
+    // Print result
+    if result.success {
+        println!("\nFork completed successfully.");
+        if let Some(cost) = result.cost_usd {
+            println!("Cost: ${cost:.4}");
+        }
+    } else if let Some(ref reason) = reason {
+        println!("\nFork failed: {reason}.");
+    } else {
+        println!("\nFork failed.");
+    }
+
+    for error in &result.errors {
+        eprintln!("Error: {error}");
+    }
+
+    let response = if result.messages.is_empty() {
+        result.result
+    } else {
+        Some(result.messages.join(""))
+    };
+
+    if let (Some(dir), Some(response)) = (&opts.output_dir, &response) {
+        let path = write_output(dir, fork_id, response)?;
+        println!("Output written to {}", path.display());
+    }
+
+    // An attached fork has already printed its replies
+    if opts.attach {
+        return Ok(());
+    }
+
+    if let Some(response) = response {
+        println!("\nResponse:\n{response}");
+    }
+
+    Ok(())
+}
+
+/// Write a fork's final answer to `<dir>/<fork_id>.txt`, creating `dir` if needed.
+fn write_output(dir: &Path, fork_id: &str, response: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let path = dir.join(format!("{fork_id}.txt"));
+    std::fs::write(&path, response)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Explain a non-success result subtype, e.g. `error_max_turns` -> "hit max turns".
+fn describe_result_subtype(subtype: &str) -> Option<String> {
+    match subtype {
+        "success" => None,
+        "error_max_turns" => Some("hit max turns".to_string()),
+        other => Some(other.trim_start_matches("error_").replace('_', " ")),
+    }
+}
+
+async fn list_entities(entity: ListEntity, tags: &[String], json: bool) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    match entity {
+        ListEntity::Forks => {
+            let forks = get_forks_from_server(port, Some(&project_str), tags).await?;
+            if json {
+                return print_json(&forks);
+            }
+            if forks.is_empty() {
+                println!("No forks found.");
+                return Ok(());
+            }
+
+            println!(
+                "{:<10} {:<28} {:<12} {:<18} {:<8} {:<16} DESCRIPTION",
+                "ID", "NAME", "STATUS", "MODEL", "EVENTS", "BRANCH"
+            );
+            println!("{}", "-".repeat(136));
+
+            for fork in forks {
+                let name = fork.fork_name.as_deref().unwrap_or("-");
+                let name_short = truncate_chars(name, 26);
+                let description = fork
+                    .description
+                    .as_deref()
+                    .and_then(|d| d.lines().next())
+                    .unwrap_or("-");
+                let description_short = if description.chars().count() > 40 {
+                    format!("{}...", truncate_chars(description, 37))
+                } else {
+                    description.to_string()
+                };
+                let model = fork.model.as_deref().unwrap_or("-");
+                let model_short =
+                    truncate_chars(model.strip_prefix("claude-").unwrap_or(model), 17);
+                println!(
+                    "{:<10} {:<28} {:<12} {:<18} {:<8} {:<16} {}",
+                    &fork.fork_id[..8.min(fork.fork_id.len())],
+                    name_short,
+                    fork.status,
+                    model_short,
+                    fork.event_count,
+                    fork.worktree_branch.as_deref().unwrap_or("-"),
+                    description_short,
+                );
+            }
+        }
+        ListEntity::Sessions => {
+            let sessions = get_sessions_from_server(port, &project_str).await?;
+            if json {
+                return print_json(&sessions);
+            }
+            if sessions.is_empty() {
+                println!("No sessions found.");
+                return Ok(());
+            }
+
+            println!("{:<38} {:<10} {:<20}", "SESSION", "FORK", "CREATED");
+            println!("{}", "-".repeat(70));
+
+            for session in sessions {
+                let fork_id = session.fork_id.as_deref().unwrap_or("-");
+                println!(
+                    "{:<38} {:<10} {:<20}",
+                    session.session_id.as_deref().unwrap_or("-"),
+                    &fork_id[..8.min(fork_id.len())],
+                    session.created_at.as_deref().unwrap_or("-"),
+                );
+            }
+        }
+        ListEntity::Jobs => {
+            let jobs = get_jobs_from_server(port, &project_str).await?;
+            if json {
+                return print_json(&jobs);
+            }
+            if jobs.is_empty() {
+                println!("No jobs found.");
+                return Ok(());
+            }
+
+            println!("{:<12} {:<10} {:<12} DESCRIPTION", "JOB", "FORK", "STATUS");
+            println!("{}", "-".repeat(70));
+
+            for job in jobs {
+                let fork_id = job.fork_id.as_deref().unwrap_or("-");
+                println!(
+                    "{:<12} {:<10} {:<12} {}",
+                    job.job_id.as_deref().unwrap_or("-"),
+                    &fork_id[..8.min(fork_id.len())],
+                    job.status.as_deref().unwrap_or("-"),
+                    job.description.as_deref().unwrap_or(""),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn list_messages(fork_id: &str, json: bool) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let query = EventQuery::for_fork(fork_id, 100);
+    let events = get_events_from_server(port, &project_str, &query).await?;
+
+    if json {
+        return print_json(&events);
+    }
+
+    if events.is_empty() {
+        println!("No messages found for fork {fork_id}.");
+        return Ok(());
+    }
+
+    for event in events {
+        let role = event.role.as_deref().unwrap_or(&event.event_type);
+        let role_display = role.to_uppercase();
+
+        if let Some(ref msg) = event.message {
+            println!("[{role_display}]:");
+            println!("{msg}");
+            println!();
+        }
+
+        if let Some(ref thinking) = event.thinking {
+            println!("[{role_display} THINKING]:");
+            let short = truncate_chars(thinking, 200);
+            let preview = if short.len() < thinking.len() {
+                format!("{short}...")
+            } else {
+                thinking.clone()
+            };
+            println!("{preview}");
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+/// Page size when fetching all of a fork's stored events.
+const LOGS_FETCH_LIMIT: usize = 10_000;
+
+/// Delay before reconnecting a dropped WebSocket.
+const LOGS_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// How often to check fork status while following logs.
+const LOGS_STATUS_POLL: Duration = Duration::from_secs(5);
+
+/// Print a single event as a log line.
+fn print_log_event(event: &StoredEvent) {
+    let role = event.role.as_deref().unwrap_or(&event.event_type);
+    let role_display = role.to_uppercase();
+
+    if let Some(ref thinking) = event.thinking {
+        println!("[{role_display} THINKING] {thinking}");
+    }
+    if let Some(ref msg) = event.message {
+        println!("[{role_display}] {msg}");
+    }
+}
+
+/// Log events already printed, so catching up after a reconnect doesn't
+/// repeat them.
+#[derive(Default)]
+struct PrintedLogs {
+    uuids: HashSet<String>,
+    /// `created_at` of the newest event printed.
+    latest: Option<String>,
+}
+
+impl PrintedLogs {
+    fn print(&mut self, event: &StoredEvent) {
+        if let Some(ref uuid) = event.uuid {
+            if !self.uuids.insert(uuid.clone()) {
+                return;
+            }
+        }
+        if event.created_at > self.latest {
+            self.latest.clone_from(&event.created_at);
+        }
+        print_log_event(event);
+    }
+}
+
+/// WebSocket URL streaming only `fork_id`'s events.
+fn logs_ws_url(port: u16, project_path: &str, fork_id: &str) -> String {
+    let mut url = format!(
+        "ws://127.0.0.1:{port}/ws?project_path={}&fork_id={}",
+        urlencoding::encode(project_path),
+        urlencoding::encode(fork_id)
+    );
+    if let Some(token) = crate::config::auth_token() {
+        url = format!("{url}&token={}", urlencoding::encode(&token));
+    }
+    url
+}
+
+/// Check whether a fork has reached a terminal status.
+async fn fork_finished(port: u16, project_path: &str, fork_id: &str) -> bool {
+    matches!(
+        get_fork_from_server(port, project_path, fork_id).await,
+        Ok(Some(fork)) if fork.is_finished()
+    )
+}
+
+async fn tail_logs(fork_id: &str, follow: bool, lines: usize) -> Result<()> {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy().to_string();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let query = EventQuery {
+        newest_first: true,
+        ..EventQuery::for_fork(fork_id, lines)
+    };
+    let mut printed = PrintedLogs::default();
+    for event in get_events_from_server(port, &project_str, &query)
+        .await?
+        .iter()
+        .rev()
+    {
+        printed.print(event);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        // The server may have restarted; re-read its port on every attempt
+        let Some(port) = server::get_server_port() else {
+            tokio::time::sleep(LOGS_RECONNECT_DELAY).await;
+            continue;
+        };
+
+        if fork_finished(port, &project_str, fork_id).await {
+            return Ok(());
+        }
+
+        let url = logs_ws_url(port, &project_str, fork_id);
+        match tokio_tungstenite::connect_async(&url).await {
+            Ok((mut socket, _)) => {
+                // Events stored before the socket was subscribed are only in
+                // the database
+                let missed = EventQuery {
+                    since: printed.latest.as_deref(),
+                    ..EventQuery::for_fork(fork_id, LOGS_FETCH_LIMIT)
+                };
+                match get_all_events_from_server(port, &project_str, missed).await {
+                    Ok(events) => events.iter().for_each(|event| printed.print(event)),
+                    Err(e) => eprintln!("Failed to fetch missed events: {e:#}"),
+                }
+
+                let mut status_poll = tokio::time::interval(LOGS_STATUS_POLL);
+                loop {
+                    tokio::select! {
+                        msg = socket.next() => {
+                            match msg {
+                                Some(Ok(Message::Text(text))) => {
+                                    if let Ok(broadcast) = serde_json::from_str::<EventBroadcast>(&text) {
+                                        if broadcast.fork_id.as_deref() == Some(fork_id) {
+                                            printed.print(&broadcast.event);
+                                        }
+                                    }
+                                }
+                                Some(Ok(_)) => {}
+                                Some(Err(_)) | None => break,
+                            }
+                        }
+                        _ = status_poll.tick() => {
+                            if fork_finished(port, &project_str, fork_id).await {
+                                return Ok(());
+                            }
+                        }
+                    }
+                }
+                eprintln!("Connection to server lost, reconnecting...");
+            }
+            Err(e) => eprintln!("Failed to connect to {url}: {e}"),
+        }
+
+        tokio::time::sleep(LOGS_RECONNECT_DELAY).await;
+    }
+}
+
+/// How often to poll fork status while waiting.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Print the final state of a finished fork.
+fn print_fork_outcome(fork: &ForkSummary) {
+    let name = fork.fork_name.as_deref().unwrap_or(&fork.fork_id);
+    let mark = if fork.status == "completed" {
+        "✓"
+    } else {
+        "✗"
+    };
+    println!(
+        "{mark} {name} ({}) {} - {} events",
+        fork.fork_id, fork.status, fork.event_count
+    );
+    if let Some(ref reason) = fork.reason {
+        println!("  Reason: {reason}");
+    }
+}
+
+async fn wait_for_forks(
+    fork_ids: Vec<String>,
+    all: bool,
+    timeout: Option<u64>,
+    json: bool,
+) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let mut pending = Vec::with_capacity(fork_ids.len());
+    for fork_id in &fork_ids {
+        pending.push(resolve_fork_id(port, &project_str, fork_id).await?);
+    }
+    if all {
+        let forks = get_forks_from_server(port, Some(&project_str), &[]).await?;
+        pending.extend(
+            forks
+                .into_iter()
+                .filter(|fork| !fork.is_finished())
+                .map(|fork| fork.fork_id),
+        );
+        if pending.is_empty() {
+            println!("No running forks");
+            return Ok(());
+        }
+    }
+
+    let deadline = timeout.map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs));
+    let mut finished = Vec::new();
+
+    loop {
+        let mut still_pending = Vec::new();
+        for fork_id in pending {
+            let fork = get_fork_from_server(port, &project_str, &fork_id)
+                .await?
+                .with_context(|| format!("Fork not found: {fork_id}"))?;
+            if fork.is_finished() {
+                if !json {
+                    print_fork_outcome(&fork);
+                }
+                finished.push(fork);
+            } else {
+                still_pending.push(fork_id);
+            }
+        }
+        pending = still_pending;
+
+        if pending.is_empty() {
+            break;
+        }
+        if deadline.is_some_and(|d| tokio::time::Instant::now() >= d) {
+            if json {
+                print_json(&finished)?;
+            }
+            bail!(
+                "Timed out waiting for {} fork(s): {}",
+                pending.len(),
+                pending.join(", ")
+            );
+        }
+        tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+    }
+
+    if json {
+        print_json(&finished)?;
+    }
+
+    let failed = finished.iter().filter(|f| f.status != "completed").count();
+    if failed > 0 {
+        bail!("{failed} of {} fork(s) did not complete", finished.len());
+    }
+
+    Ok(())
+}
+
+async fn mark_read(fork_id: Option<&str>, all: bool) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    if all {
+        let marked = mark_all_forks_read_on_server(port, &project_str).await?;
+        println!("Marked {marked} forks as read");
+    } else if let Some(fork_id) = fork_id {
+        let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+        mark_fork_read_on_server(port, &project_str, fork_id).await?;
+        println!("Marked {fork_id} as read");
+    } else {
+        bail!("Either --all or an ID is required for read command");
+    }
+
+    Ok(())
+}
+
+/// Show what a worktree fork changed since it branched, committed or not.
+async fn show_diff(fork_id: &str) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let fork = get_fork_from_server(port, &project_str, fork_id)
+        .await?
+        .with_context(|| format!("Fork not found: {fork_id}"))?;
+    let (worktree_path, _) = fork.worktree()?;
+
+    if !worktree_path.is_dir() {
+        println!("No worktree found at {}.", worktree_path.display());
+        println!("The fork either ran without --worktree or its worktree was removed.");
+        return Ok(());
+    }
+
+    let git = |args: &[&str]| -> Result<String> {
+        let output = Command::new("git")
+            .current_dir(&worktree_path)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    // Forks created before the base was recorded fall back to the main checkout's HEAD
+    let base = if let Some(base) = fork.worktree_base {
+        base
+    } else {
+        let output = Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .context("Failed to run git rev-parse")?;
+        if !output.status.success() {
+            bail!("Could not find the commit fork {fork_id} branched from");
+        }
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let committed = git(&["diff", &format!("{base}...HEAD")])?;
+    let status = git(&["status", "--short"])?;
+    if committed.trim().is_empty() && status.trim().is_empty() {
+        println!("No changes in {}", worktree_path.display());
+        return Ok(());
+    }
+
+    println!("Worktree: {}", worktree_path.display());
+    println!("Base: {}", &base[..12.min(base.len())]);
+    if !committed.trim().is_empty() {
+        println!();
+        println!("Committed changes:");
+        println!();
+        print!("{committed}");
+    }
+    if !status.trim().is_empty() {
+        println!();
+        println!("Uncommitted changes:");
+        println!();
+        print!("{status}");
+        println!();
+        print!("{}", git(&["diff", "HEAD"])?);
+    }
+
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal. Defaults to no.
+fn confirm(prompt: &str) -> bool {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+
+    print!("{prompt} [y/N] ");
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Merge a worktree fork's branch into the current branch of the main repo.
+async fn merge_fork(fork_id: &str, squash: bool, force: bool) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let fork = get_fork_from_server(port, &project_str, fork_id)
+        .await?
+        .with_context(|| format!("Fork not found: {fork_id}"))?;
+    if fork.status != "completed" && !force {
+        bail!(
+            "Fork {fork_id} is {}, not completed. Use --force to merge anyway.",
+            fork.status
+        );
+    }
+
+    let repo_root = repo_root()?;
+    let git = |args: &[&str]| -> Result<std::process::Output> {
+        Command::new("git")
+            .current_dir(&repo_root)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))
+    };
+
+    let status = git(&["status", "--porcelain"])?;
+    if !status.stdout.is_empty() {
+        bail!(
+            "Working tree at {} has uncommitted changes; commit or stash them first.",
+            repo_root.display()
+        );
+    }
+
+    let (worktree_path, branch_name) = fork.worktree()?;
+    if !git(&["rev-parse", "--verify", "--quiet", &branch_name])?
+        .status
+        .success()
+    {
+        bail!("Branch {branch_name} not found. Did the fork run with --worktree?");
+    }
+
+    // Only committed work is merged; warn about anything left behind
+    if worktree_path.is_dir() {
+        let pending = Command::new("git")
+            .current_dir(&worktree_path)
+            .args(["status", "--porcelain"])
+            .output()
+            .context("Failed to check worktree status")?;
+        if !pending.stdout.is_empty() {
+            eprintln!(
+                "Warning: {} has uncommitted changes that will not be merged.",
+                worktree_path.display()
+            );
+        }
+    }
+
+    let merge = if squash {
+        git(&["merge", "--squash", &branch_name])?
+    } else {
+        git(&["merge", "--no-edit", &branch_name])?
+    };
+
+    if !merge.status.success() {
+        let conflicts = git(&["diff", "--name-only", "--diff-filter=U"])?;
+        let conflicts = String::from_utf8_lossy(&conflicts.stdout);
+        if conflicts.trim().is_empty() {
+            bail!(
+                "Merge of {branch_name} failed: {}",
+                String::from_utf8_lossy(&merge.stderr).trim()
+            );
+        }
+
+        eprintln!("Merge of {branch_name} has conflicts in:");
+        for file in conflicts.lines() {
+            eprintln!("  {file}");
+        }
+        bail!("Resolve the conflicts and commit, or run 'git merge --abort'.");
+    }
+
+    if squash {
+        println!("Squashed {branch_name} into the index; commit when ready.");
+    } else {
+        println!("Merged {branch_name}");
+    }
+
+    if confirm(&format!("Remove worktree and branch {branch_name}?")) {
+        if worktree_path.is_dir() {
+            let worktree = worktree_path.to_string_lossy();
+            let removed = git(&["worktree", "remove", "--force", &worktree])?;
+            if !removed.status.success() {
+                bail!(
+                    "Failed to remove worktree: {}",
+                    String::from_utf8_lossy(&removed.stderr).trim()
+                );
+            }
+        }
+        let deleted = git(&["branch", "-D", &branch_name])?;
+        if !deleted.status.success() {
+            bail!(
+                "Failed to delete branch: {}",
+                String::from_utf8_lossy(&deleted.stderr).trim()
+            );
+        }
+        println!("Removed worktree and branch {branch_name}");
+    }
+
+    Ok(())
+}
+
+/// Remove worktrees (and their branches) belonging to finished forks.
+async fn prune_worktrees(dry_run: bool, older_than_days: Option<u64>) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let forks = get_forks_from_server(port, Some(&project_str), &[]).await?;
+    let cutoff = older_than_days
+        .map(|days| Utc::now() - chrono::Duration::days(i64::try_from(days).unwrap_or(i64::MAX)));
+
+    let repo_root = repo_root()?;
+    let mut pruned = 0;
+
+    // Only this project's forks are considered, so other projects'
+    // worktrees are left alone
+    for fork in &forks {
+        let (path, branch_name) = fork.worktree()?;
+        if !path.is_dir() {
+            continue;
+        }
+        let short_id = &fork.fork_id[..8.min(fork.fork_id.len())];
+
+        if !fork.is_finished() {
+            println!("Keeping {short_id} ({} is {})", fork.fork_id, fork.status);
+            continue;
+        }
+        if let Some(cutoff) = cutoff {
+            let created = fork
+                .created_at
+                .as_deref()
+                .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok());
+            if created.is_none_or(|created| created > cutoff) {
+                continue;
+            }
+        }
+
+        if dry_run {
+            println!(
+                "Would remove {} and {branch_name} ({})",
+                path.display(),
+                fork.status
+            );
+            pruned += 1;
+            continue;
+        }
+
+        let removed = Command::new("git")
+            .current_dir(&repo_root)
+            .args(["worktree", "remove", "--force"])
+            .arg(&path)
+            .output()
+            .context("Failed to run git worktree remove")?;
+        if !removed.status.success() {
+            eprintln!(
+                "Failed to remove {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&removed.stderr).trim()
+            );
+            continue;
+        }
+        let _ = Command::new("git")
+            .current_dir(&repo_root)
+            .args(["branch", "-D", &branch_name])
+            .output();
+
+        println!("Removed {} and {branch_name}", path.display());
+        pruned += 1;
+    }
+
+    if dry_run {
+        println!("{pruned} worktrees would be pruned");
+    } else {
+        println!("Pruned {pruned} worktrees");
+    }
+
+    Ok(())
+}
+
+/// Search event content and print each match with its fork.
+async fn search_events(query: &str, limit: usize, json: bool) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let hits = search_on_server(port, &project_str, query, limit).await?;
+
+    if json {
+        return print_json(&hits);
+    }
+
+    if hits.is_empty() {
+        println!("No events match \"{query}\".");
+        return Ok(());
+    }
+
+    for hit in hits {
+        let fork = hit.event.fork_id.as_deref().unwrap_or("-");
+        let when = hit.event.created_at.as_deref().unwrap_or("-");
+        println!("{fork}  {when}  [{}]", hit.field);
+        println!("    {}", hit.snippet.replace('\n', " "));
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Export a fork's events to stdout.
+async fn export_fork(fork_id: &str, format: ExportFormat) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let query = EventQuery::for_fork(fork_id, LOGS_FETCH_LIMIT);
+    let events = get_all_events_from_server(port, &project_str, query).await?;
+
+    match format {
+        ExportFormat::Json => print_json(&events),
+        ExportFormat::Markdown => {
+            let fork = get_fork_from_server(port, &project_str, fork_id).await?;
+            let title = fork
+                .and_then(|f| f.fork_name)
+                .unwrap_or_else(|| fork_id.clone());
+            print!("{}", render_markdown(&title, &events));
+            Ok(())
+        }
+    }
+}
+
+/// Render events as a Markdown transcript.
+fn render_markdown(title: &str, events: &[StoredEvent]) -> String {
+    use std::fmt::Write;
+
+    let mut out = format!("# {title}\n");
+
+    for event in events {
+        let role = event.role.as_deref().unwrap_or(&event.event_type);
+        let mut body = String::new();
+
+        if let Some(ref thinking) = event.thinking {
+            let _ = write!(
+                body,
+                "<details>\n<summary>Thinking</summary>\n\n{thinking}\n\n</details>\n\n"
+            );
+        }
+        if let Some(ref msg) = event.message {
+            let _ = write!(body, "{msg}\n\n");
+        }
+        for tool_use in event
+            .tool_uses
+            .as_ref()
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let name = tool_use
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("tool");
+            let input = tool_use
+                .get("input")
+                .map(|v| serde_json::to_string_pretty(v).unwrap_or_default())
+                .unwrap_or_default();
+            let _ = write!(body, "**Tool: {name}**\n\n```json\n{input}\n```\n\n");
+        }
+        if let Some(ref result) = event.result {
+            let _ = write!(body, "{result}\n\n");
+        }
+
+        if !body.is_empty() {
+            let _ = write!(out, "\n## {}\n\n{}", capitalize(role), body.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Uppercase the first character of a string.
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Number of events sent per request when importing.
+const IMPORT_BATCH_SIZE: usize = 100;
+
+/// Import a Claude NDJSON transcript as a new fork.
+async fn import_transcript(file: &std::path::Path, fork_id: Option<String>) -> Result<()> {
+    use std::io::BufRead;
+
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let reader = std::io::BufReader::new(
+        std::fs::File::open(file).with_context(|| format!("Failed to open {}", file.display()))?,
+    );
+
+    let fork_id = fork_id.unwrap_or_else(generate_uuid);
+    let fork_name =
+        create_fork_on_server(port, &project_str, &fork_id, None, &ForkLaunch::default()).await?;
+    println!("Importing {} as {fork_name} ({fork_id})", file.display());
+
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    let mut session_id = None;
+    let mut parsed = 0;
+    let mut skipped = 0;
+    let mut stored = 0;
+
+    for line in reader.lines() {
+        let line = line.with_context(|| format!("Failed to read {}", file.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(event) = ClaudeEvent::parse(&line) else {
+            skipped += 1;
+            continue;
+        };
+
+        if session_id.is_none() {
+            session_id.clone_from(&event.session_id);
+        }
+        parsed += 1;
+        batch.push(event);
+
+        if batch.len() >= IMPORT_BATCH_SIZE {
+            stored += send_events_to_server(port, &project_str, &batch, Some(&fork_id)).await?;
+            batch.clear();
+        }
+    }
+    stored += send_events_to_server(port, &project_str, &batch, Some(&fork_id)).await?;
+
+    let update = ForkUpdate {
+        session_id: session_id.as_deref(),
+        ..ForkUpdate::default()
+    };
+    update_fork_status_on_server(port, &project_str, &fork_id, ForkStatus::Completed, &update)
+        .await?;
 
-    run_fork(Some(session_id), message, opts, false).await
-}
+    println!("Stored {stored} of {parsed} events ({skipped} unparseable lines skipped)");
 
-async fn resume_session(session_id: &str, message: &str, opts: &ForkOptions) -> Result<()> {
-    run_fork(Some(session_id), message, opts, false).await
+    Ok(())
 }
 
-async fn start_new_session(message: &str, opts: &ForkOptions) -> Result<()> {
-    run_fork(None, message, opts, false).await
+async fn remove_fork(fork_id: &str) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
+
+    let events_deleted = delete_fork_on_server(port, &project_str, fork_id).await?;
+    println!("Removed fork {fork_id} ({events_deleted} events)");
+
+    Ok(())
 }
 
-async fn serve_ui(port: u16, open: bool) -> Result<()> {
-    crate::server::start_server(port, open).await
+async fn fork_done(fork_id: &str, summary: &str, notify: bool, json: bool) -> Result<()> {
+    // Try to update fork status via server
+    let mut fork_name = None;
+    if let Ok(port) = server::get_server_port().ok_or(()).map_err(|_| ()) {
+        if let Ok(project_path) = get_project_path() {
+            let project_str = project_path.to_string_lossy();
+            let update = ForkUpdate {
+                summary: (!summary.is_empty()).then_some(summary),
+                ..ForkUpdate::default()
+            };
+            let _ = update_fork_status_on_server(
+                port,
+                &project_str,
+                fork_id,
+                ForkStatus::Completed,
+                &update,
+            )
+            .await;
+            if let Ok(Some(fork)) = get_fork_from_server(port, &project_str, fork_id).await {
+                fork_name = fork.fork_name;
+            }
+        }
+    }
+
+    // Write to notifications file
+    let notification = Notification {
+        fork_id: fork_id.to_string(),
+        timestamp: Utc::now(),
+        summary: if summary.is_empty() {
+            "Fork completed".to_string()
+        } else {
+            summary.to_string()
+        },
+    };
+    notifications::append(&notifications::pending_path()?, &notification)?;
+
+    if notify {
+        let name = fork_name.as_deref().unwrap_or(fork_id);
+        send_desktop_notification(&format!("{name} finished"), &notification.summary);
+    }
+
+    if json {
+        return print_json(&serde_json::json!({
+            "fork_id": notification.fork_id,
+            "fork_name": fork_name,
+            "timestamp": notification.timestamp,
+            "summary": notification.summary,
+        }));
+    }
+
+    println!("✓ Fork {fork_id} done");
+    if !summary.is_empty() {
+        println!("  Summary: {summary}");
+    }
+
+    Ok(())
 }
 
-async fn run_fork(
-    parent_session_id: Option<&str>,
-    message: &str,
-    opts: &ForkOptions,
-    fork_session: bool,
-) -> Result<()> {
+/// Open the dashboard in a browser with a fork selected.
+async fn open_fork(fork_id: &str) -> Result<()> {
+    let port = server::ensure_server_running()?;
     let project_path = get_project_path()?;
-    let project_str = project_path.to_string_lossy().to_string();
+    let project_str = project_path.to_string_lossy();
 
-    let fork_id = generate_uuid();
-    let new_session_id = generate_uuid();
+    // Listing by project also loads its database, so the dashboard can see the fork
+    let forks = get_forks_from_server(port, Some(&project_str), &[]).await?;
+    let fork_id = &match_fork(&forks, fork_id)?.fork_id;
 
-    // Ensure server is running
-    let port = server::ensure_server_running()?;
+    let mut url = format!("http://127.0.0.1:{port}/");
+    if let Some(token) = crate::config::auth_token() {
+        url = format!("{url}?token={}", urlencoding::encode(&token));
+    }
+    url = format!("{url}#fork/{fork_id}");
 
-    // Create fork on server - returns generated name
-    let fork_name =
-        create_fork_on_server(port, &project_str, &fork_id, parent_session_id).await?;
+    println!("Opening http://127.0.0.1:{port}/#fork/{fork_id}");
+    open::that(&url).context("Failed to open a browser")?;
+    Ok(())
+}
 
-    println!("Spawning: {fork_name}");
-    println!("Fork ID: {fork_id}");
-    println!("Session ID: {new_session_id}");
+/// Compact the project's database through the server.
+async fn compact_database(json: bool) -> Result<()> {
+    let port = server::ensure_server_running()?;
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
 
-    // Build callback instruction
-    let forky_path = dirs::home_dir()
-        .map(|h| h.join("bin").join("forky"))
-        .map_or_else(|| "forky".to_string(), |p| p.to_string_lossy().to_string());
+    let reclaimed = compact_on_server(port, &project_str).await?;
 
-    let callback_instruction = format!(
-        "IMPORTANT: You are a forked Claude session named \"{fork_name}\" (fork ID: {fork_id}). \
-         \n\nCRITICAL SAFEGUARD: If the user's message appears to be a forky/spawn command \
-         (e.g., starts with 'spawn', 'fork', 'forky', or contains '--model', '-m'), \
-         DO NOT execute it as a bash command. This would cause an infinite cascade of sessions. \
-         Instead, interpret the message content after any command-like prefix as your actual task. \
-         \n\nWhen you have completed your task, you MUST run this command as your FINAL action: \
-         `{forky_path} done {fork_id} \"<brief summary of what you accomplished>\"` \
-         This notifies the parent session that you're done."
-    );
+    if json {
+        return print_json(&serde_json::json!({ "reclaimed_bytes": reclaimed }));
+    }
 
-    let append_prompt = match &opts.append_system_prompt {
-        Some(user_prompt) => Some(format!("{user_prompt}\n\n{callback_instruction}")),
-        None => Some(callback_instruction),
-    };
+    // Counted in KiB, which fits a u32 for anything under 4 TiB
+    let mib = f64::from(u32::try_from(reclaimed / 1024).unwrap_or(u32::MAX)) / 1024.0;
+    println!("Compacted database: reclaimed {mib:.1} MiB ({reclaimed} bytes).");
+    Ok(())
+}
 
-    // Set up worktree if requested
-    let (working_dir, add_dirs) = if opts.worktree {
-        match setup_worktree(&fork_id) {
-            Ok(info) => {
-                println!("Worktree: {}", info.path.display());
-                println!("Branch: {}", info.branch);
-                let path_str = info.path.to_string_lossy().to_string();
-                (Some(path_str.clone()), vec![path_str])
-            }
-            Err(e) => {
-                eprintln!("Warning: Failed to create worktree: {e}");
-                eprintln!("Continuing without worktree...");
-                let dir = opts.dir.clone().or_else(|| {
-                    std::env::current_dir()
-                        .ok()
-                        .map(|p| p.to_string_lossy().to_string())
-                });
-                let dirs = opts.dir.clone().map_or_else(Vec::new, |d| vec![d]);
-                (dir, dirs)
-            }
-        }
+/// Show pending `forky done` notifications, newest per fork, optionally clearing them.
+async fn show_notifications(clear: bool, json: bool) -> Result<()> {
+    let path = notifications::pending_path()?;
+    let pending = if clear {
+        notifications::take(&path)?
     } else {
-        let dir = opts.dir.clone().or_else(|| {
-            std::env::current_dir()
-                .ok()
-                .map(|p| p.to_string_lossy().to_string())
-        });
-        let dirs = opts.dir.clone().map_or_else(Vec::new, |d| vec![d]);
-        (dir, dirs)
+        notifications::read(&path)?
     };
+    let pending = notifications::latest_per_fork(pending);
 
-    // Build stream URL for real-time events
-    let stream_url = Some(format!("http://127.0.0.1:{port}/api/events"));
-
-    // Store the initial user prompt
-    let prompt_event_json = serde_json::json!({
-        "type": "user",
-        "uuid": generate_uuid(),
-        "session_id": new_session_id,
-        "message": {
-            "role": "user",
-            "content": [{"type": "text", "text": message}]
+    // Names are a nicety: don't start the server just to look them up
+    let mut names = HashMap::new();
+    if let Some(port) = server::get_server_port() {
+        if let Ok(forks) = get_forks_from_server(port, None, &[]).await {
+            for fork in forks {
+                if let Some(name) = fork.fork_name {
+                    names.insert(fork.fork_id, name);
+                }
+            }
         }
-    });
-    if let Some(prompt_event) = ClaudeEvent::parse(&prompt_event_json.to_string()) {
-        let _ = send_events_to_server(port, &project_str, &[prompt_event], Some(&fork_id)).await;
     }
 
-    // Spawn Claude
-    let claude_opts = ClaudeOptions {
-        session_id: parent_session_id.map(String::from),
-        explicit_session_id: Some(new_session_id.clone()),
-        fork_session,
-        model: opts.model.clone(),
-        message: message.to_string(),
-        working_dir,
-        add_dirs,
-        append_system_prompt: append_prompt,
-        system_prompt: opts.system_prompt.clone(),
-        chrome: opts.chrome,
-        no_chrome: opts.no_chrome,
-        agents: opts.agents.clone(),
-        mcp_config: opts.mcp_config.clone(),
-        settings: opts.settings.clone(),
-        max_turns: opts.max_turns,
-        tools: opts.tools.clone(),
-        allowed_tools: opts.allowed_tools.clone(),
-        include_partial_messages: opts.include_partial_messages,
-        stream_url: stream_url.clone(),
-        fork_id: Some(fork_id.clone()),
-        project_path: Some(project_str.clone()),
-    };
+    if json {
+        let entries: Vec<_> = pending
+            .iter()
+            .map(|n| {
+                serde_json::json!({
+                    "fork_id": n.fork_id,
+                    "fork_name": names.get(&n.fork_id),
+                    "timestamp": n.timestamp,
+                    "summary": n.summary,
+                })
+            })
+            .collect();
+        return print_json(&entries);
+    }
+
+    if pending.is_empty() {
+        println!("No pending notifications.");
+        return Ok(());
+    }
 
-    let result = spawn_claude(claude_opts).await?;
+    for notification in &pending {
+        let short_id = &notification.fork_id[..8.min(notification.fork_id.len())];
+        let name = names.get(&notification.fork_id).map_or("-", String::as_str);
+        let time = notification
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M");
+        println!("✓ {name} ({short_id}) at {time}");
+        println!("  {}", notification.summary);
+    }
 
-    // Update fork status
-    let status = if result.success { "completed" } else { "failed" };
-    let session_id = result.session_id.as_ref().unwrap_or(&new_session_id);
+    if clear {
+        println!("\nCleared {} notification(s).", pending.len());
+    }
 
-    let _ = update_fork_status_on_server(port, &project_str, &fork_id, status, Some(session_id))
-        .await;
+    Ok(())
+}
 
-    // Print result
-    if result.success {
-        println!("\nFork completed successfully.");
-        if let Some(cost) = result.cost_usd {
-            println!("Cost: ${cost:.4}");
-        }
-    } else {
-        println!("\nFork failed.");
+/// Show a desktop notification. Best-effort: a missing notifier is ignored.
+fn send_desktop_notification(title: &str, body: &str) {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        );
+        let _ = Command::new("osascript").args(["-e", &script]).output();
     }
 
-    if !result.messages.is_empty() {
-        let response = result.messages.join("");
-        println!("\nResponse:\n{response}");
-    } else if let Some(ref response) = result.result {
-        println!("\nResponse:\n{response}");
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let _ = Command::new("notify-send")
+            .args(["--app-name", "forky", title, body])
+            .output();
     }
 
-    Ok(())
+    #[cfg(not(unix))]
+    let _ = (title, body);
 }
 
-async fn list_entities(entity: ListEntity) -> Result<()> {
+/// Quote text as an AppleScript string literal.
+#[cfg(target_os = "macos")]
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+async fn show_stats(json: bool) -> Result<()> {
     let port = server::ensure_server_running()?;
     let project_path = get_project_path()?;
     let project_str = project_path.to_string_lossy();
 
-    match entity {
-        ListEntity::Forks => {
-            let forks = get_forks_from_server(port, Some(&project_str)).await?;
-            if forks.is_empty() {
-                println!("No forks found.");
-                return Ok(());
-            }
+    let stats = get_stats_from_server(port, &project_str).await?;
 
-            println!(
-                "{:<10} {:<28} {:<12} {:<8}",
-                "ID", "NAME", "STATUS", "EVENTS"
-            );
-            println!("{}", "-".repeat(60));
+    if json {
+        return print_json(&stats);
+    }
 
-            for fork in forks {
-                let name = fork.fork_name.as_deref().unwrap_or("-");
-                let name_short = if name.len() > 26 { &name[..26] } else { name };
-                println!(
-                    "{:<10} {:<28} {:<12} {:<8}",
-                    &fork.fork_id[..8.min(fork.fork_id.len())],
-                    name_short,
-                    fork.status,
-                    fork.event_count,
-                );
-            }
-        }
-        ListEntity::Sessions => {
-            println!("Session listing via server not yet implemented.");
-            println!("Use 'forky list forks' to see forks with their session IDs.");
-        }
-        ListEntity::Jobs => {
-            println!("Job listing via server not yet implemented.");
-            println!("Use 'forky list forks' to see forks.");
-        }
+    println!("Project: {}", stats.project_path);
+    println!("Forks:   {}", stats.fork_count);
+
+    let mut statuses: Vec<_> = stats.forks_by_status.iter().collect();
+    statuses.sort();
+    for (status, count) in statuses {
+        println!("  {status:<12} {count}");
+    }
+
+    println!("Events:  {}", stats.event_count);
+    println!("Turns:   {}", stats.total_turns);
+    println!(
+        "Time:    {:.1}s",
+        Duration::from_millis(stats.total_duration_ms).as_secs_f64()
+    );
+    println!("Cost:    ${:.4}", stats.total_cost_usd);
+    println!("Avg/fork: ${:.4}", stats.avg_cost_per_fork);
+    println!(
+        "Tokens:  {} in, {} out, {} cache read, {} cache write",
+        stats.tokens.input,
+        stats.tokens.output,
+        stats.tokens.cache_read,
+        stats.tokens.cache_creation
+    );
+
+    let mut by_fork: Vec<_> = stats.tokens_by_fork.iter().collect();
+    by_fork.sort_by(|a, b| a.0.cmp(b.0));
+    for (fork_id, tokens) in by_fork {
+        let short_id = &fork_id[..8.min(fork_id.len())];
+        println!(
+            "  {short_id:<12} {} in, {} out, {} cache read",
+            tokens.input, tokens.output, tokens.cache_read
+        );
     }
+
     Ok(())
 }
 
-async fn list_messages(fork_id: &str) -> Result<()> {
+async fn show_fork_cost(fork_id: &str, json: bool) -> Result<()> {
     let port = server::ensure_server_running()?;
     let project_path = get_project_path()?;
     let project_str = project_path.to_string_lossy();
+    let fork_id = &resolve_fork_id(port, &project_str, fork_id).await?;
 
-    let events = get_events_from_server(port, &project_str, Some(fork_id), 100).await?;
+    let breakdown = get_fork_cost_from_server(port, &project_str, fork_id).await?;
 
-    if events.is_empty() {
-        println!("No messages found for fork {fork_id}.");
+    if json {
+        return print_json(&breakdown);
+    }
+
+    if breakdown.turns.is_empty() {
+        println!("No events recorded for fork {fork_id}.");
         return Ok(());
     }
 
-    for event in events {
-        let role = event.role.as_deref().unwrap_or(&event.event_type);
-        let role_display = role.to_uppercase();
+    println!(
+        "{:<5} {:<17} {:>10} {:>11} {:>10} {:>10} {:>11} {:>10}",
+        "TURN", "STARTED", "COST", "CUMULATIVE", "INPUT", "OUTPUT", "CACHE READ", "SUBAGENT"
+    );
+    println!("{}", "-".repeat(91));
+    for turn in &breakdown.turns {
+        let started = turn
+            .started_at
+            .as_deref()
+            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+            .map_or_else(
+                || "-".to_string(),
+                |ts| {
+                    ts.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                },
+            );
+        println!(
+            "{:<5} {:<17} {:>10} {:>11} {:>10} {:>10} {:>11} {:>10}",
+            turn.turn,
+            started,
+            format!("${:.4}", turn.cost_usd),
+            format!("${:.4}", turn.cumulative_cost_usd),
+            turn.tokens.input,
+            turn.tokens.output,
+            turn.tokens.cache_read,
+            turn.subagent_events
+        );
+    }
 
-        if let Some(ref msg) = event.message {
-            println!("[{role_display}]:");
-            println!("{msg}");
-            println!();
-        }
+    println!();
+    let mut by_model: Vec<_> = breakdown.tokens_by_model.iter().collect();
+    by_model.sort_by(|a, b| a.0.cmp(b.0));
+    for (model, tokens) in by_model {
+        println!(
+            "  {model:<28} {} in, {} out, {} cache read",
+            tokens.input, tokens.output, tokens.cache_read
+        );
+    }
+    println!("Total: ${:.4}", breakdown.total_cost_usd);
 
-        if let Some(ref thinking) = event.thinking {
-            println!("[{role_display} THINKING]:");
-            let preview = if thinking.len() > 200 {
-                format!("{}...", &thinking[..200])
-            } else {
-                thinking.clone()
-            };
-            println!("{preview}");
-            println!();
-        }
+    Ok(())
+}
+
+/// Report the background server's PID, port and health without starting it.
+fn show_server_status(json: bool) -> Result<()> {
+    let status = server::server_status();
+    if json {
+        return print_json(&status);
     }
 
+    let pid = status
+        .pid
+        .map_or_else(|| "-".to_string(), |pid| pid.to_string());
+    let port = status
+        .port
+        .map_or_else(|| "-".to_string(), |port| port.to_string());
+    let state = match (status.alive, status.healthy) {
+        (true, true) => "running",
+        (true, false) => "not responding",
+        (false, _) if status.pid.is_some() => "not running (stale PID file)",
+        (false, _) => "not running",
+    };
+    println!("Server: {state}");
+    println!("PID:    {pid}");
+    println!("Port:   {port}");
+
     Ok(())
 }
 
-async fn fork_done(fork_id: &str, summary: &str) -> Result<()> {
-    use std::fs::OpenOptions;
-    use std::io::Write;
+/// Stop the background server, clearing any stale PID and port files.
+fn shut_down_server(json: bool) -> Result<()> {
+    let stopped = server::stop_server()?;
+    if json {
+        return print_json(&serde_json::json!({ "stopped_pid": stopped }));
+    }
 
-    // Try to update fork status via server
-    if let Ok(port) = server::get_server_port().ok_or(()).map_err(|_| ()) {
-        if let Ok(project_path) = get_project_path() {
-            let project_str = project_path.to_string_lossy();
-            let _ =
-                update_fork_status_on_server(port, &project_str, fork_id, "completed", None).await;
-        }
+    match stopped {
+        Some(pid) => println!("Stopped server (PID {pid})"),
+        None => println!("Server was not running"),
     }
 
-    // Write to notifications file
-    let notif_dir = dirs::home_dir()
-        .context("Could not find home directory")?
-        .join(".forky")
-        .join("notifications");
-    std::fs::create_dir_all(&notif_dir)?;
-
-    let notif_file = notif_dir.join("pending.txt");
-    let notification = format!(
-        "{}|{}|{}\n",
-        fork_id,
-        Utc::now().format("%Y-%m-%d %H:%M:%S"),
-        if summary.is_empty() {
-            "Fork completed"
-        } else {
-            summary
+    Ok(())
+}
+
+/// Print an at-a-glance summary of the current project.
+///
+/// Unlike other commands this never starts the server; it reports that the
+/// server is down instead.
+async fn show_status(json: bool) -> Result<()> {
+    let project_path = get_project_path()?;
+    let project_str = project_path.to_string_lossy();
+
+    let Some(port) = server::get_server_port() else {
+        if json {
+            return print_json(&serde_json::json!({
+                "project_path": project_str,
+                "server_port": null,
+            }));
         }
-    );
+        println!("Project: {project_str}");
+        println!("Server:  not running (start it with `forky serve`)");
+        return Ok(());
+    };
 
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&notif_file)?;
-    file.write_all(notification.as_bytes())?;
+    let forks = get_forks_from_server(port, Some(&project_str), &[]).await?;
+    let stats = get_stats_from_server(port, &project_str).await?;
+    // Older servers don't report slot usage
+    let pool = get_pool_from_server(port).await.ok();
+    let latest = forks
+        .iter()
+        .max_by(|a, b| a.created_at.as_deref().cmp(&b.created_at.as_deref()));
+
+    if json {
+        return print_json(&serde_json::json!({
+            "project_path": project_str,
+            "server_port": port,
+            "forks_by_status": stats.forks_by_status,
+            "cost_today_usd": stats.cost_today_usd,
+            "total_cost_usd": stats.total_cost_usd,
+            "slots": pool,
+            "latest_fork": latest,
+        }));
+    }
 
-    println!("✓ Fork {fork_id} done");
-    if !summary.is_empty() {
-        println!("  Summary: {summary}");
+    let statuses = [
+        ForkStatus::Running,
+        ForkStatus::Completed,
+        ForkStatus::Failed,
+        ForkStatus::Cancelled,
+        ForkStatus::Timeout,
+    ];
+    let counts: Vec<_> = statuses
+        .iter()
+        .filter_map(|status| {
+            let count = stats.forks_by_status.get(status.as_str())?;
+            Some(format!("{count} {status}"))
+        })
+        .collect();
+
+    println!("Project: {project_str}");
+    println!("Server:  running on port {port}");
+    if let Some(pool) = pool {
+        println!("Slots:   {} running, {} queued", pool.active, pool.queued);
+    }
+    if counts.is_empty() {
+        println!("Forks:   none");
+    } else {
+        println!("Forks:   {}", counts.join(", "));
+    }
+    println!(
+        "Cost:    ${:.4} today, ${:.4} total",
+        stats.cost_today_usd, stats.total_cost_usd
+    );
+    if let Some(fork) = latest {
+        println!(
+            "Latest:  {} ({}, {})",
+            fork.fork_name.as_deref().unwrap_or("-"),
+            fork.status,
+            &fork.fork_id[..8.min(fork.fork_id.len())]
+        );
     }
 
     Ok(())
 }
 
-async fn list_events(session_filter: Option<&str>, limit: usize) -> Result<()> {
+async fn list_events(query: &EventQuery<'_>, json: bool) -> Result<()> {
     let port = server::ensure_server_running()?;
     let project_path = get_project_path()?;
     let project_str = project_path.to_string_lossy();
 
-    let events = get_events_from_server(port, &project_str, None, limit).await?;
+    let events = get_events_from_server(port, &project_str, query).await?;
+
+    if json {
+        return print_json(&events);
+    }
 
     if events.is_empty() {
         println!("No events found.");
@@ -840,24 +3256,13 @@ async fn list_events(session_filter: Option<&str>, limit: usize) -> Result<()> {
     println!("{}", "-".repeat(70));
 
     for event in events {
-        // Apply session filter if provided
-        if let Some(filter) = session_filter {
-            if let Some(ref sid) = event.session_id {
-                if !sid.starts_with(filter) {
-                    continue;
-                }
-            } else {
-                continue;
-            }
-        }
-
         let uuid = event.uuid.as_deref().unwrap_or("-");
-        let uuid_short = if uuid.len() > 8 { &uuid[..8] } else { uuid };
+        let uuid_short = truncate_chars(uuid, 8);
 
         let role = event.role.as_deref().unwrap_or("-");
         let msg = event.message.as_deref().unwrap_or("-");
-        let msg_short = if msg.len() > 35 {
-            format!("{}...", &msg[..32])
+        let msg_short = if msg.chars().count() > 35 {
+            format!("{}...", truncate_chars(msg, 32))
         } else {
             msg.to_string()
         };
@@ -1,6 +1,7 @@
 //! CLI argument parsing module.
 
 mod args;
+mod cascade;
 mod commands;
 
 pub use args::Cli;
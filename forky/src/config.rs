@@ -0,0 +1,191 @@
+//! User configuration loaded from `~/.forky/config.toml`.
+//!
+//! Every setting is optional and sits underneath the command line:
+//! a flag passed explicitly always wins over the config file.
+//!
+//! ```toml
+//! default_model = "sonnet"
+//! worktree = true
+//! server_port = 58231
+//! claude_path = "/usr/local/bin/claude"
+//! auth_token = "secret"
+//! log_max_bytes = 10485760
+//! daily_budget_usd = 5.0
+//! notify = true
+//! webhook_url = "https://example.com/forky"
+//! broadcast_buffer = 1000
+//! worktree_root = "/fast-disk/forky-worktrees"
+//! max_concurrent = 4
+//! copy_env = ["PATH", "HOME", "ANTHROPIC_*"]
+//!
+//! [names]
+//! first_names = ["Ada", "Grace"]
+//! suffixes_comma = ["Platform Team"]
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Config file name inside `~/.forky`.
+const CONFIG_FILE: &str = "config.toml";
+
+/// Environment variable that overrides `auth_token`.
+const AUTH_TOKEN_ENV: &str = "FORKY_AUTH_TOKEN";
+
+/// Environment variable that overrides `worktree_root`.
+const WORKTREE_ROOT_ENV: &str = "FORKY_WORKTREE_ROOT";
+
+/// Environment variable that overrides `name_seed`.
+const NAME_SEED_ENV: &str = "FORKY_NAME_SEED";
+
+/// Defaults applied underneath command-line arguments.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Model used when `--model` is not given.
+    pub default_model: Option<String>,
+    /// Run every fork in a git worktree (`--no-worktree` opts out).
+    pub worktree: bool,
+    /// Directory new worktrees are created in (default `~/.forky/worktrees`).
+    pub worktree_root: Option<PathBuf>,
+    /// Port the server listens on.
+    pub server_port: Option<u16>,
+    /// Path to the `claude` binary.
+    pub claude_path: Option<String>,
+    /// Bearer token required by (and sent to) the server.
+    pub auth_token: Option<String>,
+    /// Refuse to start new forks once a project has spent this much today.
+    pub daily_budget_usd: Option<f64>,
+    /// Show a desktop notification when a fork finishes.
+    pub notify: bool,
+    /// URL the server POSTs to whenever a fork finishes.
+    pub webhook_url: Option<String>,
+    /// Forks allowed to run at once; further forks queue for a slot.
+    pub max_concurrent: Option<usize>,
+    /// Live events buffered per dashboard client before it has to re-fetch.
+    pub broadcast_buffer: Option<usize>,
+    /// Cap on each fork's transcript log in `~/.forky/logs`.
+    pub log_max_bytes: Option<u64>,
+    /// Start forks from an empty environment, copying only these variables.
+    pub copy_env: Option<Vec<String>>,
+    /// Custom word lists for generated fork names.
+    pub names: NameLists,
+    /// Give every new fork the same generated name, e.g. for a demo recording.
+    pub name_seed: Option<u64>,
+}
+
+/// Word lists for fork names. An empty list falls back to the built-in one.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NameLists {
+    pub first_names: Vec<String>,
+    /// Suffixes joined with a space ("Gandalf the Deprecated").
+    pub suffixes_space: Vec<String>,
+    /// Suffixes joined with a comma ("Greg, CPA").
+    pub suffixes_comma: Vec<String>,
+    /// Suffixes joined with a dash ("Greg—Terms and Conditions Apply").
+    pub suffixes_dash: Vec<String>,
+    /// Suffixes joined with "of the" ("Greg of the Merge Conflict").
+    pub suffixes_of_the: Vec<String>,
+}
+
+impl Config {
+    /// Load `~/.forky/config.toml`, falling back to defaults if it doesn't exist.
+    pub fn load() -> Result<Self> {
+        let Some(home) = dirs::home_dir() else {
+            return Ok(Self::default());
+        };
+        Self::load_from(&home.join(".forky").join(CONFIG_FILE))
+    }
+
+    /// Load a config file, falling back to defaults if it doesn't exist.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+/// Get the server auth token from `FORKY_AUTH_TOKEN` or the config file.
+pub fn auth_token() -> Option<String> {
+    std::env::var(AUTH_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or_else(|| Config::load().ok()?.auth_token)
+}
+
+/// Get the worktree root from `FORKY_WORKTREE_ROOT` or the config file.
+pub fn worktree_root() -> Option<PathBuf> {
+    std::env::var_os(WORKTREE_ROOT_ENV)
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Config::load().ok()?.worktree_root)
+}
+
+/// Get the fork name seed from `FORKY_NAME_SEED` or the config file.
+///
+/// Names are generated by the server, so the variable has to be set where
+/// the server starts.
+pub fn name_seed() -> Option<u64> {
+    std::env::var(NAME_SEED_ENV)
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .or_else(|| Config::load().ok()?.name_seed)
+}
+
+/// The process-wide HTTP client, sending the auth token if one is configured.
+///
+/// Built once so every request shares a connection pool and keep-alive
+/// connections to the local server.
+pub fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(token) = auth_token() {
+            if let Ok(value) = reqwest::header::HeaderValue::from_str(&format!("Bearer {token}")) {
+                headers.insert(reqwest::header::AUTHORIZATION, value);
+            }
+        }
+
+        reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn missing_file_is_default() {
+        let dir = tempdir().unwrap();
+        let config = Config::load_from(&dir.path().join(CONFIG_FILE)).unwrap();
+        assert!(config.default_model.is_none());
+        assert!(!config.worktree);
+    }
+
+    #[test]
+    fn parses_partial_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, "default_model = \"sonnet\"\nserver_port = 6000\n").unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.default_model.as_deref(), Some("sonnet"));
+        assert_eq!(config.server_port, Some(6000));
+        assert!(!config.worktree);
+        assert!(config.claude_path.is_none());
+    }
+}
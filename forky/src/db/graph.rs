@@ -6,7 +6,7 @@
 //! - Forks, Sessions, Jobs are also entities with their respective labels
 //! - Relationships form a navigable graph of Claude conversations
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -15,8 +15,9 @@ use manifoldb_core::{Edge, Entity, EntityId, Value};
 use manifoldb_graph::store::{EdgeStore, IdGenerator, NodeStore};
 use manifoldb_storage::backends::RedbEngine;
 use manifoldb_storage::{StorageEngine, Transaction};
+use serde::{Deserialize, Serialize};
 
-use crate::claude::ClaudeEvent;
+use crate::claude::{ClaudeEvent, ToolResult};
 
 /// Edge type for parent-child event relationships (sub-agent nesting).
 pub const EDGE_CHILD_OF: &str = "CHILD_OF";
@@ -30,6 +31,12 @@ pub const EDGE_HAS_SESSION: &str = "HAS_SESSION";
 /// Edge type for fork-to-job relationships.
 pub const EDGE_HAS_JOB: &str = "HAS_JOB";
 
+/// Edge type linking a retried fork to the fork it re-runs.
+pub const EDGE_RETRY_OF: &str = "RETRY_OF";
+
+/// Edge type linking a fork to the fork (or session) it was forked from.
+pub const EDGE_FORKED_FROM: &str = "FORKED_FROM";
+
 /// Edge type for session-to-event relationships.
 pub const EDGE_HAS_EVENT: &str = "HAS_EVENT";
 
@@ -45,14 +52,182 @@ pub const LABEL_SESSION: &str = "Session";
 /// Label for job entities.
 pub const LABEL_JOB: &str = "Job";
 
+/// A node in a fork's conversation tree.
+///
+/// The root node stands for the fork itself (`event_type` "fork"); its
+/// children are the top-level events, and sub-agent events nest beneath the
+/// event holding the `tool_use` that spawned them.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventNode {
+    pub uuid: Option<String>,
+    pub event_type: String,
+    pub role: Option<String>,
+    pub message: Option<String>,
+    pub created_at: Option<String>,
+    pub children: Vec<Self>,
+}
+
+/// Lifecycle status of a fork, stored as a string on the Fork entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForkStatus {
+    Running,
+    Completed,
+    Failed,
+    /// Stopped on purpose by the user.
+    #[serde(alias = "interrupted")]
+    Cancelled,
+    /// Killed after running past its time limit.
+    Timeout,
+}
+
+impl ForkStatus {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Timeout => "timeout",
+        }
+    }
+
+    /// Whether the fork has stopped running, for better or worse.
+    pub const fn is_finished(self) -> bool {
+        !matches!(self, Self::Running)
+    }
+}
+
+impl std::str::FromStr for ForkStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            // Older forks were marked "interrupted" by the dashboard
+            "cancelled" | "interrupted" => Ok(Self::Cancelled),
+            "timeout" => Ok(Self::Timeout),
+            other => anyhow::bail!("Unknown fork status: {other}"),
+        }
+    }
+}
+
+impl std::fmt::Display for ForkStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a fork was launched, kept on the Fork entity so it can be re-run.
+#[derive(Debug, Clone, Default)]
+pub struct ForkLaunch {
+    /// The task message the fork was started with.
+    pub description: Option<String>,
+    pub model: Option<String>,
+    pub worktree: bool,
+    /// Where the fork's git worktree was created, if it has one.
+    pub worktree_path: Option<String>,
+    /// Branch checked out in the worktree.
+    pub worktree_branch: Option<String>,
+    /// Commit the worktree branch started from.
+    pub worktree_base: Option<String>,
+    /// AI CLI the fork runs on; `None` means claude.
+    pub ai_provider: Option<String>,
+    /// `fork_id` of the fork this one retries.
+    pub retry_of: Option<String>,
+    /// Free-form labels for filtering forks (e.g. "bugfix").
+    pub tags: Vec<String>,
+}
+
+/// Tags stored on a Fork entity, as a JSON array in its `tags` property.
+pub fn fork_tags(entity: &Entity) -> Vec<String> {
+    match entity.properties.get("tags") {
+        Some(Value::String(json)) => serde_json::from_str(json).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Details recorded alongside a fork status change.
+#[derive(Debug, Clone, Default)]
+pub struct ForkUpdate {
+    pub session_id: Option<String>,
+    /// Why the fork ended up in this status (e.g. timeout).
+    pub reason: Option<String>,
+    /// Subtype of the final result event (e.g. `error_max_turns`).
+    pub result_subtype: Option<String>,
+    /// Model the fork actually ran on, replacing the one it was launched with.
+    pub model: Option<String>,
+    /// What the fork reported it did, from `forky done`.
+    pub summary: Option<String>,
+}
+
 /// Graph database for Forky using ManifoldDB.
 pub struct GraphDatabase {
     engine: Arc<RedbEngine>,
+    /// Database file, needed to reopen it after compaction. `None` when the
+    /// database only lives in memory.
+    path: Option<PathBuf>,
+    /// Set when compaction couldn't reopen the file. The engine is then an
+    /// empty in-memory stand-in and the database must not be used.
+    closed: bool,
     id_gen: IdGenerator,
     /// Index: tool_use_id -> EntityId of the event containing it
     tool_use_index: HashMap<String, EntityId>,
     /// Index: event uuid -> EntityId
     uuid_index: HashMap<String, EntityId>,
+    /// Index: `session_id` -> `EntityId`s of its events
+    session_index: HashMap<String, Vec<EntityId>>,
+    /// Index: `fork_id` -> `EntityId` of its Fork node
+    fork_index: HashMap<String, EntityId>,
+    /// Index: `tool_use_id` not seen yet -> events waiting to link to it, with the edge type
+    pending_links: HashMap<String, Vec<(EntityId, &'static str)>>,
+}
+
+/// In-memory indexes rebuilt from the database at open time.
+struct Indexes {
+    tool_use: HashMap<String, EntityId>,
+    uuid: HashMap<String, EntityId>,
+    session: HashMap<String, Vec<EntityId>>,
+    fork: HashMap<String, EntityId>,
+    pending_links: HashMap<String, Vec<(EntityId, &'static str)>>,
+}
+
+/// Index changes made inside a write transaction, applied once it commits so
+/// a rolled-back batch leaves the indexes untouched.
+#[derive(Default)]
+struct IndexUpdates {
+    uuid: HashMap<String, EntityId>,
+    session: Vec<(String, EntityId)>,
+    tool_use: HashMap<String, EntityId>,
+    /// Links waiting for a `tool_use` that is still missing.
+    pending_added: Vec<(String, EntityId, &'static str)>,
+    /// `tool_use_ids` whose waiting links were created.
+    pending_resolved: HashSet<String>,
+}
+
+impl IndexUpdates {
+    /// Apply the changes to the database's indexes.
+    fn apply(self, db: &mut GraphDatabase) {
+        db.uuid_index.extend(self.uuid);
+        for (session_id, entity_id) in self.session {
+            db.session_index
+                .entry(session_id)
+                .or_default()
+                .push(entity_id);
+        }
+        db.tool_use_index.extend(self.tool_use);
+        for tool_id in &self.pending_resolved {
+            db.pending_links.remove(tool_id);
+        }
+        for (tool_id, entity_id, edge_type) in self.pending_added {
+            db.pending_links
+                .entry(tool_id)
+                .or_default()
+                .push((entity_id, edge_type));
+        }
+    }
 }
 
 impl GraphDatabase {
@@ -92,31 +267,45 @@ impl GraphDatabase {
     pub fn open_at(path: &PathBuf) -> Result<Self> {
         let engine = RedbEngine::open(path)
             .with_context(|| format!("Failed to open database at {}", path.display()))?;
+        Self::with_engine(engine, Some(path.clone()))
+    }
+
+    /// Open an empty database that lives only in memory.
+    #[cfg(test)]
+    pub fn open_memory() -> Result<Self> {
+        let engine = RedbEngine::in_memory().context("Failed to open in-memory database")?;
+        Self::with_engine(engine, None)
+    }
 
+    fn with_engine(engine: RedbEngine, path: Option<PathBuf>) -> Result<Self> {
         let id_gen = IdGenerator::new();
 
         // Build indexes by scanning existing data
-        let (tool_use_index, uuid_index) = Self::build_indexes(&engine, &id_gen)?;
+        let indexes = Self::build_indexes(&engine, &id_gen)?;
 
         Ok(Self {
             engine: Arc::new(engine),
+            path,
+            closed: false,
             id_gen,
-            tool_use_index,
-            uuid_index,
+            tool_use_index: indexes.tool_use,
+            uuid_index: indexes.uuid,
+            session_index: indexes.session,
+            fork_index: indexes.fork,
+            pending_links: indexes.pending_links,
         })
     }
 
     /// Build indexes from existing data.
-    fn build_indexes(
-        engine: &RedbEngine,
-        _id_gen: &IdGenerator,
-    ) -> Result<(HashMap<String, EntityId>, HashMap<String, EntityId>)> {
+    fn build_indexes(engine: &RedbEngine, _id_gen: &IdGenerator) -> Result<Indexes> {
         let mut tool_use_index = HashMap::new();
         let mut uuid_index = HashMap::new();
+        let mut session_index: HashMap<String, Vec<EntityId>> = HashMap::new();
+        let mut links: Vec<(String, EntityId, &'static str)> = Vec::new();
 
         // Scan all Event entities
         let tx = engine.begin_read()?;
-        let event_ids = NodeStore::find_by_label(&tx, &LABEL_EVENT.into())?;
+        let event_ids = Self::event_ids(&tx)?;
 
         for entity_id in event_ids {
             if let Some(entity) = NodeStore::get(&tx, entity_id)? {
@@ -125,6 +314,14 @@ impl GraphDatabase {
                     uuid_index.insert(uuid.clone(), entity_id);
                 }
 
+                // Index by session_id
+                if let Some(Value::String(session_id)) = entity.properties.get("session_id") {
+                    session_index
+                        .entry(session_id.clone())
+                        .or_default()
+                        .push(entity_id);
+                }
+
                 // Index by tool_use_ids
                 if let Some(Value::String(tool_ids_json)) = entity.properties.get("tool_use_ids") {
                     if let Ok(ids) = serde_json::from_str::<Vec<String>>(tool_ids_json) {
@@ -133,20 +330,135 @@ impl GraphDatabase {
                         }
                     }
                 }
+
+                for (tool_id, edge_type) in Self::link_targets(&entity) {
+                    links.push((tool_id, entity_id, edge_type));
+                }
+            }
+        }
+
+        // Index forks by fork_id
+        let mut fork_index = HashMap::new();
+        for entity_id in NodeStore::find_by_label(&tx, &LABEL_FORK.into())? {
+            if let Some(entity) = NodeStore::get(&tx, entity_id)? {
+                if let Some(Value::String(fork_id)) = entity.properties.get("fork_id") {
+                    fork_index.insert(fork_id.clone(), entity_id);
+                }
+            }
+        }
+
+        // Links whose tool_use never arrived are still waiting for it
+        let mut pending_links: HashMap<String, Vec<(EntityId, &'static str)>> = HashMap::new();
+        for (tool_id, entity_id, edge_type) in links {
+            if !tool_use_index.contains_key(&tool_id) {
+                pending_links
+                    .entry(tool_id)
+                    .or_default()
+                    .push((entity_id, edge_type));
             }
         }
 
-        Ok((tool_use_index, uuid_index))
+        Ok(Indexes {
+            tool_use: tool_use_index,
+            uuid: uuid_index,
+            session: session_index,
+            fork: fork_index,
+            pending_links,
+        })
+    }
+
+    /// The `tool_use` IDs a stored event links to, with the edge type of each link.
+    fn link_targets(entity: &Entity) -> Vec<(String, &'static str)> {
+        let mut targets = Vec::new();
+        if let Some(Value::String(parent_id)) = entity.properties.get("parent_tool_use_id") {
+            targets.push((parent_id.clone(), EDGE_CHILD_OF));
+        }
+        if let Some(Value::String(results_json)) = entity.properties.get("tool_results") {
+            if let Ok(results) = serde_json::from_str::<Vec<ToolResult>>(results_json) {
+                for result in results {
+                    targets.push((result.tool_use_id, EDGE_RESPONDS_TO));
+                }
+            }
+        }
+        targets
     }
 
     /// Store a Claude event as a graph entity.
     ///
     /// Creates an Event entity and links it to its parent if `parent_tool_use_id` is set.
+    /// An event whose uuid is already stored is not stored again; its existing
+    /// entity is returned instead.
     pub fn store_event(&mut self, event: &ClaudeEvent, fork_id: Option<&str>) -> Result<EntityId> {
-        let mut tx = self.engine.begin_write()?;
+        let engine = Arc::clone(&self.engine);
+        let mut tx = engine.begin_write()?;
+        let mut updates = IndexUpdates::default();
+        if let Some(entity_id) = self.stored_event_id(&tx, &updates, event)? {
+            return Ok(entity_id);
+        }
+        let entity_id = self.insert_event(&mut tx, &mut updates, event, fork_id)?;
+        tx.commit()?;
+        updates.apply(self);
+        Ok(entity_id)
+    }
+
+    /// Store a batch of events in a single write transaction.
+    ///
+    /// An event can link to a parent that appears earlier in the same batch.
+    /// If anything fails, nothing from the batch is committed and the indexes
+    /// are left as they were. Events whose uuid is already stored (e.g. from a
+    /// retried batch) are skipped and get `None`.
+    pub fn store_events(
+        &mut self,
+        events: &[(ClaudeEvent, Option<&str>)],
+    ) -> Result<Vec<Option<EntityId>>> {
+        let engine = Arc::clone(&self.engine);
+        let mut tx = engine.begin_write()?;
+
+        let mut updates = IndexUpdates::default();
+        let mut entity_ids = Vec::with_capacity(events.len());
+        for (event, fork_id) in events {
+            let entity_id = if self.stored_event_id(&tx, &updates, event)?.is_some() {
+                None
+            } else {
+                Some(self.insert_event(&mut tx, &mut updates, event, *fork_id)?)
+            };
+            entity_ids.push(entity_id);
+        }
+
+        tx.commit()?;
+        updates.apply(self);
+        Ok(entity_ids)
+    }
 
+    /// The entity already holding an event with this event's uuid, if any.
+    fn stored_event_id<T: Transaction>(
+        &self,
+        tx: &T,
+        updates: &IndexUpdates,
+        event: &ClaudeEvent,
+    ) -> Result<Option<EntityId>> {
+        let Some(&entity_id) = event
+            .uuid
+            .as_ref()
+            .and_then(|uuid| updates.uuid.get(uuid).or_else(|| self.uuid_index.get(uuid)))
+        else {
+            return Ok(None);
+        };
+        // The index can point at an entity from a batch that was rolled back
+        Ok(NodeStore::get(tx, entity_id)?.map(|_| entity_id))
+    }
+
+    /// Create an Event entity and its edges inside an open transaction,
+    /// recording the index changes in `updates`.
+    fn insert_event<T: Transaction>(
+        &self,
+        tx: &mut T,
+        updates: &mut IndexUpdates,
+        event: &ClaudeEvent,
+        fork_id: Option<&str>,
+    ) -> Result<EntityId> {
         // Create the event entity
-        let entity = NodeStore::create(&mut tx, &self.id_gen, |id| {
+        let entity = NodeStore::create(tx, &self.id_gen, |id| {
             let mut e = Entity::new(id).with_label(LABEL_EVENT);
 
             // Fork ID
@@ -206,7 +518,7 @@ impl GraphDatabase {
                 e = e.with_property("tool_results", Value::String(tool_results_json));
             }
 
-            // Metrics (cost is useful, token counts are not)
+            // Metrics
             if let Some(cost) = event.cost_usd {
                 e = e.with_property("cost_usd", Value::Float(cost));
             }
@@ -219,6 +531,17 @@ impl GraphDatabase {
             if let Some(turns) = event.num_turns {
                 e = e.with_property("num_turns", Value::Int(i64::from(turns)));
             }
+            if let Some(ref usage) = event.usage {
+                let tokens = [
+                    ("input_tokens", usage.input_tokens),
+                    ("output_tokens", usage.output_tokens),
+                    ("cache_read_tokens", usage.cache_read_tokens),
+                    ("cache_creation_tokens", usage.cache_creation_tokens),
+                ];
+                for (key, count) in tokens {
+                    e = e.with_property(key, Value::Int(i64::try_from(count).unwrap_or(i64::MAX)));
+                }
+            }
 
             // Tool use IDs (for indexing children)
             if !event.tool_use_ids.is_empty() {
@@ -226,6 +549,9 @@ impl GraphDatabase {
                 e = e.with_property("tool_use_ids", Value::String(ids_json));
             }
 
+            // Timestamp for ordering and time-range queries
+            e = e.with_property("created_at", Value::String(event.created_at().to_rfc3339()));
+
             // Store raw JSON
             let raw_json = serde_json::to_string(&event.raw).unwrap_or_default();
             e = e.with_property("raw", Value::String(raw_json));
@@ -237,44 +563,136 @@ impl GraphDatabase {
 
         // Update indexes
         if let Some(ref uuid) = event.uuid {
-            self.uuid_index.insert(uuid.clone(), entity_id);
+            updates.uuid.insert(uuid.clone(), entity_id);
+        }
+        if let Some(ref session_id) = event.session_id {
+            updates.session.push((session_id.clone(), entity_id));
         }
         for tool_id in &event.tool_use_ids {
-            self.tool_use_index.insert(tool_id.clone(), entity_id);
+            updates.tool_use.insert(tool_id.clone(), entity_id);
         }
 
-        // Create edge to parent if parent_tool_use_id is set (sub-agent nesting)
-        if let Some(ref parent_tool_id) = event.parent_tool_use_id {
-            if let Some(&parent_entity_id) = self.tool_use_index.get(parent_tool_id) {
-                // Create CHILD_OF edge from this event to parent
-                EdgeStore::create(
-                    &mut tx,
-                    &self.id_gen,
-                    entity_id,
-                    parent_entity_id,
-                    EDGE_CHILD_OF,
-                    |id| Edge::new(id, entity_id, parent_entity_id, EDGE_CHILD_OF),
-                )?;
+        self.link_event(tx, updates, event, entity_id)?;
+
+        Ok(entity_id)
+    }
+
+    /// Create the edges between a new event and the events it refers to,
+    /// leaving links to a `tool_use` that is not stored yet pending.
+    fn link_event<T: Transaction>(
+        &self,
+        tx: &mut T,
+        updates: &mut IndexUpdates,
+        event: &ClaudeEvent,
+        entity_id: EntityId,
+    ) -> Result<()> {
+        // CHILD_OF links a sub-agent event to the event that spawned it;
+        // RESPONDS_TO links a tool_result to its tool_use
+        let targets = event
+            .parent_tool_use_id
+            .iter()
+            .map(|parent_id| (parent_id, EDGE_CHILD_OF))
+            .chain(
+                event
+                    .tool_results
+                    .iter()
+                    .map(|result| (&result.tool_use_id, EDGE_RESPONDS_TO)),
+            );
+        for (tool_id, edge_type) in targets {
+            let target = updates
+                .tool_use
+                .get(tool_id)
+                .or_else(|| self.tool_use_index.get(tool_id));
+            if let Some(&target_entity_id) = target {
+                self.link(tx, entity_id, target_entity_id, edge_type)?;
+            } else {
+                // Interleaved streams can deliver an event before the tool_use it refers to
+                updates
+                    .pending_added
+                    .push((tool_id.clone(), entity_id, edge_type));
             }
-            // If parent not found yet, that's OK - we'll link later or leave unlinked
         }
 
-        // Create RESPONDS_TO edges for tool_results (links to the tool_use events)
-        for tool_result in &event.tool_results {
-            if let Some(&target_entity_id) = self.tool_use_index.get(&tool_result.tool_use_id) {
-                EdgeStore::create(
-                    &mut tx,
-                    &self.id_gen,
-                    entity_id,
-                    target_entity_id,
-                    EDGE_RESPONDS_TO,
-                    |id| Edge::new(id, entity_id, target_entity_id, EDGE_RESPONDS_TO),
-                )?;
+        // Link events that arrived before this one, earlier in this batch or not
+        for tool_id in &event.tool_use_ids {
+            let mut waiting = Vec::new();
+            if updates.pending_resolved.insert(tool_id.clone()) {
+                waiting.extend(
+                    self.pending_links
+                        .get(tool_id)
+                        .into_iter()
+                        .flatten()
+                        .copied(),
+                );
+            }
+            updates.pending_added.retain(|(id, source, edge_type)| {
+                if id == tool_id {
+                    waiting.push((*source, *edge_type));
+                }
+                id != tool_id
+            });
+            for (source_entity_id, edge_type) in waiting {
+                self.link(tx, source_entity_id, entity_id, edge_type)?;
             }
         }
 
+        Ok(())
+    }
+
+    /// Create an edge from one event to another.
+    fn link<T: Transaction>(
+        &self,
+        tx: &mut T,
+        source: EntityId,
+        target: EntityId,
+        edge_type: &'static str,
+    ) -> Result<()> {
+        EdgeStore::create(tx, &self.id_gen, source, target, edge_type, |id| {
+            Edge::new(id, source, target, edge_type)
+        })?;
+        Ok(())
+    }
+
+    /// Create missing `CHILD_OF` and `RESPONDS_TO` edges for events whose
+    /// `tool_use` is now stored.
+    ///
+    /// Events stored before their `tool_use` are linked as it arrives, but data
+    /// written by older versions may still have unlinked events. Returns the
+    /// number of edges created.
+    pub fn relink_orphans(&self) -> Result<usize> {
+        let engine = Arc::clone(&self.engine);
+        let mut tx = engine.begin_write()?;
+
+        let mut orphans = Vec::new();
+        for entity_id in Self::event_ids(&tx)? {
+            let Some(entity) = NodeStore::get(&tx, entity_id)? else {
+                continue;
+            };
+            let targets = Self::link_targets(&entity);
+            if targets.is_empty() {
+                continue;
+            }
+
+            let edges = EdgeStore::get_outgoing(&tx, entity_id)?;
+            for (tool_id, edge_type) in targets {
+                let Some(&target_entity_id) = self.tool_use_index.get(&tool_id) else {
+                    continue;
+                };
+                let linked = edges.iter().any(|edge| {
+                    edge.edge_type == edge_type.into() && edge.target == target_entity_id
+                });
+                if !linked {
+                    orphans.push((entity_id, target_entity_id, edge_type));
+                }
+            }
+        }
+
+        for &(source, target, edge_type) in &orphans {
+            self.link(&mut tx, source, target, edge_type)?;
+        }
         tx.commit()?;
-        Ok(entity_id)
+
+        Ok(orphans.len())
     }
 
     /// Get an event by its UUID.
@@ -289,21 +707,53 @@ impl GraphDatabase {
 
     /// Get events for a session.
     pub fn get_events_for_session(&self, session_id: &str) -> Result<Vec<Entity>> {
-        let tx = self.engine.begin_read()?;
-        let event_ids = NodeStore::find_by_label(&tx, &LABEL_EVENT.into())?;
+        let Some(entity_ids) = self.session_index.get(session_id) else {
+            return Ok(Vec::new());
+        };
 
+        let tx = self.engine.begin_read()?;
         let mut events = Vec::new();
+        for &entity_id in entity_ids {
+            if let Some(entity) = NodeStore::get(&tx, entity_id)? {
+                events.push(entity);
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Count events per `fork_id`.
+    pub fn count_events_by_fork(&self) -> Result<HashMap<String, usize>> {
+        let tx = self.engine.begin_read()?;
+        let event_ids = Self::event_ids(&tx)?;
+
+        let mut counts = HashMap::new();
         for entity_id in event_ids {
             if let Some(entity) = NodeStore::get(&tx, entity_id)? {
-                if let Some(Value::String(sid)) = entity.properties.get("session_id") {
-                    if sid == session_id {
-                        events.push(entity);
-                    }
+                if let Some(Value::String(fid)) = entity.properties.get("fork_id") {
+                    *counts.entry(fid.clone()).or_insert(0) += 1;
                 }
             }
         }
 
-        Ok(events)
+        Ok(counts)
+    }
+
+    /// Count the events belonging to a single fork.
+    pub fn count_events_for_fork(&self, fork_id: &str) -> Result<usize> {
+        let tx = self.engine.begin_read()?;
+        let mut count = 0;
+        NodeStore::for_each(&tx, |entity| {
+            let in_fork = matches!(
+                entity.properties.get("fork_id"),
+                Some(Value::String(fid)) if fid == fork_id
+            );
+            if in_fork && entity.labels.iter().any(|l| l.as_str() == LABEL_EVENT) {
+                count += 1;
+            }
+            true
+        })?;
+        Ok(count)
     }
 
     /// Get child events (events that have this event as parent via tool_use_id).
@@ -325,6 +775,84 @@ impl GraphDatabase {
         Ok(children)
     }
 
+    /// Build the nested conversation tree for a fork by following `CHILD_OF` edges.
+    pub fn get_event_tree(&self, fork_id: &str) -> Result<EventNode> {
+        let tx = self.engine.begin_read()?;
+
+        let mut events: HashMap<u64, Entity> = HashMap::new();
+        for entity_id in Self::event_ids(&tx)? {
+            if let Some(entity) = NodeStore::get(&tx, entity_id)? {
+                if let Some(Value::String(fid)) = entity.properties.get("fork_id") {
+                    if fid == fork_id {
+                        events.insert(entity_id.as_u64(), entity);
+                    }
+                }
+            }
+        }
+
+        // Map each parent to its children; events whose parent isn't in this fork are roots
+        let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+        let mut roots = Vec::new();
+        for entity in events.values() {
+            let parent = EdgeStore::get_outgoing(&tx, entity.id)?
+                .into_iter()
+                .find(|edge| {
+                    edge.edge_type == EDGE_CHILD_OF.into()
+                        && events.contains_key(&edge.target.as_u64())
+                })
+                .map(|edge| edge.target.as_u64());
+            match parent {
+                Some(parent) => children.entry(parent).or_default().push(entity.id.as_u64()),
+                None => roots.push(entity.id.as_u64()),
+            }
+        }
+
+        let mut visited = HashSet::new();
+        Ok(EventNode {
+            uuid: None,
+            event_type: "fork".to_string(),
+            role: None,
+            message: None,
+            created_at: None,
+            children: Self::build_event_nodes(roots, &events, &children, &mut visited),
+        })
+    }
+
+    /// Recursively turn event ids into tree nodes, ordered by creation time.
+    fn build_event_nodes(
+        mut ids: Vec<u64>,
+        events: &HashMap<u64, Entity>,
+        children: &HashMap<u64, Vec<u64>>,
+        visited: &mut HashSet<u64>,
+    ) -> Vec<EventNode> {
+        let get_str = |id: &u64, key: &str| -> Option<String> {
+            events[id].properties.get(key).and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                _ => None,
+            })
+        };
+
+        ids.sort_by_key(|id| (get_str(id, "created_at"), *id));
+
+        let mut nodes = Vec::new();
+        for id in ids {
+            // Guard against malformed edges forming a cycle
+            if !visited.insert(id) {
+                continue;
+            }
+            let child_ids = children.get(&id).cloned().unwrap_or_default();
+            nodes.push(EventNode {
+                uuid: get_str(&id, "uuid"),
+                event_type: get_str(&id, "type").unwrap_or_else(|| "unknown".to_string()),
+                role: get_str(&id, "role"),
+                message: get_str(&id, "message"),
+                created_at: get_str(&id, "created_at"),
+                children: Self::build_event_nodes(child_ids, events, children, visited),
+            });
+        }
+        nodes
+    }
+
     /// Create a Fork entity.
     pub fn create_fork(
         &mut self,
@@ -332,9 +860,25 @@ impl GraphDatabase {
         parent_session_id: Option<&str>,
         status: &str,
         fork_name: Option<&str>,
+        launch: &ForkLaunch,
     ) -> Result<EntityId> {
         let mut tx = self.engine.begin_write()?;
 
+        // Resolve the retried fork first so a bad id fails before anything is written
+        let retried_entity_id = match launch.retry_of {
+            Some(ref original) => {
+                let found = self.find_fork(&tx, original)?.map(|entity| entity.id);
+                Some(found.with_context(|| format!("Fork not found: {original}"))?)
+            }
+            None => None,
+        };
+
+        // An unknown parent (e.g. a plain interactive session) just gets no edge
+        let origin_entity_id = match parent_session_id {
+            Some(pid) => Self::find_session_owner(&tx, pid)?,
+            None => None,
+        };
+
         let entity = NodeStore::create(&mut tx, &self.id_gen, |id| {
             let mut e = Entity::new(id)
                 .with_label(LABEL_FORK)
@@ -351,55 +895,169 @@ impl GraphDatabase {
                 e = e.with_property("fork_name", Value::String(name.to_string()));
             }
 
+            if let Some(ref description) = launch.description {
+                e = e.with_property("description", Value::String(description.clone()));
+            }
+            if let Some(ref model) = launch.model {
+                e = e.with_property("model", Value::String(model.clone()));
+            }
+            e = e.with_property("worktree", Value::Bool(launch.worktree));
+            if let Some(ref path) = launch.worktree_path {
+                e = e.with_property("worktree_path", Value::String(path.clone()));
+            }
+            if let Some(ref branch) = launch.worktree_branch {
+                e = e.with_property("worktree_branch", Value::String(branch.clone()));
+            }
+            if let Some(ref base) = launch.worktree_base {
+                e = e.with_property("worktree_base", Value::String(base.clone()));
+            }
+            let provider = launch.ai_provider.as_deref().unwrap_or("claude");
+            e = e.with_property("ai_provider", Value::String(provider.to_string()));
+            if !launch.tags.is_empty() {
+                let tags_json = serde_json::to_string(&launch.tags).unwrap_or_default();
+                e = e.with_property("tags", Value::String(tags_json));
+            }
+
             e
         })?;
 
+        if let Some(retried_entity_id) = retried_entity_id {
+            EdgeStore::create(
+                &mut tx,
+                &self.id_gen,
+                entity.id,
+                retried_entity_id,
+                EDGE_RETRY_OF,
+                |id| Edge::new(id, entity.id, retried_entity_id, EDGE_RETRY_OF),
+            )?;
+        }
+
+        if let Some(origin_entity_id) = origin_entity_id {
+            EdgeStore::create(
+                &mut tx,
+                &self.id_gen,
+                entity.id,
+                origin_entity_id,
+                EDGE_FORKED_FROM,
+                |id| Edge::new(id, entity.id, origin_entity_id, EDGE_FORKED_FROM),
+            )?;
+        }
+
         tx.commit()?;
+        self.fork_index.insert(fork_id.to_string(), entity.id);
         Ok(entity.id)
     }
 
-    /// Update fork status and optionally set session_id.
-    pub fn update_fork_status(
-        &self,
-        fork_id: &str,
-        status: &str,
-        session_id: Option<&str>,
-    ) -> Result<()> {
-        let tx = self.engine.begin_read()?;
-        let fork_ids = NodeStore::find_by_label(&tx, &LABEL_FORK.into())?;
-        drop(tx);
+    /// Find the fork that ran `session_id`, or the bare Session entity if no
+    /// fork owns it.
+    fn find_session_owner<T: Transaction>(tx: &T, session_id: &str) -> Result<Option<EntityId>> {
+        let wanted = Value::String(session_id.to_string());
 
-        for entity_id in fork_ids {
-            let tx = self.engine.begin_read()?;
-            if let Some(entity) = NodeStore::get(&tx, entity_id)? {
-                if let Some(Value::String(fid)) = entity.properties.get("fork_id") {
-                    if fid == fork_id {
-                        drop(tx);
-                        let mut tx = self.engine.begin_write()?;
-                        let mut updated = entity.clone();
-                        updated
-                            .properties
-                            .insert("status".to_string(), Value::String(status.to_string()));
-                        if let Some(sid) = session_id {
-                            updated
-                                .properties
-                                .insert("session_id".to_string(), Value::String(sid.to_string()));
-                        }
-                        if status == "completed" || status == "failed" {
-                            updated.properties.insert(
-                                "completed_at".to_string(),
-                                Value::String(chrono::Utc::now().to_rfc3339()),
-                            );
-                        }
-                        NodeStore::update(&mut tx, &updated)?;
-                        tx.commit()?;
-                        return Ok(());
-                    }
+        for entity_id in NodeStore::find_by_label(tx, &LABEL_FORK.into())? {
+            if let Some(entity) = NodeStore::get(tx, entity_id)? {
+                if entity.properties.get("session_id") == Some(&wanted) {
+                    return Ok(Some(entity_id));
                 }
             }
         }
 
-        Ok(())
+        for entity_id in NodeStore::find_by_label(tx, &LABEL_SESSION.into())? {
+            let Some(entity) = NodeStore::get(tx, entity_id)? else {
+                continue;
+            };
+            if entity.properties.get("session_id") != Some(&wanted) {
+                continue;
+            }
+            let owner = EdgeStore::get_incoming(tx, entity_id)?
+                .into_iter()
+                .find(|edge| edge.edge_type == EDGE_HAS_SESSION.into())
+                .map(|edge| edge.source);
+            return Ok(Some(owner.unwrap_or(entity_id)));
+        }
+
+        Ok(None)
+    }
+
+    /// Walk `FORKED_FROM` edges from a fork back to its root.
+    ///
+    /// Returns the fork itself first, then each ancestor in turn. The last
+    /// entry may be a Session if the root session isn't owned by a fork.
+    /// Empty if the fork doesn't exist.
+    pub fn get_fork_lineage(&self, fork_id: &str) -> Result<Vec<Entity>> {
+        let Some(fork) = self.get_fork(fork_id)? else {
+            return Ok(Vec::new());
+        };
+
+        let tx = self.engine.begin_read()?;
+        let mut visited = HashSet::from([fork.id]);
+        let mut lineage = vec![fork];
+
+        loop {
+            let current = lineage[lineage.len() - 1].id;
+            let parent = EdgeStore::get_outgoing(&tx, current)?
+                .into_iter()
+                .find(|edge| edge.edge_type == EDGE_FORKED_FROM.into())
+                .map(|edge| edge.target);
+
+            let Some(parent) = parent.filter(|id| visited.insert(*id)) else {
+                break;
+            };
+            let Some(entity) = NodeStore::get(&tx, parent)? else {
+                break;
+            };
+            lineage.push(entity);
+        }
+
+        Ok(lineage)
+    }
+
+    /// Update fork status and optionally set `session_id` and a status reason.
+    ///
+    /// Returns the status the fork had before, or `None` if there is no such
+    /// fork.
+    pub fn update_fork_status(
+        &self,
+        fork_id: &str,
+        status: ForkStatus,
+        update: &ForkUpdate,
+    ) -> Result<Option<ForkStatus>> {
+        // Read and write in one transaction so concurrent updates can't
+        // overwrite each other's properties
+        let mut tx = self.engine.begin_write()?;
+        let Some(mut updated) = self.find_fork(&tx, fork_id)? else {
+            return Ok(None);
+        };
+        let previous = match updated.properties.get("status") {
+            Some(Value::String(s)) => s.parse().unwrap_or(ForkStatus::Running),
+            _ => ForkStatus::Running,
+        };
+
+        updated
+            .properties
+            .insert("status".to_string(), Value::String(status.to_string()));
+        let details = [
+            ("session_id", &update.session_id),
+            ("reason", &update.reason),
+            ("result_subtype", &update.result_subtype),
+            ("model", &update.model),
+            ("summary", &update.summary),
+        ];
+        for (key, value) in details {
+            if let Some(value) = value {
+                updated
+                    .properties
+                    .insert(key.to_string(), Value::String(value.clone()));
+            }
+        }
+        if status.is_finished() {
+            updated.properties.insert(
+                "completed_at".to_string(),
+                Value::String(chrono::Utc::now().to_rfc3339()),
+            );
+        }
+        NodeStore::update(&mut tx, &updated)?;
+        tx.commit()?;
+        Ok(Some(previous))
     }
 
     /// List all forks.
@@ -441,19 +1099,88 @@ impl GraphDatabase {
     /// Get a fork by its fork_id.
     pub fn get_fork(&self, fork_id: &str) -> Result<Option<Entity>> {
         let tx = self.engine.begin_read()?;
-        let fork_ids = NodeStore::find_by_label(&tx, &LABEL_FORK.into())?;
+        self.find_fork(&tx, fork_id)
+    }
 
-        for entity_id in fork_ids {
+    /// Find the Fork node with this `fork_id` within `tx`.
+    fn find_fork<T: Transaction>(&self, tx: &T, fork_id: &str) -> Result<Option<Entity>> {
+        match self.fork_index.get(fork_id) {
+            Some(&entity_id) => Ok(NodeStore::get(tx, entity_id)?),
+            None => Ok(None),
+        }
+    }
+
+    /// IDs of the forks whose `fork_id` starts with `prefix`, sorted.
+    pub fn fork_ids_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .fork_index
+            .keys()
+            .filter(|id| id.starts_with(prefix))
+            .cloned()
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Delete a fork along with all of its events.
+    ///
+    /// Removes every Event whose `fork_id` matches, the edges touching those
+    /// events, and finally the Fork node itself, all in one write transaction.
+    /// Returns the number of deleted events, or `None` if the fork doesn't exist.
+    pub fn delete_fork(&mut self, fork_id: &str) -> Result<Option<usize>> {
+        let mut tx = self.engine.begin_write()?;
+
+        let Some(fork) = self.find_fork(&tx, fork_id)? else {
+            return Ok(None);
+        };
+
+        let mut doomed = vec![fork.id];
+        for entity_id in Self::event_ids(&tx)? {
             if let Some(entity) = NodeStore::get(&tx, entity_id)? {
                 if let Some(Value::String(fid)) = entity.properties.get("fork_id") {
                     if fid == fork_id {
-                        return Ok(Some(entity));
+                        doomed.push(entity_id);
                     }
                 }
             }
         }
+        let doomed_ids: HashSet<u64> = doomed.iter().map(|id| id.as_u64()).collect();
 
-        Ok(None)
+        // Remove edges first. Edges between two doomed nodes are covered by the
+        // source's outgoing list, so incoming edges only need the external ones.
+        for &entity_id in &doomed {
+            for edge in EdgeStore::get_outgoing(&tx, entity_id)? {
+                EdgeStore::delete(&mut tx, edge.id)?;
+            }
+            for edge in EdgeStore::get_incoming(&tx, entity_id)? {
+                if !doomed_ids.contains(&edge.source.as_u64()) {
+                    EdgeStore::delete(&mut tx, edge.id)?;
+                }
+            }
+        }
+
+        for &entity_id in &doomed {
+            NodeStore::delete(&mut tx, entity_id)?;
+        }
+
+        tx.commit()?;
+
+        // Drop index entries that would now dangle
+        self.fork_index.remove(fork_id);
+        self.uuid_index
+            .retain(|_, id| !doomed_ids.contains(&id.as_u64()));
+        self.tool_use_index
+            .retain(|_, id| !doomed_ids.contains(&id.as_u64()));
+        self.session_index.retain(|_, ids| {
+            ids.retain(|id| !doomed_ids.contains(&id.as_u64()));
+            !ids.is_empty()
+        });
+        self.pending_links.retain(|_, links| {
+            links.retain(|(id, _)| !doomed_ids.contains(&id.as_u64()));
+            !links.is_empty()
+        });
+
+        Ok(Some(doomed.len() - 1))
     }
 
     /// Get the most recent fork.
@@ -464,9 +1191,8 @@ impl GraphDatabase {
 
     /// Mark a fork as read.
     pub fn mark_fork_read(&self, fork_id: &str) -> Result<()> {
-        if let Some(entity) = self.get_fork(fork_id)? {
-            let mut tx = self.engine.begin_write()?;
-            let mut updated = entity;
+        let mut tx = self.engine.begin_write()?;
+        if let Some(mut updated) = self.find_fork(&tx, fork_id)? {
             updated
                 .properties
                 .insert("read".to_string(), Value::Bool(true));
@@ -478,31 +1204,28 @@ impl GraphDatabase {
 
     /// Mark all forks as read.
     pub fn mark_all_forks_read(&self) -> Result<usize> {
-        let forks = self.list_forks()?;
+        let mut tx = self.engine.begin_write()?;
         let mut count = 0;
 
-        for entity in forks {
-            if let Some(Value::Bool(false)) = entity.properties.get("read") {
-                let mut tx = self.engine.begin_write()?;
-                let mut updated = entity;
+        for entity_id in NodeStore::find_by_label(&tx, &LABEL_FORK.into())? {
+            let Some(mut updated) = NodeStore::get(&tx, entity_id)? else {
+                continue;
+            };
+            if matches!(updated.properties.get("read"), Some(Value::Bool(false))) {
                 updated
                     .properties
                     .insert("read".to_string(), Value::Bool(true));
                 NodeStore::update(&mut tx, &updated)?;
-                tx.commit()?;
                 count += 1;
             }
         }
 
+        tx.commit()?;
         Ok(count)
     }
 
     /// Create a Session entity linked to a fork.
-    pub fn create_session(
-        &mut self,
-        session_id: &str,
-        fork_entity_id: EntityId,
-    ) -> Result<EntityId> {
+    pub fn create_session(&self, session_id: &str, fork_entity_id: EntityId) -> Result<EntityId> {
         let mut tx = self.engine.begin_write()?;
 
         let entity = NodeStore::create(&mut tx, &self.id_gen, |id| {
@@ -528,7 +1251,7 @@ impl GraphDatabase {
 
     /// Create a Job entity linked to a fork.
     pub fn create_job(
-        &mut self,
+        &self,
         job_id: &str,
         description: &str,
         fork_entity_id: EntityId,
@@ -558,6 +1281,121 @@ impl GraphDatabase {
         Ok(entity.id)
     }
 
+    /// List all sessions with the `fork_id` of the fork that owns each.
+    pub fn list_sessions(&self) -> Result<Vec<(Entity, Option<String>)>> {
+        self.list_fork_children(LABEL_SESSION, EDGE_HAS_SESSION)
+    }
+
+    /// List all jobs with the `fork_id` of the fork that owns each.
+    pub fn list_jobs(&self) -> Result<Vec<(Entity, Option<String>)>> {
+        self.list_fork_children(LABEL_JOB, EDGE_HAS_JOB)
+    }
+
+    /// List entities with `label`, resolving their owning fork through
+    /// incoming `edge_type` edges.
+    fn list_fork_children(
+        &self,
+        label: &'static str,
+        edge_type: &'static str,
+    ) -> Result<Vec<(Entity, Option<String>)>> {
+        let tx = self.engine.begin_read()?;
+
+        let mut children = Vec::new();
+        for entity_id in NodeStore::find_by_label(&tx, &label.into())? {
+            let Some(entity) = NodeStore::get(&tx, entity_id)? else {
+                continue;
+            };
+
+            let mut fork_id = None;
+            for edge in EdgeStore::get_incoming(&tx, entity_id)? {
+                if edge.edge_type != edge_type.into() {
+                    continue;
+                }
+                if let Some(fork) = NodeStore::get(&tx, edge.source)? {
+                    if let Some(Value::String(fid)) = fork.properties.get("fork_id") {
+                        fork_id = Some(fid.clone());
+                        break;
+                    }
+                }
+            }
+
+            children.push((entity, fork_id));
+        }
+
+        Ok(children)
+    }
+
+    /// Collect the ids of every Event entity.
+    ///
+    /// `NodeStore::find_by_label` ends its scan range by bumping the last byte
+    /// of the label hash, and the hash of "Event" ends in 0xFF, so it never
+    /// finds anything. Walk the entities and check labels instead.
+    fn event_ids<T: Transaction>(tx: &T) -> Result<Vec<EntityId>> {
+        let mut ids = Vec::new();
+        NodeStore::for_each(tx, |entity| {
+            if entity.labels.iter().any(|l| l.as_str() == LABEL_EVENT) {
+                ids.push(entity.id);
+            }
+            true
+        })?;
+        Ok(ids)
+    }
+
+    /// Compact the database file, returning how many bytes were reclaimed.
+    ///
+    /// Deleted forks leave free pages behind; this rewrites the file without
+    /// them. The database is closed while it runs, so no other handle to the
+    /// engine may be alive (callers hold the only reference, e.g. under the
+    /// server's write lock). An in-memory database has nothing to reclaim.
+    ///
+    /// If the file can't be reopened afterwards the database is left
+    /// [closed](Self::is_closed) and should be dropped.
+    pub fn compact(&mut self) -> Result<u64> {
+        let Some(path) = self.path.clone() else {
+            return Ok(0);
+        };
+        let size_before = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to stat {}", path.display()))?
+            .len();
+
+        // Park an in-memory engine so the file can be closed and reopened
+        let parked = Arc::new(RedbEngine::in_memory()?);
+        let engine = std::mem::replace(&mut self.engine, parked);
+        let engine = match Arc::try_unwrap(engine) {
+            Ok(engine) => engine,
+            Err(engine) => {
+                self.engine = engine;
+                anyhow::bail!("Database is in use; try again once other requests finish");
+            }
+        };
+        drop(engine);
+
+        let compacted = redb::Database::open(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut db| db.compact().map_err(anyhow::Error::from))
+            .with_context(|| format!("Failed to compact {}", path.display()));
+
+        // Reopen whether or not compaction worked
+        match RedbEngine::open(&path) {
+            Ok(engine) => self.engine = Arc::new(engine),
+            Err(e) => {
+                self.closed = true;
+                return Err(e).with_context(|| format!("Failed to reopen {}", path.display()));
+            }
+        }
+        compacted?;
+
+        let size_after = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to stat {}", path.display()))?
+            .len();
+        Ok(size_before.saturating_sub(size_after))
+    }
+
+    /// Whether a failed compaction left the database without its file.
+    pub const fn is_closed(&self) -> bool {
+        self.closed
+    }
+
     /// Get the underlying engine for advanced queries.
     pub fn engine(&self) -> &Arc<RedbEngine> {
         &self.engine
@@ -570,9 +1408,7 @@ mod tests {
     use tempfile::tempdir;
 
     fn test_db() -> GraphDatabase {
-        let dir = tempdir().unwrap();
-        let path = dir.path().join("test.redb");
-        GraphDatabase::open_at(&path).unwrap()
+        GraphDatabase::open_memory().unwrap()
     }
 
     #[test]
@@ -589,12 +1425,216 @@ mod tests {
         assert!(retrieved.is_some());
     }
 
+    #[test]
+    fn test_store_event_is_idempotent() {
+        let mut db = test_db();
+        let event =
+            ClaudeEvent::parse(r#"{"type":"assistant","uuid":"dup","session_id":"sess-1"}"#)
+                .unwrap();
+
+        let first = db.store_event(&event, Some("fork-1")).unwrap();
+        assert_eq!(db.store_event(&event, Some("fork-1")).unwrap(), first);
+
+        // A retried batch skips what was already stored
+        let batch = vec![(event.clone(), Some("fork-1")), (event, Some("fork-1"))];
+        assert_eq!(db.store_events(&batch).unwrap(), vec![None, None]);
+
+        assert_eq!(db.get_events_for_session("sess-1").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_session_index() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.redb");
+
+        {
+            let mut db = GraphDatabase::open_at(&path).unwrap();
+            for raw in [
+                r#"{"type":"user","uuid":"e1","session_id":"sess-1"}"#,
+                r#"{"type":"assistant","uuid":"e2","session_id":"sess-1"}"#,
+                r#"{"type":"assistant","uuid":"e3","session_id":"sess-2"}"#,
+            ] {
+                db.store_event(&ClaudeEvent::parse(raw).unwrap(), None)
+                    .unwrap();
+            }
+            assert_eq!(db.get_events_for_session("sess-1").unwrap().len(), 2);
+        }
+
+        // The index is rebuilt when the database is reopened
+        let db = GraphDatabase::open_at(&path).unwrap();
+        assert_eq!(db.get_events_for_session("sess-1").unwrap().len(), 2);
+        assert_eq!(db.get_events_for_session("sess-2").unwrap().len(), 1);
+        assert!(db.get_events_for_session("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fork_index() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.redb");
+
+        {
+            let mut db = GraphDatabase::open_at(&path).unwrap();
+            db.create_fork("fork-a", None, "running", None, &ForkLaunch::default())
+                .unwrap();
+            db.create_fork("fork-b", None, "running", None, &ForkLaunch::default())
+                .unwrap();
+        }
+
+        // The index is rebuilt when the database is reopened
+        let mut db = GraphDatabase::open_at(&path).unwrap();
+        assert!(db.get_fork("fork-a").unwrap().is_some());
+        assert!(db.get_fork("missing").unwrap().is_none());
+
+        db.delete_fork("fork-a").unwrap();
+        assert!(db.get_fork("fork-a").unwrap().is_none());
+        assert!(db.get_fork("fork-b").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_fork_ids_with_prefix() {
+        let mut db = test_db();
+        for fork_id in ["abc-1", "abc-2", "abd-1"] {
+            db.create_fork(fork_id, None, "running", None, &ForkLaunch::default())
+                .unwrap();
+        }
+
+        assert_eq!(db.fork_ids_with_prefix("abc"), vec!["abc-1", "abc-2"]);
+        assert_eq!(db.fork_ids_with_prefix("abd"), vec!["abd-1"]);
+        assert!(db.fork_ids_with_prefix("x").is_empty());
+    }
+
+    #[test]
+    fn test_store_events_links_within_batch() {
+        let mut db = test_db();
+        let batch: Vec<(ClaudeEvent, Option<&str>)> = [
+            r#"{"type":"assistant","uuid":"spawn","session_id":"s","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Task","input":{}}]}}"#,
+            r#"{"type":"assistant","uuid":"sub","session_id":"s","parent_tool_use_id":"toolu_1"}"#,
+        ]
+        .into_iter()
+        .map(|raw| (ClaudeEvent::parse(raw).unwrap(), Some("fork-1")))
+        .collect();
+
+        let ids: Vec<EntityId> = db
+            .store_events(&batch)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(ids.len(), 2);
+
+        let children = db.get_child_events(ids[0]).unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].id, ids[1]);
+    }
+
+    #[test]
+    fn test_rolled_back_insert_leaves_indexes() {
+        let db = test_db();
+        let sub = ClaudeEvent::parse(
+            r#"{"type":"assistant","uuid":"sub","session_id":"s","parent_tool_use_id":"toolu_1"}"#,
+        )
+        .unwrap();
+
+        let engine = Arc::clone(&db.engine);
+        let mut tx = engine.begin_write().unwrap();
+        let mut updates = IndexUpdates::default();
+        db.insert_event(&mut tx, &mut updates, &sub, Some("fork-1"))
+            .unwrap();
+        drop(tx);
+
+        assert!(db.uuid_index.is_empty());
+        assert!(db.session_index.is_empty());
+        assert!(db.pending_links.is_empty());
+        assert!(db.get_event_by_uuid("sub").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_child_before_parent() {
+        let spawn = r#"{"type":"assistant","uuid":"spawn","session_id":"s","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Task","input":{}}]}}"#;
+        let sub =
+            r#"{"type":"assistant","uuid":"sub","session_id":"s","parent_tool_use_id":"toolu_1"}"#;
+
+        let mut db = test_db();
+        let child = db
+            .store_event(&ClaudeEvent::parse(sub).unwrap(), Some("fork-1"))
+            .unwrap();
+        let parent = db
+            .store_event(&ClaudeEvent::parse(spawn).unwrap(), Some("fork-1"))
+            .unwrap();
+
+        let children = db.get_child_events(parent).unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].id, child);
+        assert_eq!(db.relink_orphans().unwrap(), 0);
+
+        // Data from before deferred linking: the child was never linked
+        let mut db = test_db();
+        db.store_event(&ClaudeEvent::parse(sub).unwrap(), Some("fork-1"))
+            .unwrap();
+        db.pending_links.clear();
+        let parent = db
+            .store_event(&ClaudeEvent::parse(spawn).unwrap(), Some("fork-1"))
+            .unwrap();
+        assert!(db.get_child_events(parent).unwrap().is_empty());
+
+        assert_eq!(db.relink_orphans().unwrap(), 1);
+        assert_eq!(db.get_child_events(parent).unwrap().len(), 1);
+        assert_eq!(db.relink_orphans().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_tool_result_before_tool_use() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.redb");
+
+        let result = {
+            let mut db = GraphDatabase::open_at(&path).unwrap();
+            let raw = r#"{"type":"user","uuid":"res","session_id":"s","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"ok"}]}}"#;
+            db.store_event(&ClaudeEvent::parse(raw).unwrap(), Some("fork-1"))
+                .unwrap()
+        };
+
+        // The pending link survives a reopen
+        let mut db = GraphDatabase::open_at(&path).unwrap();
+        let raw = r#"{"type":"assistant","uuid":"use","session_id":"s","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Read","input":{}}]}}"#;
+        let tool_use = db
+            .store_event(&ClaudeEvent::parse(raw).unwrap(), Some("fork-1"))
+            .unwrap();
+
+        let tx = db.engine.begin_read().unwrap();
+        let edges = EdgeStore::get_outgoing(&tx, result).unwrap();
+        assert!(edges
+            .iter()
+            .any(|e| e.edge_type == EDGE_RESPONDS_TO.into() && e.target == tool_use));
+        drop(tx);
+        assert_eq!(db.relink_orphans().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_count_events_by_fork() {
+        let mut db = test_db();
+        for (uuid, fork) in [("e1", "fork-1"), ("e2", "fork-1"), ("e3", "fork-2")] {
+            let raw = format!(r#"{{"type":"assistant","uuid":"{uuid}","session_id":"s"}}"#);
+            db.store_event(&ClaudeEvent::parse(&raw).unwrap(), Some(fork))
+                .unwrap();
+        }
+
+        assert_eq!(db.count_events_for_fork("fork-1").unwrap(), 2);
+        assert_eq!(db.count_events_for_fork("fork-2").unwrap(), 1);
+    }
+
     #[test]
     fn test_fork_lifecycle() {
         let mut db = test_db();
 
         let fork_id = db
-            .create_fork("fork-1", Some("parent-sess"), "running")
+            .create_fork(
+                "fork-1",
+                Some("parent-sess"),
+                "running",
+                None,
+                &ForkLaunch::default(),
+            )
             .unwrap();
         assert!(fork_id.as_u64() > 0);
 
@@ -604,12 +1644,265 @@ mod tests {
             Some(&Value::String("running".to_string()))
         );
 
-        db.update_fork_status("fork-1", "completed", None).unwrap();
+        let update = ForkUpdate {
+            session_id: Some("sess-1".to_string()),
+            result_subtype: Some("success".to_string()),
+            model: Some("claude-haiku-4-5".to_string()),
+            ..ForkUpdate::default()
+        };
+        let previous = db
+            .update_fork_status("fork-1", ForkStatus::Completed, &update)
+            .unwrap();
+        assert_eq!(previous, Some(ForkStatus::Running));
 
         let fork = db.get_fork("fork-1").unwrap().unwrap();
         assert_eq!(
             fork.properties.get("status"),
             Some(&Value::String("completed".to_string()))
         );
+        assert_eq!(
+            fork.properties.get("result_subtype"),
+            Some(&Value::String("success".to_string()))
+        );
+        assert_eq!(
+            fork.properties.get("session_id"),
+            Some(&Value::String("sess-1".to_string()))
+        );
+        assert_eq!(
+            fork.properties.get("model"),
+            Some(&Value::String("claude-haiku-4-5".to_string()))
+        );
+        assert!(!fork.properties.contains_key("reason"));
+
+        let previous = db
+            .update_fork_status("fork-1", ForkStatus::Completed, &ForkUpdate::default())
+            .unwrap();
+        assert_eq!(previous, Some(ForkStatus::Completed));
+        let missing = db
+            .update_fork_status(
+                "no-such-fork",
+                ForkStatus::Completed,
+                &ForkUpdate::default(),
+            )
+            .unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn test_fork_status_round_trip() {
+        for status in [
+            ForkStatus::Running,
+            ForkStatus::Completed,
+            ForkStatus::Failed,
+            ForkStatus::Cancelled,
+            ForkStatus::Timeout,
+        ] {
+            assert_eq!(status.as_str().parse::<ForkStatus>().unwrap(), status);
+        }
+        assert_eq!(
+            "interrupted".parse::<ForkStatus>().unwrap(),
+            ForkStatus::Cancelled
+        );
+        assert!("bogus".parse::<ForkStatus>().is_err());
+    }
+
+    #[test]
+    fn test_retry_fork() {
+        let mut db = test_db();
+        let launch = ForkLaunch {
+            description: Some("fix the build".to_string()),
+            model: Some("sonnet".to_string()),
+            worktree: true,
+            worktree_path: Some("/tmp/worktrees/fork-1".to_string()),
+            worktree_branch: Some("forky/fork-1".to_string()),
+            worktree_base: None,
+            ai_provider: None,
+            retry_of: None,
+            tags: vec!["bugfix".to_string(), "ci".to_string()],
+        };
+        let original = db
+            .create_fork("fork-1", None, "failed", None, &launch)
+            .unwrap();
+
+        let fork = db.get_fork("fork-1").unwrap().unwrap();
+        assert_eq!(
+            fork.properties.get("description"),
+            Some(&Value::String("fix the build".to_string()))
+        );
+        assert_eq!(fork.properties.get("worktree"), Some(&Value::Bool(true)));
+        assert_eq!(
+            fork.properties.get("worktree_branch"),
+            Some(&Value::String("forky/fork-1".to_string()))
+        );
+        assert_eq!(
+            fork.properties.get("ai_provider"),
+            Some(&Value::String("claude".to_string()))
+        );
+        assert_eq!(fork_tags(&fork), ["bugfix", "ci"]);
+
+        let retry = ForkLaunch {
+            retry_of: Some("fork-1".to_string()),
+            ..launch
+        };
+        let retried = db
+            .create_fork("fork-2", None, "running", None, &retry)
+            .unwrap();
+
+        let tx = db.engine.begin_read().unwrap();
+        let edges = EdgeStore::get_outgoing(&tx, retried).unwrap();
+        assert!(edges
+            .iter()
+            .any(|e| e.edge_type == EDGE_RETRY_OF.into() && e.target == original));
+        drop(tx);
+
+        let missing = ForkLaunch {
+            retry_of: Some("nope".to_string()),
+            ..ForkLaunch::default()
+        };
+        assert!(db
+            .create_fork("fork-3", None, "running", None, &missing)
+            .is_err());
+        assert!(db.get_fork("fork-3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_fork_lineage() {
+        let mut db = test_db();
+        let launch = ForkLaunch::default();
+
+        db.create_fork("fork-1", Some("interactive"), "running", None, &launch)
+            .unwrap();
+        let update = ForkUpdate {
+            session_id: Some("sess-1".to_string()),
+            ..ForkUpdate::default()
+        };
+        db.update_fork_status("fork-1", ForkStatus::Completed, &update)
+            .unwrap();
+
+        db.create_fork("fork-2", Some("sess-1"), "running", None, &launch)
+            .unwrap();
+        let update = ForkUpdate {
+            session_id: Some("sess-2".to_string()),
+            ..ForkUpdate::default()
+        };
+        db.update_fork_status("fork-2", ForkStatus::Completed, &update)
+            .unwrap();
+
+        db.create_fork("fork-3", Some("sess-2"), "running", None, &launch)
+            .unwrap();
+
+        let fork_ids: Vec<_> = db
+            .get_fork_lineage("fork-3")
+            .unwrap()
+            .iter()
+            .map(|e| e.properties.get("fork_id").cloned())
+            .collect();
+        assert_eq!(
+            fork_ids,
+            ["fork-3", "fork-2", "fork-1"].map(|id| Some(Value::String(id.to_string())))
+        );
+
+        assert_eq!(db.get_fork_lineage("fork-1").unwrap().len(), 1);
+        assert!(db.get_fork_lineage("nope").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compact() {
+        let dir = tempdir().unwrap();
+        let mut db = GraphDatabase::open_at(&dir.path().join("test.redb")).unwrap();
+
+        let launch = ForkLaunch {
+            description: Some("x".repeat(64 * 1024)),
+            ..ForkLaunch::default()
+        };
+        for i in 0..50 {
+            db.create_fork(&format!("fork-{i}"), None, "completed", None, &launch)
+                .unwrap();
+        }
+        for i in 1..50 {
+            db.delete_fork(&format!("fork-{i}")).unwrap();
+        }
+
+        assert!(db.compact().unwrap() > 0);
+        assert!(db.get_fork("fork-0").unwrap().is_some());
+        assert!(db.get_fork("fork-1").unwrap().is_none());
+
+        // Still writable after reopening
+        db.create_fork("fork-new", None, "running", None, &ForkLaunch::default())
+            .unwrap();
+        assert!(db.get_fork("fork-new").unwrap().is_some());
+
+        // Another live handle on the engine blocks compaction
+        let engine = db.engine().clone();
+        assert!(db.compact().is_err());
+        drop(engine);
+        assert!(db.get_fork("fork-0").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_delete_fork() {
+        let mut db = test_db();
+        db.create_fork("fork-1", None, "running", None, &ForkLaunch::default())
+            .unwrap();
+
+        let event =
+            ClaudeEvent::parse(r#"{"type":"assistant","uuid":"evt-1","session_id":"sess-1"}"#)
+                .unwrap();
+        db.store_event(&event, Some("fork-1")).unwrap();
+
+        assert_eq!(db.delete_fork("fork-1").unwrap(), Some(1));
+        assert!(db.get_fork("fork-1").unwrap().is_none());
+        assert!(db.get_event_by_uuid("evt-1").unwrap().is_none());
+        assert_eq!(db.delete_fork("fork-1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_event_tree() {
+        let mut db = test_db();
+        let events = [
+            r#"{"type":"user","uuid":"prompt","session_id":"s"}"#,
+            r#"{"type":"assistant","uuid":"spawn","session_id":"s","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Task","input":{}}]}}"#,
+            r#"{"type":"assistant","uuid":"sub","session_id":"s","parent_tool_use_id":"toolu_1"}"#,
+        ];
+        for raw in events {
+            let event = ClaudeEvent::parse(raw).unwrap();
+            db.store_event(&event, Some("fork-1")).unwrap();
+        }
+
+        let tree = db.get_event_tree("fork-1").unwrap();
+        assert_eq!(tree.event_type, "fork");
+        assert_eq!(tree.children.len(), 2);
+
+        let spawn = tree
+            .children
+            .iter()
+            .find(|n| n.uuid.as_deref() == Some("spawn"))
+            .unwrap();
+        assert_eq!(spawn.children.len(), 1);
+        assert_eq!(spawn.children[0].uuid.as_deref(), Some("sub"));
+
+        assert!(db.get_event_tree("fork-2").unwrap().children.is_empty());
+    }
+
+    #[test]
+    fn test_list_sessions_and_jobs() {
+        let mut db = test_db();
+        let fork = db
+            .create_fork("fork-1", None, "running", None, &ForkLaunch::default())
+            .unwrap();
+        db.create_session("sess-1", fork).unwrap();
+        db.create_job("job-1", "review", fork).unwrap();
+
+        let sessions = db.list_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].1.as_deref(), Some("fork-1"));
+
+        let jobs = db.list_jobs().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(
+            jobs[0].0.properties.get("job_id"),
+            Some(&Value::String("job-1".to_string()))
+        );
+        assert_eq!(jobs[0].1.as_deref(), Some("fork-1"));
     }
 }
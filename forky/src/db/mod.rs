@@ -5,4 +5,6 @@
 
 mod graph;
 
-pub use graph::GraphDatabase;
+pub use graph::{
+    fork_tags, EventNode, ForkLaunch, ForkStatus, ForkUpdate, GraphDatabase, LABEL_FORK,
+};
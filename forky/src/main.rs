@@ -10,19 +10,42 @@
 
 mod claude;
 mod cli;
+mod config;
 mod db;
 mod names;
+mod notifications;
 mod process;
 mod server;
 mod session;
 
 use anyhow::Result;
 use clap::Parser;
+use tracing_subscriber::EnvFilter;
 
 use cli::{execute, Cli};
 
+/// Diagnostics shown when neither `--log-level` nor `RUST_LOG` is set.
+const DEFAULT_LOG_LEVEL: &str = "warn";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    init_tracing(cli.log_level.as_deref());
     execute(cli).await
 }
+
+/// Send diagnostics to stderr, leaving stdout for command output.
+///
+/// `--log-level` wins over `RUST_LOG`; both take filter directives such as
+/// `debug` or `forky::server=debug`.
+fn init_tracing(log_level: Option<&str>) {
+    let filter = log_level
+        .map(EnvFilter::new)
+        .or_else(|| EnvFilter::try_from_default_env().ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_LOG_LEVEL));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
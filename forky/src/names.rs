@@ -4,6 +4,11 @@
 //! "Three Raccoons in a Trenchcoat, CPA".
 
 use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use crate::config::{self, Config, NameLists};
 
 const FIRST_NAMES: &[&str] = &[
     // Simple/Classic
@@ -76,22 +81,40 @@ pub struct ForkName {
     pub full_name: String,
 }
 
-/// Generate a random fork name.
+/// Generate a random fork name, or the same one every time if a name seed
+/// is configured.
 pub fn generate_name() -> ForkName {
-    let mut rng = rand::rng();
+    config::name_seed().map_or_else(
+        || generate_name_with_rng(&mut rand::rng()),
+        generate_name_seeded,
+    )
+}
+
+/// Generate the same fork name every time for a given seed.
+pub fn generate_name_seeded(seed: u64) -> ForkName {
+    generate_name_with_rng(&mut StdRng::seed_from_u64(seed))
+}
 
-    let first = FIRST_NAMES.choose(&mut rng).unwrap_or(&"Fork");
+/// Generate a fork name, using any word lists from the config file.
+pub fn generate_name_with_rng<R: Rng + ?Sized>(rng: &mut R) -> ForkName {
+    let lists = Config::load().map(|c| c.names).unwrap_or_default();
+    generate_name_from(&lists, rng)
+}
+
+/// Generate a fork name, preferring custom word lists over the built-in ones.
+pub fn generate_name_from<R: Rng + ?Sized>(lists: &NameLists, rng: &mut R) -> ForkName {
+    let first = choose(&lists.first_names, FIRST_NAMES, rng).unwrap_or("Fork");
 
     // Pick a random suffix category
-    let suffix_type = rand::random::<u8>() % 4;
-    let (separator, suffixes) = match suffix_type {
-        0 => (" ", SUFFIXES_SPACE),
-        1 => (", ", SUFFIXES_COMMA),
-        2 => ("—", SUFFIXES_DASH),
-        _ => (" of the ", SUFFIXES_OF_THE),
+    let suffix_type = rng.random_range(0..4);
+    let (separator, custom, builtin) = match suffix_type {
+        0 => (" ", &lists.suffixes_space, SUFFIXES_SPACE),
+        1 => (", ", &lists.suffixes_comma, SUFFIXES_COMMA),
+        2 => ("—", &lists.suffixes_dash, SUFFIXES_DASH),
+        _ => (" of the ", &lists.suffixes_of_the, SUFFIXES_OF_THE),
     };
 
-    let suffix = suffixes.choose(&mut rng).unwrap_or(&"the Unknown");
+    let suffix = choose(custom, builtin, rng).unwrap_or("the Unknown");
 
     ForkName {
         nickname: first.to_string(),
@@ -99,6 +122,19 @@ pub fn generate_name() -> ForkName {
     }
 }
 
+/// Pick from the custom list, or the built-in one if it's empty.
+fn choose<'a, R: Rng + ?Sized>(
+    custom: &'a [String],
+    builtin: &'a [&'a str],
+    rng: &mut R,
+) -> Option<&'a str> {
+    if custom.is_empty() {
+        builtin.choose(rng).copied()
+    } else {
+        custom.choose(rng).map(String::as_str)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,4 +146,23 @@ mod tests {
         assert!(!name.full_name.is_empty());
         assert!(name.full_name.contains(&name.nickname));
     }
+
+    #[test]
+    fn test_generate_name_seeded() {
+        let a = generate_name_seeded(42);
+        let b = generate_name_seeded(42);
+        assert_eq!(a.full_name, b.full_name);
+        assert_eq!(a.nickname, b.nickname);
+    }
+
+    #[test]
+    fn test_generate_name_from_custom_lists() {
+        let lists = NameLists {
+            first_names: vec!["Ada".to_string()],
+            ..NameLists::default()
+        };
+        let name = generate_name_from(&lists, &mut StdRng::seed_from_u64(7));
+        assert_eq!(name.nickname, "Ada");
+        assert!(name.full_name.starts_with("Ada"));
+    }
 }
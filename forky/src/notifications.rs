@@ -0,0 +1,207 @@
+//! Completion notifications left for the parent session.
+//!
+//! `forky done` appends one JSON object per line to
+//! `~/.forky/notifications/pending.txt`:
+//!
+//! ```text
+//! {"fork_id":"0193…","timestamp":"2025-01-01T12:00:00Z","summary":"Fixed the build"}
+//! ```
+//!
+//! Older versions wrote `fork_id|YYYY-MM-DD HH:MM:SS|summary` lines, which
+//! are still understood when reading.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Timestamp format of the old pipe-delimited lines.
+const LEGACY_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A fork finishing, as recorded by `forky done`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub fork_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub summary: String,
+}
+
+impl Notification {
+    /// Parse one line of the pending file, JSON or legacy pipe-delimited.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if line.starts_with('{') {
+            return serde_json::from_str(line).ok();
+        }
+
+        // Legacy: the summary is last, so any pipes in it stay in the summary
+        let mut parts = line.splitn(3, '|');
+        let fork_id = parts.next()?.to_string();
+        let timestamp = NaiveDateTime::parse_from_str(parts.next()?, LEGACY_TIMESTAMP_FORMAT)
+            .ok()?
+            .and_utc();
+        let summary = parts.next().unwrap_or_default().to_string();
+
+        Some(Self {
+            fork_id,
+            timestamp,
+            summary,
+        })
+    }
+}
+
+/// Path of the pending notifications file.
+pub fn pending_path() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .context("Could not find home directory")?
+        .join(".forky")
+        .join("notifications")
+        .join("pending.txt"))
+}
+
+/// Append a notification to the file at `path`.
+pub fn append(path: &Path, notification: &Notification) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let mut line = serde_json::to_string(notification)?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read every notification in the file at `path`, skipping unreadable lines.
+///
+/// A missing file has no notifications.
+pub fn read(path: &Path) -> Result<Vec<Notification>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+
+    Ok(contents.lines().filter_map(Notification::parse).collect())
+}
+
+/// Read and remove every notification in the file at `path`.
+///
+/// The file is moved aside before reading, so a fork finishing meanwhile
+/// starts a fresh file instead of being lost.
+pub fn take(path: &Path) -> Result<Vec<Notification>> {
+    let mut taken = path.as_os_str().to_owned();
+    taken.push(".taken");
+    let taken = PathBuf::from(taken);
+
+    match std::fs::rename(path, &taken) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to move {}", path.display()));
+        }
+    }
+
+    let notifications = read(&taken)?;
+    std::fs::remove_file(&taken)
+        .with_context(|| format!("Failed to remove {}", taken.display()))?;
+    Ok(notifications)
+}
+
+/// Keep only the latest notification for each fork, oldest first.
+pub fn latest_per_fork(notifications: Vec<Notification>) -> Vec<Notification> {
+    let mut latest: HashMap<String, Notification> = HashMap::new();
+    for notification in notifications {
+        match latest.get(&notification.fork_id) {
+            Some(existing) if existing.timestamp > notification.timestamp => {}
+            _ => {
+                latest.insert(notification.fork_id.clone(), notification);
+            }
+        }
+    }
+
+    let mut latest: Vec<_> = latest.into_values().collect();
+    latest.sort_by_key(|n| n.timestamp);
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn round_trips_summaries_with_pipes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notifications").join("pending.txt");
+        let notification = Notification {
+            fork_id: "fork-1".to_string(),
+            timestamp: Utc::now(),
+            summary: "a | b | c".to_string(),
+        };
+
+        append(&path, &notification).unwrap();
+        append(&path, &notification).unwrap();
+
+        assert_eq!(
+            read(&path).unwrap(),
+            vec![notification.clone(), notification]
+        );
+        assert!(read(&dir.path().join("missing.txt")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn take_empties_the_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("pending.txt");
+        std::fs::write(&path, "fork-1|2025-01-02 03:04:05|done\n").unwrap();
+
+        assert_eq!(take(&path).unwrap().len(), 1);
+        assert!(!path.exists());
+        assert!(take(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn keeps_latest_per_fork() {
+        let lines = [
+            "fork-1|2025-01-02 03:00:00|first",
+            "fork-2|2025-01-02 04:00:00|other",
+            "fork-1|2025-01-02 05:00:00|second",
+        ];
+        let latest = latest_per_fork(
+            lines
+                .iter()
+                .filter_map(|l| Notification::parse(l))
+                .collect(),
+        );
+
+        let summaries: Vec<_> = latest.iter().map(|n| n.summary.as_str()).collect();
+        assert_eq!(summaries, ["other", "second"]);
+    }
+
+    #[test]
+    fn reads_legacy_lines() {
+        let parsed = Notification::parse("fork-1|2025-01-02 03:04:05|did x|and y").unwrap();
+        assert_eq!(parsed.fork_id, "fork-1");
+        assert_eq!(parsed.timestamp.to_rfc3339(), "2025-01-02T03:04:05+00:00");
+        assert_eq!(parsed.summary, "did x|and y");
+
+        assert!(Notification::parse("").is_none());
+        assert!(Notification::parse("fork-1|not a time|x").is_none());
+    }
+}
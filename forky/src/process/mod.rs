@@ -7,4 +7,4 @@ mod spawn;
 mod pool;
 
 pub use spawn::{ProcessOptions, ProcessResult, ProcessOutput, spawn_process};
-pub use pool::{ProcessPool, PooledProcess};
+pub use pool::{PoolMetrics, PoolSlot, ProcessPool, PooledProcess, SlotRequest};
@@ -1,16 +1,20 @@
 //! Process pool for managing multiple concurrent processes.
 //!
 //! Provides a pool abstraction for spawning and managing multiple processes
-//! with concurrency limits and resource management.
+//! with concurrency limits, priority scheduling and resource management.
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashMap};
 use std::process::ExitStatus;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot, Semaphore};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
 
-use super::spawn::{ProcessOptions, ProcessOutput, ProcessResult};
+use super::spawn::{terminate, LineBuffer, ProcessOptions, ProcessOutput, ProcessResult};
 
 /// A unique identifier for a pooled process.
 pub type ProcessId = usize;
@@ -47,13 +51,263 @@ pub enum PoolEvent {
     Output { id: ProcessId, output: ProcessOutput },
 
     /// A process has completed.
-    Completed { id: ProcessId, success: bool },
+    Completed {
+        id: ProcessId,
+        success: bool,
+        /// Wall-clock time from spawn to exit.
+        duration: Duration,
+    },
+}
+
+/// Snapshot of pool activity.
+///
+/// Slots taken with [`ProcessPool::request_slot`] count too: a held slot is
+/// active, and is counted as completed once released.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PoolMetrics {
+    /// Processes currently running.
+    pub active: usize,
+    /// Processes waiting for a slot.
+    pub queued: usize,
+    /// Processes that finished successfully.
+    pub completed: usize,
+    /// Processes that failed, timed out or could not be spawned.
+    pub failed: usize,
+}
+
+/// Lock-free counters behind [`PoolMetrics`].
+#[derive(Debug, Default)]
+struct PoolCounters {
+    active: AtomicUsize,
+    queued: AtomicUsize,
+    completed: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl PoolCounters {
+    fn snapshot(&self) -> PoolMetrics {
+        PoolMetrics {
+            active: self.active.load(Ordering::SeqCst),
+            queued: self.queued.load(Ordering::SeqCst),
+            completed: self.completed.load(Ordering::SeqCst),
+            failed: self.failed.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Move a process from queued to active.
+    fn start(&self) {
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        self.active.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Count a process that was dropped from the queue without starting.
+    fn cancel(&self) {
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        self.failed.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Move a process from active to completed or failed.
+    fn finish(&self, success: bool) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        if success {
+            self.completed.fetch_add(1, Ordering::SeqCst);
+        } else {
+            self.failed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Priority used by [`ProcessPool::spawn`].
+pub const DEFAULT_PRIORITY: u8 = 0;
+
+/// A task waiting for a slot in the pool.
+struct Waiter {
+    priority: u8,
+    /// Arrival order, so equal priorities stay FIFO.
+    seq: u64,
+    tx: oneshot::Sender<SlotPermit>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        // Max-heap: higher priority first, then earlier arrival
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct GateState {
+    available: usize,
+    next_seq: u64,
+    waiters: BinaryHeap<Waiter>,
+}
+
+/// Priority-aware replacement for a counting semaphore.
+///
+/// Free slots are handed directly to the highest-priority waiter.
+struct PriorityGate {
+    state: Mutex<GateState>,
+}
+
+impl PriorityGate {
+    fn new(slots: usize) -> Self {
+        Self {
+            state: Mutex::new(GateState {
+                available: slots,
+                ..GateState::default()
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, GateState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wait for the slot of a waiter queued with [`enqueue`](Self::enqueue).
+    async fn acquire(self: &Arc<Self>, rx: oneshot::Receiver<SlotPermit>) -> SlotPermit {
+        // The sender is only dropped along with the gate, which we hold
+        rx.await
+            .unwrap_or_else(|_| SlotPermit { gate: self.clone() })
+    }
+
+    /// Join the queue, returning the waiter's sequence number and the
+    /// channel its slot arrives on (straight away if one is free).
+    fn enqueue(self: &Arc<Self>, priority: u8) -> (u64, oneshot::Receiver<SlotPermit>) {
+        let (tx, rx) = oneshot::channel();
+        let mut state = self.lock();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+
+        if state.available > 0 && state.waiters.is_empty() {
+            state.available -= 1;
+            // Unlock first: a rejected permit would release back into the gate
+            drop(state);
+            let _ = tx.send(SlotPermit { gate: self.clone() });
+        } else {
+            state.waiters.push(Waiter { priority, seq, tx });
+            drop(state);
+        }
+        (seq, rx)
+    }
+
+    /// Number of waiters that will be served before `seq`, or `None` if it
+    /// is no longer waiting.
+    fn position(&self, seq: u64) -> Option<usize> {
+        let state = self.lock();
+        let waiter = state.waiters.iter().find(|w| w.seq == seq)?;
+        let ahead = state.waiters.iter().filter(|w| *w > waiter).count();
+        drop(state);
+        Some(ahead)
+    }
+
+    /// Pass a freed slot to the next waiter, or return it to the pool.
+    fn release(self: &Arc<Self>) {
+        let mut state = self.lock();
+        let Some(waiter) = state.waiters.pop() else {
+            state.available += 1;
+            return;
+        };
+        drop(state);
+
+        // If the waiter has gone away, the rejected permit is dropped here
+        // and the slot moves on to the next one.
+        let _ = waiter.tx.send(SlotPermit { gate: self.clone() });
+    }
+
+    fn available(&self) -> usize {
+        self.lock().available
+    }
+
+    fn queued(&self) -> usize {
+        self.lock().waiters.len()
+    }
+}
+
+/// A held slot in the pool, released on drop.
+struct SlotPermit {
+    gate: Arc<PriorityGate>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        self.gate.release();
+    }
+}
+
+/// A queued request for a slot, for processes started outside the pool.
+///
+/// Dropping the request gives up its place in the queue.
+pub struct SlotRequest {
+    gate: Arc<PriorityGate>,
+    seq: u64,
+    rx: oneshot::Receiver<SlotPermit>,
+    counters: Arc<PoolCounters>,
+    /// Whether the slot has been taken, so dropping the request afterwards
+    /// doesn't count it as cancelled.
+    taken: bool,
+}
+
+impl SlotRequest {
+    /// Number of requests ahead of this one, or `None` once it has been
+    /// handed a slot.
+    pub fn position(&self) -> Option<usize> {
+        self.gate.position(self.seq)
+    }
+
+    /// Take the slot if it has been granted.
+    pub fn try_take(&mut self) -> Option<PoolSlot> {
+        let permit = self.rx.try_recv().ok()?;
+        self.taken = true;
+        self.counters.start();
+        Some(PoolSlot {
+            _permit: permit,
+            counters: self.counters.clone(),
+        })
+    }
+}
+
+impl Drop for SlotRequest {
+    fn drop(&mut self) {
+        if !self.taken {
+            self.counters.cancel();
+        }
+    }
+}
+
+/// A slot held outside the pool, counting against its limit until dropped.
+pub struct PoolSlot {
+    _permit: SlotPermit,
+    counters: Arc<PoolCounters>,
+}
+
+impl Drop for PoolSlot {
+    fn drop(&mut self) {
+        // The pool can't tell how the holder fared
+        self.counters.finish(true);
+    }
 }
 
 /// A pool for managing multiple concurrent processes.
 ///
 /// The pool limits concurrency and provides a unified interface for
-/// spawning and monitoring multiple processes.
+/// spawning and monitoring multiple processes. When the pool is full,
+/// waiting processes are started highest priority first.
 ///
 /// # Example
 ///
@@ -80,34 +334,44 @@ pub enum PoolEvent {
 /// }
 /// ```
 pub struct ProcessPool {
-    /// Semaphore for limiting concurrency.
-    semaphore: Arc<Semaphore>,
+    /// Priority-aware gate for limiting concurrency.
+    gate: Arc<PriorityGate>,
 
     /// Counter for generating unique process IDs.
     next_id: AtomicUsize,
 
+    /// Activity counters.
+    counters: Arc<PoolCounters>,
+
     /// Optional channel for pool-wide events.
     event_tx: Option<mpsc::Sender<PoolEvent>>,
+
+    /// Set to `true` by [`shutdown_now`](Self::shutdown_now).
+    cancel: watch::Sender<bool>,
+
+    /// Task running each queued or running process. Finished tasks are
+    /// dropped as new ones are added.
+    tasks: Mutex<Vec<(ProcessId, JoinHandle<ProcessResult>)>>,
 }
 
 impl ProcessPool {
     /// Create a new process pool with the given concurrency limit.
     pub fn new(max_concurrent: usize) -> Self {
         Self {
-            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            gate: Arc::new(PriorityGate::new(max_concurrent)),
             next_id: AtomicUsize::new(0),
+            counters: Arc::new(PoolCounters::default()),
             event_tx: None,
+            cancel: watch::Sender::new(false),
+            tasks: Mutex::new(Vec::new()),
         }
     }
 
     /// Create a pool with an event channel for monitoring all processes.
     pub fn with_events(max_concurrent: usize) -> (Self, mpsc::Receiver<PoolEvent>) {
         let (tx, rx) = mpsc::channel(1000);
-        let pool = Self {
-            semaphore: Arc::new(Semaphore::new(max_concurrent)),
-            next_id: AtomicUsize::new(0),
-            event_tx: Some(tx),
-        };
+        let mut pool = Self::new(max_concurrent);
+        pool.event_tx = Some(tx);
         (pool, rx)
     }
 
@@ -115,58 +379,92 @@ impl ProcessPool {
     ///
     /// This will block if the pool is at capacity until a slot becomes available.
     pub async fn spawn(&self, options: ProcessOptions) -> Result<PooledProcess> {
+        self.spawn_with_priority(options, DEFAULT_PRIORITY).await
+    }
+
+    /// Spawn a process in the pool with the given priority.
+    ///
+    /// When the pool is at capacity, higher-priority processes are started
+    /// before lower-priority ones; equal priorities run in submission order.
+    #[allow(
+        clippy::unused_async,
+        reason = "async like `spawn`, so callers don't change if spawning ever waits"
+    )]
+    pub async fn spawn_with_priority(
+        &self,
+        options: ProcessOptions,
+        priority: u8,
+    ) -> Result<PooledProcess> {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
-        let semaphore = self.semaphore.clone();
+        let gate = self.gate.clone();
+        let counters = self.counters.clone();
+        counters.queued.fetch_add(1, Ordering::SeqCst);
         let event_tx = self.event_tx.clone();
+        let cancel = self.cancel.subscribe();
         let buffer_size = options.buffer_size;
 
         // Create channels
         let (output_tx, output_rx) = mpsc::channel(buffer_size);
         let (result_tx, result_rx) = oneshot::channel();
 
-        // Spawn task to run the process
-        tokio::spawn(async move {
-            // Acquire semaphore permit (waits if at capacity)
-            let _permit = semaphore.acquire().await;
-
-            // Notify started
-            if let Some(ref tx) = event_tx {
-                let _ = tx.send(PoolEvent::Started { id }).await;
-            }
+        // Queue before spawning the task, so equal priorities keep the order
+        // they were submitted in
+        let (_, slot_rx) = gate.enqueue(priority);
 
-            // Run the actual process
-            match run_process_internal(options, id, output_tx.clone(), event_tx.clone()).await {
-                Ok(result) => {
-                    let success = result.success();
+        // Spawn task to run the process
+        let handle = tokio::spawn(async move {
+            // Acquire a slot (waits if at capacity), unless the pool shuts down first
+            let permit = tokio::select! {
+                permit = gate.acquire(slot_rx) => Some(permit),
+                () = cancelled(cancel.clone()) => None,
+            };
 
-                    // Notify completed
-                    if let Some(ref tx) = event_tx {
-                        let _ = tx.send(PoolEvent::Completed { id, success }).await;
-                    }
+            let (result, success) = if let Some(_permit) = permit {
+                counters.start();
 
-                    let _ = result_tx.send(result);
+                // Notify started
+                if let Some(ref tx) = event_tx {
+                    let _ = tx.send(PoolEvent::Started { id }).await;
                 }
-                Err(e) => {
-                    // Notify completed with failure
-                    if let Some(ref tx) = event_tx {
-                        let _ = tx
-                            .send(PoolEvent::Completed {
-                                id,
-                                success: false,
-                            })
-                            .await;
-                    }
 
-                    // Send a failure result
-                    let _ = result_tx.send(ProcessResult {
-                        status: <ExitStatus as ExitStatusExt>::default(),
-                        stdout: Vec::new(),
-                        stderr: vec![e.to_string()],
-                        timed_out: false,
-                    });
-                }
+                // Run the actual process
+                let (result, success) =
+                    match run_process_internal(options, id, output_tx, event_tx.clone(), cancel)
+                        .await
+                    {
+                        Ok(result) => {
+                            let success = result.success() && !result.timed_out;
+                            (result, success)
+                        }
+                        Err(e) => (failed_result(e.to_string()), false),
+                    };
+                counters.finish(success);
+                (result, success)
+            } else {
+                counters.cancel();
+                let message = "Pool shut down before the process started".to_string();
+                (failed_result(message), false)
+            };
+
+            // Notify completed
+            if let Some(ref tx) = event_tx {
+                let _ = tx
+                    .send(PoolEvent::Completed {
+                        id,
+                        success,
+                        duration: result.duration,
+                    })
+                    .await;
             }
+
+            let _ = result_tx.send(result.clone());
+            result
         });
+        let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+        // Their results already went to their `PooledProcess`
+        tasks.retain(|(_, task)| !task.is_finished());
+        tasks.push((id, handle));
+        drop(tasks);
 
         Ok(PooledProcess {
             id,
@@ -175,6 +473,23 @@ impl ProcessPool {
         })
     }
 
+    /// Queue for a slot without spawning a process through the pool.
+    ///
+    /// For processes started elsewhere (e.g. by another program) that should
+    /// still count against the pool's limit. Slots are shared fairly with
+    /// [`spawn`](Self::spawn) at the default priority.
+    pub fn request_slot(&self) -> SlotRequest {
+        self.counters.queued.fetch_add(1, Ordering::SeqCst);
+        let (seq, rx) = self.gate.enqueue(DEFAULT_PRIORITY);
+        SlotRequest {
+            gate: self.gate.clone(),
+            seq,
+            rx,
+            counters: self.counters.clone(),
+            taken: false,
+        }
+    }
+
     /// Spawn multiple processes and wait for all to complete.
     pub async fn spawn_all(
         &self,
@@ -197,24 +512,109 @@ impl ProcessPool {
         Ok(results)
     }
 
+    /// Spawn multiple processes and receive each result as soon as it finishes.
+    ///
+    /// Unlike [`spawn_all`](Self::spawn_all), a slow process doesn't hold up
+    /// the results of ones that finish after it. The receiver closes once
+    /// every process has reported. Output isn't streamed; it's only in the
+    /// results.
+    pub async fn spawn_all_streaming(
+        &self,
+        options_list: Vec<ProcessOptions>,
+    ) -> Result<mpsc::Receiver<(ProcessId, ProcessResult)>> {
+        let (tx, rx) = mpsc::channel(options_list.len().max(1));
+
+        for options in options_list {
+            let PooledProcess {
+                id,
+                output,
+                result_rx,
+            } = self.spawn(options).await?;
+            // Nobody reads the output, so don't let a full channel stall the process
+            drop(output);
+
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Ok(result) = result_rx.await {
+                    let _ = tx.send((id, result)).await;
+                }
+            });
+        }
+
+        Ok(rx)
+    }
+
+    /// Stop the pool, waiting for queued and running processes to finish.
+    ///
+    /// Taking `self` means nothing new can be spawned. Returns the result of
+    /// every process that was queued or running; processes that finished
+    /// earlier report only through their [`PooledProcess`].
+    pub async fn shutdown(self) -> HashMap<ProcessId, ProcessResult> {
+        let tasks = self
+            .tasks
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let mut results = HashMap::new();
+        for (id, handle) in tasks {
+            if let Ok(result) = handle.await {
+                results.insert(id, result);
+            }
+        }
+        results
+    }
+
+    /// Stop the pool, killing running processes and dropping queued ones
+    /// without starting them.
+    ///
+    /// Returns the same results as [`shutdown`](Self::shutdown); cancelled
+    /// processes are reported as failures.
+    pub async fn shutdown_now(self) -> HashMap<ProcessId, ProcessResult> {
+        self.cancel.send_replace(true);
+        self.shutdown().await
+    }
+
     /// Get the number of available slots in the pool.
     pub fn available_permits(&self) -> usize {
-        self.semaphore.available_permits()
+        self.gate.available()
+    }
+
+    /// Get a snapshot of running, queued and finished process counts.
+    pub fn metrics(&self) -> PoolMetrics {
+        self.counters.snapshot()
+    }
+
+    /// Get the number of processes waiting for a slot.
+    pub fn queued(&self) -> usize {
+        self.gate.queued()
     }
 }
 
-/// Internal function to run a process and stream output.
-async fn run_process_internal(
-    options: ProcessOptions,
-    id: ProcessId,
-    output_tx: mpsc::Sender<ProcessOutput>,
-    event_tx: Option<mpsc::Sender<PoolEvent>>,
-) -> Result<ProcessResult> {
+/// Resolve once the pool is shut down with [`ProcessPool::shutdown_now`].
+async fn cancelled(mut cancel: watch::Receiver<bool>) {
+    // A pool dropped without shutting down never cancels anything
+    if cancel.wait_for(|&cancelled| cancelled).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Result reported for a process that failed to spawn or never started.
+fn failed_result(message: String) -> ProcessResult {
+    ProcessResult {
+        status: <ExitStatus as ExitStatusExt>::default(),
+        stdout: Vec::new(),
+        stderr: vec![message],
+        timed_out: false,
+        truncated: false,
+        duration: Duration::ZERO,
+    }
+}
+
+/// Build the command for a pooled process, piping only the output it captures.
+fn build_command(options: &ProcessOptions) -> tokio::process::Command {
     use std::process::Stdio;
-    use tokio::io::{AsyncBufReadExt, BufReader};
-    use tokio::process::Command;
 
-    let mut cmd = Command::new(&options.program);
+    let mut cmd = tokio::process::Command::new(&options.program);
     cmd.args(&options.args);
 
     if let Some(ref dir) = options.working_dir {
@@ -246,77 +646,133 @@ async fn run_process_internal(
     }
 
     cmd.stdin(Stdio::null());
+    cmd
+}
 
-    let mut child = cmd
+/// Where a reader task sends the lines of one output pipe.
+struct OutputSink {
+    id: ProcessId,
+    output_tx: mpsc::Sender<ProcessOutput>,
+    event_tx: Option<mpsc::Sender<PoolEvent>>,
+    max_lines: Option<usize>,
+}
+
+impl OutputSink {
+    /// Forward each line of `pipe` as it arrives, returning the lines kept
+    /// for the result once the pipe closes.
+    async fn read(
+        self,
+        pipe: impl tokio::io::AsyncRead + Unpin,
+        wrap: fn(String) -> ProcessOutput,
+    ) -> LineBuffer {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut lines = LineBuffer::new(self.max_lines);
+        let mut reader = BufReader::new(pipe).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            lines.push(line.clone());
+            let _ = self.output_tx.send(wrap(line.clone())).await;
+            if let Some(ref tx) = self.event_tx {
+                let _ = tx
+                    .send(PoolEvent::Output {
+                        id: self.id,
+                        output: wrap(line),
+                    })
+                    .await;
+            }
+        }
+        lines
+    }
+}
+
+/// Internal function to run a process and stream output.
+async fn run_process_internal(
+    options: ProcessOptions,
+    id: ProcessId,
+    output_tx: mpsc::Sender<ProcessOutput>,
+    event_tx: Option<mpsc::Sender<PoolEvent>>,
+    cancel: watch::Receiver<bool>,
+) -> Result<ProcessResult> {
+    let started = Instant::now();
+    let mut child = build_command(&options)
         .spawn()
         .with_context(|| format!("Failed to spawn process: {}", options.program))?;
 
     let stdout = child.stdout.take();
     let stderr = child.stderr.take();
 
-    let mut stdout_lines = Vec::new();
-    let mut stderr_lines = Vec::new();
-
-    // Spawn stdout reader
-    let stdout_handle = if let Some(stdout) = stdout {
-        let output_tx = output_tx.clone();
-        let event_tx = event_tx.clone();
-        Some(tokio::spawn(async move {
-            let mut lines = Vec::new();
-            let mut reader = BufReader::new(stdout).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                lines.push(line.clone());
-                let _ = output_tx.send(ProcessOutput::Stdout(line.clone())).await;
-                if let Some(ref tx) = event_tx {
-                    let _ = tx
-                        .send(PoolEvent::Output {
-                            id,
-                            output: ProcessOutput::Stdout(line),
-                        })
-                        .await;
-                }
+    let max_output_lines = options.max_output_lines;
+    let mut stdout_lines = LineBuffer::default();
+    let mut stderr_lines = LineBuffer::default();
+
+    let mut stdout_handle = stdout.map(|stdout| {
+        let sink = OutputSink {
+            id,
+            output_tx: output_tx.clone(),
+            event_tx: event_tx.clone(),
+            max_lines: max_output_lines,
+        };
+        tokio::spawn(sink.read(stdout, ProcessOutput::Stdout))
+    });
+    let mut stderr_handle = stderr.map(|stderr| {
+        let sink = OutputSink {
+            id,
+            output_tx: output_tx.clone(),
+            event_tx: event_tx.clone(),
+            max_lines: max_output_lines,
+        };
+        tokio::spawn(sink.read(stderr, ProcessOutput::Stderr))
+    });
+
+    let mut timed_out = false;
+
+    // Wait for readers to complete. A finished handle is cleared so it is
+    // never polled again if the timeout fires part-way through.
+    let join_readers = async {
+        if let Some(handle) = stdout_handle.as_mut() {
+            if let Ok(lines) = handle.await {
+                stdout_lines = lines;
             }
-            lines
-        }))
-    } else {
-        None
-    };
+            stdout_handle = None;
+        }
 
-    // Spawn stderr reader
-    let stderr_handle = if let Some(stderr) = stderr {
-        let output_tx = output_tx.clone();
-        let event_tx = event_tx.clone();
-        Some(tokio::spawn(async move {
-            let mut lines = Vec::new();
-            let mut reader = BufReader::new(stderr).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                lines.push(line.clone());
-                let _ = output_tx.send(ProcessOutput::Stderr(line.clone())).await;
-                if let Some(ref tx) = event_tx {
-                    let _ = tx
-                        .send(PoolEvent::Output {
-                            id,
-                            output: ProcessOutput::Stderr(line),
-                        })
-                        .await;
-                }
+        if let Some(handle) = stderr_handle.as_mut() {
+            if let Ok(lines) = handle.await {
+                stderr_lines = lines;
             }
-            lines
-        }))
-    } else {
-        None
+            stderr_handle = None;
+        }
     };
 
-    // Wait for readers to complete
-    if let Some(handle) = stdout_handle {
-        if let Ok(lines) = handle.await {
-            stdout_lines = lines;
+    let deadline = async {
+        match options.timeout {
+            Some(duration) => tokio::time::sleep(duration).await,
+            None => std::future::pending().await,
         }
-    }
+    };
+
+    let stop = tokio::select! {
+        () = join_readers => false,
+        () = deadline => {
+            timed_out = true;
+            true
+        }
+        () = cancelled(cancel) => true,
+    };
 
-    if let Some(handle) = stderr_handle {
-        if let Ok(lines) = handle.await {
-            stderr_lines = lines;
+    if stop {
+        // Stop the process on timeout or shutdown; its pipes close and the readers finish
+        terminate(&mut child, options.graceful_timeout).await;
+
+        if let Some(handle) = stdout_handle {
+            if let Ok(lines) = handle.await {
+                stdout_lines = lines;
+            }
+        }
+        if let Some(handle) = stderr_handle {
+            if let Ok(lines) = handle.await {
+                stderr_lines = lines;
+            }
         }
     }
 
@@ -331,9 +787,11 @@ async fn run_process_internal(
 
     Ok(ProcessResult {
         status,
-        stdout: stdout_lines,
-        stderr: stderr_lines,
-        timed_out: false,
+        duration: started.elapsed(),
+        truncated: stdout_lines.truncated() || stderr_lines.truncated(),
+        stdout: stdout_lines.into_vec(),
+        stderr: stderr_lines.into_vec(),
+        timed_out,
     })
 }
 
@@ -392,6 +850,95 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_pool_spawn_all_streaming() {
+        let pool = ProcessPool::new(4);
+
+        let options = vec![
+            ProcessOptions::new("sleep").arg("1"),
+            ProcessOptions::new("echo").arg("fast"),
+        ];
+
+        let mut rx = pool.spawn_all_streaming(options).await.unwrap();
+
+        // The quick process reports first even though it was submitted second
+        let (first_id, first) = rx.recv().await.unwrap();
+        assert_eq!(first_id, 1);
+        assert_eq!(first.stdout, vec!["fast"]);
+
+        let (second_id, second) = rx.recv().await.unwrap();
+        assert_eq!(second_id, 0);
+        assert!(second.success());
+
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pool_shutdown() {
+        let pool = ProcessPool::new(1);
+
+        pool.spawn(ProcessOptions::new("echo").arg("one"))
+            .await
+            .unwrap();
+        pool.spawn(ProcessOptions::new("echo").arg("two"))
+            .await
+            .unwrap();
+
+        // Queued work still runs before shutdown returns
+        let results = pool.shutdown().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[&1].stdout, vec!["two"]);
+        assert!(results.values().all(ProcessResult::success));
+    }
+
+    #[tokio::test]
+    async fn test_pool_forgets_finished_tasks() {
+        let pool = ProcessPool::new(2);
+
+        for _ in 0..3 {
+            let proc = pool.spawn(ProcessOptions::new("true")).await.unwrap();
+            assert!(proc.wait().await.unwrap().success());
+        }
+        let running = pool
+            .spawn(ProcessOptions::new("sleep").arg("0.1"))
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = pool
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, vec![running.id]);
+    }
+
+    #[tokio::test]
+    async fn test_pool_shutdown_now() {
+        let pool = ProcessPool::new(1);
+
+        let running = pool
+            .spawn(ProcessOptions::new("sleep").arg("10"))
+            .await
+            .unwrap();
+        pool.spawn(ProcessOptions::new("echo").arg("queued"))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let start = Instant::now();
+        let results = pool.shutdown_now().await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        assert!(!results[&0].success());
+        assert!(!results[&1].success());
+        assert!(results[&1].stdout.is_empty());
+
+        // Handles still get their result
+        assert!(!running.wait().await.unwrap().success());
+    }
+
     #[tokio::test]
     async fn test_pool_with_events() {
         let (pool, mut events) = ProcessPool::with_events(2);
@@ -417,7 +964,7 @@ mod tests {
                         got_output = true;
                     }
                 }
-                PoolEvent::Completed { id, success } => {
+                PoolEvent::Completed { id, success, .. } => {
                     assert_eq!(id, proc.id);
                     assert!(success);
                     break;
@@ -430,8 +977,6 @@ mod tests {
 
     #[tokio::test]
     async fn test_pool_concurrency_limit() {
-        use std::time::{Duration, Instant};
-
         let pool = ProcessPool::new(1); // Only 1 concurrent process
 
         let start = Instant::now();
@@ -455,4 +1000,168 @@ mod tests {
         // With concurrency limit of 1, should take at least 200ms
         assert!(elapsed >= Duration::from_millis(180)); // Allow some slack
     }
+
+    #[tokio::test]
+    async fn test_pool_timeout() {
+        let (pool, mut events) = ProcessPool::with_events(1);
+
+        let proc = pool
+            .spawn(
+                ProcessOptions::new("sleep")
+                    .arg("30")
+                    .timeout(Duration::from_millis(100)),
+            )
+            .await
+            .unwrap();
+
+        let result = proc.wait().await.unwrap();
+        assert!(result.timed_out);
+        assert!(!result.success());
+
+        while let Some(event) = events.recv().await {
+            if let PoolEvent::Completed {
+                success, duration, ..
+            } = event
+            {
+                assert!(!success);
+                assert!(duration >= Duration::from_millis(100));
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_max_output_lines() {
+        let pool = ProcessPool::new(1);
+
+        let mut proc = pool
+            .spawn(ProcessOptions::new("seq").arg("10").max_output_lines(2))
+            .await
+            .unwrap();
+
+        // Streaming still sees every line
+        let mut streamed = 0;
+        while let Some(output) = proc.output.recv().await {
+            if matches!(output, ProcessOutput::Stdout(_)) {
+                streamed += 1;
+            }
+        }
+        assert_eq!(streamed, 10);
+
+        let result = proc.wait().await.unwrap();
+        assert!(result.truncated);
+        assert_eq!(result.stdout, vec!["9", "10"]);
+    }
+
+    #[tokio::test]
+    async fn test_pool_slot_requests() {
+        let pool = ProcessPool::new(1);
+
+        let mut first = pool.request_slot();
+        let slot = first.try_take().expect("free slot is granted at once");
+        assert_eq!(first.position(), None);
+
+        let mut second = pool.request_slot();
+        let third = pool.request_slot();
+        assert!(second.try_take().is_none());
+        assert_eq!(second.position(), Some(0));
+        assert_eq!(third.position(), Some(1));
+        assert_eq!(pool.available_permits(), 0);
+        let metrics = pool.metrics();
+        assert_eq!((metrics.active, metrics.queued), (1, 2));
+
+        drop(slot);
+        let slot = second
+            .try_take()
+            .expect("freed slot goes to the next request");
+        assert_eq!(third.position(), Some(0));
+
+        // Giving up a place in the queue doesn't leak the slot
+        drop(third);
+        drop(slot);
+        assert_eq!(pool.available_permits(), 1);
+        assert_eq!(
+            pool.metrics(),
+            PoolMetrics {
+                active: 0,
+                queued: 0,
+                completed: 2,
+                failed: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pool_priority() {
+        let (pool, mut events) = ProcessPool::with_events(1);
+
+        // Occupy the only slot so the next two queue up
+        let blocker = pool
+            .spawn(ProcessOptions::new("sleep").arg("0.2"))
+            .await
+            .unwrap();
+        let low = pool
+            .spawn(ProcessOptions::new("echo").arg("low"))
+            .await
+            .unwrap();
+        let high = pool
+            .spawn_with_priority(ProcessOptions::new("echo").arg("high"), 10)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(pool.queued(), 2);
+
+        let mut started = Vec::new();
+        while let Some(event) = events.recv().await {
+            if let PoolEvent::Started { id } = event {
+                started.push(id);
+                if started.len() == 3 {
+                    break;
+                }
+            }
+        }
+        assert_eq!(started, vec![blocker.id, high.id, low.id]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_pool_equal_priority_fifo() {
+        let (pool, mut events) = ProcessPool::with_events(1);
+
+        let mut ids = Vec::new();
+        for _ in 0..8 {
+            let proc = pool.spawn(ProcessOptions::new("true")).await.unwrap();
+            ids.push(proc.id);
+        }
+        // Spawning queues straight away, before any task has run
+        assert_eq!(pool.queued(), 7);
+
+        let mut started = Vec::new();
+        while let Some(event) = events.recv().await {
+            if let PoolEvent::Started { id } = event {
+                started.push(id);
+                if started.len() == ids.len() {
+                    break;
+                }
+            }
+        }
+        assert_eq!(started, ids);
+    }
+
+    #[tokio::test]
+    async fn test_pool_metrics() {
+        let pool = ProcessPool::new(2);
+        assert_eq!(pool.metrics(), PoolMetrics::default());
+
+        let ok = pool.spawn(ProcessOptions::new("true")).await.unwrap();
+        let bad = pool.spawn(ProcessOptions::new("false")).await.unwrap();
+        let _ = ok.wait().await.unwrap();
+        let _ = bad.wait().await.unwrap();
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.active, 0);
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.completed, 1);
+        assert_eq!(metrics.failed, 1);
+    }
 }
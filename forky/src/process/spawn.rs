@@ -3,18 +3,18 @@
 //! Provides async process spawning with:
 //! - Configurable stdio handling
 //! - Real-time stdout/stderr streaming via channels
-//! - Timeout support
+//! - Timeout support, with optional SIGTERM grace period before SIGKILL
 //! - Environment variable management
 //! - Working directory configuration
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::process::ExitStatus;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
-use tokio::sync::mpsc;
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
 use tokio::time::timeout;
 
 /// Output line from a spawned process.
@@ -52,6 +52,10 @@ pub struct ProcessOptions {
     /// Timeout for the entire process execution.
     pub timeout: Option<Duration>,
 
+    /// On timeout, send SIGTERM and wait this long before SIGKILL
+    /// (default: kill immediately). Ignored off Unix.
+    pub graceful_timeout: Option<Duration>,
+
     /// Whether to capture stdout (default: true).
     pub capture_stdout: bool,
 
@@ -63,6 +67,10 @@ pub struct ProcessOptions {
 
     /// Buffer size for output channels (default: 1000).
     pub buffer_size: usize,
+
+    /// Keep only the last this-many lines of each of stdout and stderr in
+    /// the result (default: keep everything). Streamed output is unaffected.
+    pub max_output_lines: Option<usize>,
 }
 
 impl ProcessOptions {
@@ -76,10 +84,12 @@ impl ProcessOptions {
             env_remove: Vec::new(),
             env_clear: false,
             timeout: None,
+            graceful_timeout: None,
             capture_stdout: true,
             capture_stderr: true,
             pipe_stdin: false,
             buffer_size: 1000,
+            max_output_lines: None,
         }
     }
 
@@ -142,6 +152,12 @@ impl ProcessOptions {
         self
     }
 
+    /// Give the process this long to exit after SIGTERM before it is killed.
+    pub const fn graceful_timeout(mut self, duration: Duration) -> Self {
+        self.graceful_timeout = Some(duration);
+        self
+    }
+
     /// Enable stdin piping.
     pub fn pipe_stdin(mut self) -> Self {
         self.pipe_stdin = true;
@@ -153,10 +169,55 @@ impl ProcessOptions {
         self.buffer_size = size;
         self
     }
+
+    /// Cap the lines of stdout and stderr kept in the result.
+    pub const fn max_output_lines(mut self, lines: usize) -> Self {
+        self.max_output_lines = Some(lines);
+        self
+    }
+}
+
+/// Output lines collected for a [`ProcessResult`], keeping only the most
+/// recent `max` once the cap is reached.
+#[derive(Debug, Default)]
+pub(super) struct LineBuffer {
+    lines: VecDeque<String>,
+    max: Option<usize>,
+    truncated: bool,
+}
+
+impl LineBuffer {
+    pub(super) fn new(max: Option<usize>) -> Self {
+        Self {
+            max,
+            ..Self::default()
+        }
+    }
+
+    pub(super) fn push(&mut self, line: String) {
+        if let Some(max) = self.max {
+            if self.lines.len() >= max {
+                self.truncated = true;
+                if self.lines.pop_front().is_none() {
+                    return;
+                }
+            }
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Whether any lines were dropped.
+    pub(super) const fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub(super) fn into_vec(self) -> Vec<String> {
+        self.lines.into()
+    }
 }
 
 /// Result from a completed process.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ProcessResult {
     /// Exit status of the process.
     pub status: ExitStatus,
@@ -169,6 +230,12 @@ pub struct ProcessResult {
 
     /// Whether the process was killed due to timeout.
     pub timed_out: bool,
+
+    /// Whether older lines were dropped to stay within `max_output_lines`.
+    pub truncated: bool,
+
+    /// Wall-clock time from spawn to exit.
+    pub duration: Duration,
 }
 
 impl ProcessResult {
@@ -262,6 +329,7 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
     }
 
     // Spawn the process
+    let started = Instant::now();
     let mut child = cmd
         .spawn()
         .with_context(|| format!("Failed to spawn process: {}", options.program))?;
@@ -303,8 +371,8 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
     drop(tx);
 
     // Collect output with optional timeout
-    let mut stdout_lines = Vec::new();
-    let mut stderr_lines = Vec::new();
+    let mut stdout_lines = LineBuffer::new(options.max_output_lines);
+    let mut stderr_lines = LineBuffer::new(options.max_output_lines);
     let mut timed_out = false;
 
     let collect_future = async {
@@ -320,8 +388,7 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
     if let Some(duration) = options.timeout {
         if timeout(duration, collect_future).await.is_err() {
             timed_out = true;
-            // Kill the process on timeout
-            let _ = child.kill().await;
+            terminate(&mut child, options.graceful_timeout).await;
         }
     } else {
         collect_future.await;
@@ -335,16 +402,59 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
 
     Ok(ProcessResult {
         status,
-        stdout: stdout_lines,
-        stderr: stderr_lines,
+        duration: started.elapsed(),
+        truncated: stdout_lines.truncated() || stderr_lines.truncated(),
+        stdout: stdout_lines.into_vec(),
+        stderr: stderr_lines.into_vec(),
         timed_out,
     })
 }
 
+/// Stop a child, politely first if there is a grace period.
+///
+/// Sends SIGTERM and waits up to `grace` for the process to exit before
+/// falling back to SIGKILL. Without a grace period, or off Unix, the process
+/// is killed straight away.
+pub(super) async fn terminate(child: &mut Child, grace: Option<Duration>) {
+    #[cfg(unix)]
+    if let (Some(grace), Some(pid)) = (grace, child.id()) {
+        let signalled = Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status()
+            .await
+            .is_ok_and(|status| status.success());
+        if signalled && timeout(grace, child.wait()).await.is_ok() {
+            return;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = grace;
+
+    let _ = child.kill().await;
+}
+
+/// Handle for stopping a process started with [`spawn_process_streaming`].
+///
+/// Dropping the handle does not kill the process.
+#[derive(Debug)]
+pub struct KillHandle {
+    tx: Option<oneshot::Sender<()>>,
+}
+
+impl KillHandle {
+    /// Ask the process to terminate.
+    ///
+    /// Returns `false` if the process has already exited or was already killed.
+    pub fn kill(&mut self) -> bool {
+        self.tx.take().is_some_and(|tx| tx.send(()).is_ok())
+    }
+}
+
 /// Spawn a process with streaming output via a channel.
 ///
 /// Returns a receiver that yields output lines as they arrive,
-/// along with a handle to wait for process completion.
+/// a handle to wait for process completion, and a [`KillHandle`]
+/// to stop the process early.
 ///
 /// # Example
 ///
@@ -353,7 +463,7 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
 ///
 /// #[tokio::main]
 /// async fn main() -> anyhow::Result<()> {
-///     let (mut rx, handle) = spawn_process_streaming(
+///     let (mut rx, handle, mut kill) = spawn_process_streaming(
 ///         ProcessOptions::new("tail")
 ///             .arg("-f")
 ///             .arg("/var/log/system.log")
@@ -362,6 +472,9 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
 ///     // Process output as it arrives
 ///     while let Some(output) = rx.recv().await {
 ///         match output {
+///             ProcessOutput::Stdout(line) if line.contains("shutdown") => {
+///                 kill.kill();
+///             }
 ///             ProcessOutput::Stdout(line) => println!("OUT: {}", line),
 ///             ProcessOutput::Stderr(line) => eprintln!("ERR: {}", line),
 ///             ProcessOutput::Exit(status) => {
@@ -376,7 +489,11 @@ pub async fn spawn_process(options: ProcessOptions) -> Result<ProcessResult> {
 /// ```
 pub async fn spawn_process_streaming(
     options: ProcessOptions,
-) -> Result<(mpsc::Receiver<ProcessOutput>, tokio::task::JoinHandle<Result<ExitStatus>>)> {
+) -> Result<(
+    mpsc::Receiver<ProcessOutput>,
+    tokio::task::JoinHandle<Result<ExitStatus>>,
+    KillHandle,
+)> {
     let mut cmd = Command::new(&options.program);
 
     // Add arguments
@@ -457,17 +574,24 @@ pub async fn spawn_process_streaming(
         });
     }
 
-    // Spawn task to wait for process and send exit status
+    let (kill_tx, mut kill_rx) = oneshot::channel::<()>();
+
+    // Spawn task to wait for process (or a kill request) and send exit status
     let handle = tokio::spawn(async move {
-        let status = child
-            .wait()
-            .await
-            .context("Failed to wait for process to exit")?;
+        let status = tokio::select! {
+            status = child.wait() => status,
+            // A dropped KillHandle disables this branch rather than killing
+            Ok(()) = &mut kill_rx => {
+                child.kill().await.context("Failed to kill process")?;
+                child.wait().await
+            }
+        }
+        .context("Failed to wait for process to exit")?;
         let _ = tx.send(ProcessOutput::Exit(status)).await;
         Ok(status)
     });
 
-    Ok((rx, handle))
+    Ok((rx, handle, KillHandle { tx: Some(kill_tx) }))
 }
 
 #[cfg(test)]
@@ -529,6 +653,56 @@ mod tests {
 
         assert!(result.timed_out);
         assert!(!result.success());
+        assert!(result.duration >= Duration::from_millis(100));
+        assert!(result.duration < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_max_output_lines() {
+        let result = spawn_process(ProcessOptions::new("seq").arg("100").max_output_lines(3))
+            .await
+            .unwrap();
+
+        assert!(result.truncated);
+        assert_eq!(result.stdout, vec!["98", "99", "100"]);
+
+        let result = spawn_process(ProcessOptions::new("seq").arg("3").max_output_lines(3))
+            .await
+            .unwrap();
+        assert!(!result.truncated);
+        assert_eq!(result.stdout.len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_graceful_timeout() {
+        // Exits cleanly on SIGTERM
+        let result = spawn_process(
+            ProcessOptions::new("sh")
+                .arg("-c")
+                .arg("trap 'exit 0' TERM; sleep 10 >/dev/null & wait")
+                .timeout(Duration::from_millis(200))
+                .graceful_timeout(Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.code(), Some(0));
+
+        // Ignores SIGTERM, so is killed once the grace period runs out
+        let started = std::time::Instant::now();
+        let result = spawn_process(
+            ProcessOptions::new("sh")
+                .arg("-c")
+                .arg("trap '' TERM; sleep 10 >/dev/null & wait")
+                .timeout(Duration::from_millis(200))
+                .graceful_timeout(Duration::from_millis(300)),
+        )
+        .await
+        .unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.code(), None);
+        assert!(started.elapsed() >= Duration::from_millis(500));
     }
 
     #[tokio::test]
@@ -555,4 +729,26 @@ mod tests {
         assert!(!result.success());
         assert_eq!(result.code(), Some(42));
     }
+
+    #[tokio::test]
+    async fn test_streaming_kill() {
+        let (mut rx, handle, mut kill) =
+            spawn_process_streaming(ProcessOptions::new("sleep").arg("30"))
+                .await
+                .unwrap();
+
+        assert!(kill.kill());
+        assert!(!kill.kill());
+
+        let status = handle.await.unwrap().unwrap();
+        assert!(!status.success());
+
+        let mut saw_exit = false;
+        while let Some(output) = rx.recv().await {
+            if matches!(output, ProcessOutput::Exit(_)) {
+                saw_exit = true;
+            }
+        }
+        assert!(saw_exit);
+    }
 }
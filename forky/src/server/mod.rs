@@ -2,88 +2,313 @@
 //!
 //! Architecture:
 //! - One global server runs at ~/.forky (manages PID/port files)
+//! - `forky serve` runs it in the foreground; `forky server status|stop` manage the daemon
 //! - Each project gets its own ManifoldDB at <project>/.claude/mod-claude/forky.redb
 //! - All DB access goes through the server to avoid lock contention
 //! - CLI is a thin client that talks to the server via HTTP
+//! - Each request runs in a `tracing` span; a daemonized server logs to ~/.forky/server.log
+//! - Failed requests return `{"error": "..."}` (500s include the cause unless bound beyond loopback)
 //!
-//! Endpoints:
+//! Endpoints (a `:id`, or `fork_id` filter, may be a unique prefix of the fork ID):
 //! - POST /api/events - Store events (requires project_path)
 //! - GET /api/events - Query events
+//! - GET /api/search - Case-insensitive text search over event content
 //! - POST /api/forks - Create a fork
-//! - GET /api/forks - List forks
-//! - PATCH /api/forks/:id - Update fork status
-//! - WS /ws - WebSocket for real-time updates
+//! - GET /api/forks - List forks (`tag=a,b` keeps forks carrying every listed tag)
+//! - PATCH /api/forks/:id - Update fork status (POSTs `webhook_url` when a fork finishes)
+//! - DELETE /api/forks/:id - Delete a fork and its events
+//! - GET /api/forks/:id/tree - Nested conversation tree for a fork
+//! - GET /api/forks/:id/lineage - The fork and the forks it descends from, nearest first
+//! - GET /api/forks/:id/cost - Per-turn cost and per-model tokens for a fork
+//! - POST /api/forks/:id/read - Mark a fork as read
+//! - POST /api/forks/read-all - Mark every fork as read
+//! - POST /api/forks/:id/slot - Queue for one of the `max_concurrent` run slots (poll until granted, then to renew it)
+//! - GET /api/pool - Forks holding and queued for run slots across every project
+//! - GET /api/sessions - List sessions with their owning fork
+//! - GET /api/jobs - List jobs with their owning fork
+//! - GET /api/stats - Aggregate cost/turns/duration for a project
+//! - GET /api/projects - Every project seen so far, including ones not loaded since a restart
+//! - POST /api/maintenance/compact - Reclaim space in a project's database file
+//! - POST /api/maintenance/relink - Link events stored before the `tool_use` they refer to
+//! - GET /api/health - Liveness probe reporting the server PID; the CLI waits for it after starting the server
+//! - GET /api/stream - Server-Sent Events version of `/ws`, with the same filters
+//! - WS /ws - WebSocket for real-time updates (filter with `project_path`/`fork_id`)
 //! - GET / - Dashboard UI
 
-use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use axum::{
-    extract::{Path, Query, State, WebSocketUpgrade},
-    http::StatusCode,
-    response::{Html, IntoResponse},
-    routing::{get, patch, post},
+    extract::{Path, Query, Request, State, WebSocketUpgrade},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
+    routing::{delete, get, patch, post},
     Json, Router,
 };
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, RwLock};
+use subtle::ConstantTimeEq;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tokio::time::Instant;
+use tracing::Instrument;
 
 use crate::claude::ClaudeEvent;
-use crate::db::GraphDatabase;
+use crate::config::Config;
+use crate::db::{
+    fork_tags, EventNode, ForkLaunch, ForkStatus, ForkUpdate, GraphDatabase, LABEL_FORK,
+};
+use crate::process::{PoolMetrics, PoolSlot, ProcessPool, SlotRequest};
 
 /// Server configuration file paths.
 const SERVER_DIR: &str = ".forky";
 const PID_FILE: &str = "server.pid";
 const PORT_FILE: &str = "server.port";
+/// Where a daemonized server's diagnostics go.
+const LOG_FILE: &str = "server.log";
+/// Every project the server has opened a database for, kept across restarts.
+const PROJECTS_FILE: &str = "projects.json";
+
+/// Port used when neither `--port` nor the config file sets one.
+pub const DEFAULT_PORT: u16 = 58231;
+
+/// Ports tried, counting up from the configured one, when it's taken.
+const PORT_SCAN_ATTEMPTS: usize = 10;
+
+/// Broadcasts buffered per WebSocket client when `broadcast_buffer` isn't set.
+const DEFAULT_BROADCAST_BUFFER: usize = 1000;
+
+/// How long a single webhook delivery may take.
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Deliveries attempted before a webhook is dropped.
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// Forks allowed to run at once when `max_concurrent` isn't set.
+const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// A queued fork that hasn't polled for its slot in this long is dropped
+/// from the queue (its CLI has probably gone away).
+const SLOT_REQUEST_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// A held slot whose fork hasn't renewed it in this long is taken back, so a
+/// CLI that dies without reporting a finished status doesn't keep it forever.
+const SLOT_LEASE_TTL: std::time::Duration = std::time::Duration::from_secs(45);
 
 /// Database manager - handles multiple project databases.
 pub struct DatabaseManager {
     /// Map of project_path -> GraphDatabase
     databases: HashMap<PathBuf, GraphDatabase>,
+    /// Every project with a database, loaded or not
+    known_projects: BTreeSet<PathBuf>,
+    /// Where `known_projects` is saved (`~/.forky/projects.json`)
+    registry_path: Option<PathBuf>,
 }
 
 impl DatabaseManager {
     pub fn new() -> Self {
+        let registry_path = get_server_dir().ok().map(|dir| dir.join(PROJECTS_FILE));
+        let known_projects = registry_path
+            .as_deref()
+            .map(load_project_registry)
+            .unwrap_or_default();
         Self {
             databases: HashMap::new(),
+            known_projects,
+            registry_path,
         }
     }
 
     /// Get or create database for a project path.
     pub fn get_or_create(&mut self, project_path: &PathBuf) -> Result<&mut GraphDatabase> {
         if !self.databases.contains_key(project_path) {
-            let db_path = project_path
-                .join(".claude")
-                .join("mod-claude")
-                .join("forky.redb");
+            let db_path = project_db_path(project_path);
             std::fs::create_dir_all(db_path.parent().unwrap())?;
             let db = GraphDatabase::open_at(&db_path)
                 .with_context(|| format!("Failed to open database at {}", db_path.display()))?;
             self.databases.insert(project_path.clone(), db);
+
+            if self.known_projects.insert(project_path.clone()) {
+                self.save_project_registry();
+            }
         }
         Ok(self.databases.get_mut(project_path).unwrap())
     }
 
+    /// Open the database of every known project that isn't loaded yet.
+    ///
+    /// A project that fails to open is logged and skipped.
+    pub fn load_known_projects(&mut self) {
+        let unloaded: Vec<PathBuf> = self
+            .known_projects
+            .iter()
+            .filter(|project| !self.databases.contains_key(*project))
+            .cloned()
+            .collect();
+        for project_path in unloaded {
+            if let Err(e) = self.get_or_create(&project_path) {
+                tracing::warn!(project = %project_path.display(), "Failed to load project: {e:#}");
+            }
+        }
+    }
+
+    /// Write the known projects to the registry file.
+    ///
+    /// Failing to save only costs the dashboard a project after a restart,
+    /// so errors are logged rather than returned.
+    fn save_project_registry(&self) {
+        let Some(ref path) = self.registry_path else {
+            return;
+        };
+        let saved = serde_json::to_string_pretty(&self.known_projects)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| std::fs::write(path, json).map_err(anyhow::Error::from));
+        if let Err(e) = saved {
+            tracing::warn!(path = %path.display(), "Failed to save project registry: {e:#}");
+        }
+    }
+
+    /// Compact a project's database, returning how many bytes were reclaimed.
+    ///
+    /// A database that couldn't be reopened afterwards is unloaded, so the
+    /// next request opens the file again instead of writing to a stand-in.
+    pub fn compact(&mut self, project_path: &PathBuf) -> Result<u64> {
+        let db = self.get_or_create(project_path)?;
+        let reclaimed = db.compact();
+        if db.is_closed() {
+            self.databases.remove(project_path);
+        }
+        reclaimed
+    }
+
     /// Get database for a project (if exists).
     pub fn get(&self, project_path: &PathBuf) -> Option<&GraphDatabase> {
         self.databases.get(project_path)
     }
 
-    /// List all active projects.
+    /// List every known project, including ones not loaded since the server started.
     pub fn list_projects(&self) -> Vec<PathBuf> {
-        self.databases.keys().cloned().collect()
+        self.known_projects.iter().cloned().collect()
     }
 }
 
+/// Location of a project's database file.
+fn project_db_path(project_path: &std::path::Path) -> PathBuf {
+    project_path
+        .join(".claude")
+        .join("mod-claude")
+        .join("forky.redb")
+}
+
+/// Read the project registry, dropping projects whose database is gone.
+fn load_project_registry(path: &std::path::Path) -> BTreeSet<PathBuf> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return BTreeSet::new(),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), "Failed to read project registry: {e}");
+            return BTreeSet::new();
+        }
+    };
+    let projects: BTreeSet<PathBuf> = match serde_json::from_str(&contents) {
+        Ok(projects) => projects,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), "Ignoring malformed project registry: {e}");
+            return BTreeSet::new();
+        }
+    };
+    projects
+        .into_iter()
+        .filter(|project| project_db_path(project).exists())
+        .collect()
+}
+
 /// Shared server state.
 pub struct ServerState {
     /// Database manager (handles multiple project DBs).
     db_manager: RwLock<DatabaseManager>,
     /// Broadcast channel for real-time updates.
     tx: broadcast::Sender<EventBroadcast>,
+    /// Bearer token required on `/api/*` and `/ws`, if set.
+    auth_token: Option<String>,
+    /// Where finished-fork notifications are posted, if configured.
+    webhook: Option<Webhook>,
+    /// Caps how many forks run at once across every project.
+    pool: ProcessPool,
+    /// Forks queued for, or holding, a slot in `pool`.
+    slots: Mutex<SlotTable>,
+}
+
+/// Run slots handed out to fork CLIs, keyed by `fork_id`.
+#[derive(Default)]
+struct SlotTable {
+    /// Forks waiting for a slot, with when they last polled.
+    waiting: HashMap<String, (SlotRequest, Instant)>,
+    /// Forks holding a slot until they report a finished status or stop
+    /// renewing it, with when they last renewed it.
+    held: HashMap<String, (PoolSlot, Instant)>,
+}
+
+/// Response to a slot request.
+#[derive(Debug, Serialize)]
+pub struct SlotStatus {
+    pub granted: bool,
+    /// Forks ahead of this one in the queue.
+    pub position: usize,
+}
+
+/// A configured `webhook_url` and the client used to call it.
+#[derive(Clone)]
+struct Webhook {
+    url: String,
+    client: reqwest::Client,
+}
+
+/// Body posted to the webhook when a fork finishes.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    pub fork_id: String,
+    pub name: Option<String>,
+    pub status: ForkStatus,
+    pub project_path: String,
+    /// What the fork reported it did, or why it ended the way it did.
+    pub summary: Option<String>,
+}
+
+impl Webhook {
+    /// POST a payload in the background, retrying failures a few times.
+    fn fire(&self, payload: WebhookPayload) {
+        let webhook = self.clone();
+        tokio::spawn(async move {
+            let mut delay = std::time::Duration::from_millis(500);
+            for attempt in 1..=WEBHOOK_ATTEMPTS {
+                let sent = webhook.client.post(&webhook.url).json(&payload).send();
+                let failure = match sent.await {
+                    Ok(resp) if resp.status().is_success() => return,
+                    Ok(resp) if resp.status().is_client_error() => {
+                        tracing::warn!(url = %webhook.url, status = %resp.status(), "Webhook rejected");
+                        return;
+                    }
+                    Ok(resp) => resp.status().to_string(),
+                    Err(e) => e.to_string(),
+                };
+                if attempt == WEBHOOK_ATTEMPTS {
+                    tracing::warn!(url = %webhook.url, "Webhook failed: {failure}");
+                    return;
+                }
+                tracing::debug!(url = %webhook.url, attempt, "Webhook attempt failed: {failure}");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        });
+    }
 }
 
 /// Event broadcast message.
@@ -97,6 +322,28 @@ pub struct EventBroadcast {
     pub fork_id: Option<String>,
 }
 
+/// Optional filters for live event streams.
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamFilter {
+    pub project_path: Option<String>,
+    pub fork_id: Option<String>,
+}
+
+impl StreamFilter {
+    /// Whether a broadcast passes every filter that was given.
+    fn matches(&self, broadcast: &EventBroadcast) -> bool {
+        let project_matches = self
+            .project_path
+            .as_ref()
+            .is_none_or(|p| *p == broadcast.project_path);
+        let fork_matches = self
+            .fork_id
+            .as_ref()
+            .is_none_or(|f| broadcast.fork_id.as_ref() == Some(f));
+        project_matches && fork_matches
+    }
+}
+
 /// Stored event (full data for API).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StoredEvent {
@@ -118,6 +365,11 @@ pub struct StoredEvent {
     pub total_cost_usd: Option<f64>,
     pub duration_ms: Option<u64>,
     pub num_turns: Option<u32>,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub cache_read_tokens: Option<u64>,
+    pub cache_creation_tokens: Option<u64>,
+    pub created_at: Option<String>,
     pub raw: Option<serde_json::Value>,
 }
 
@@ -150,9 +402,139 @@ impl StoredEvent {
             total_cost_usd: e.total_cost_usd,
             duration_ms: e.duration_ms,
             num_turns: e.num_turns,
+            input_tokens: e.usage.as_ref().map(|u| u.input_tokens),
+            output_tokens: e.usage.as_ref().map(|u| u.output_tokens),
+            cache_read_tokens: e.usage.as_ref().map(|u| u.cache_read_tokens),
+            cache_creation_tokens: e.usage.as_ref().map(|u| u.cache_creation_tokens),
+            created_at: Some(e.created_at().to_rfc3339()),
             raw: Some(e.raw.clone()),
         }
     }
+
+    /// Convert a stored Event entity back into its API shape.
+    pub fn from_entity(entity: &manifoldb_core::Entity) -> Self {
+        use manifoldb_core::Value;
+
+        let get_str = |key: &str| -> Option<String> {
+            entity.properties.get(key).and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                _ => None,
+            })
+        };
+        let get_int = |key: &str| -> Option<i64> {
+            entity.properties.get(key).and_then(|v| match v {
+                Value::Int(i) => Some(*i),
+                _ => None,
+            })
+        };
+        let get_float = |key: &str| -> Option<f64> {
+            entity.properties.get(key).and_then(|v| match v {
+                Value::Float(f) => Some(*f),
+                _ => None,
+            })
+        };
+
+        Self {
+            fork_id: get_str("fork_id"),
+            uuid: get_str("uuid"),
+            session_id: get_str("session_id"),
+            parent_tool_use_id: get_str("parent_tool_use_id"),
+            event_type: get_str("type").unwrap_or_else(|| "unknown".to_string()),
+            subtype: get_str("subtype"),
+            message: get_str("message"),
+            thinking: get_str("thinking"),
+            result: get_str("result"),
+            model: get_str("model"),
+            message_id: get_str("message_id"),
+            role: get_str("role"),
+            tool_uses: get_str("tool_uses").and_then(|s| serde_json::from_str(&s).ok()),
+            tool_results: get_str("tool_results").and_then(|s| serde_json::from_str(&s).ok()),
+            cost_usd: get_float("cost_usd"),
+            total_cost_usd: get_float("total_cost_usd"),
+            duration_ms: get_int("duration_ms").map(|i| i as u64),
+            num_turns: get_int("num_turns").map(|i| i as u32),
+            input_tokens: get_int("input_tokens").and_then(|i| u64::try_from(i).ok()),
+            output_tokens: get_int("output_tokens").and_then(|i| u64::try_from(i).ok()),
+            cache_read_tokens: get_int("cache_read_tokens").and_then(|i| u64::try_from(i).ok()),
+            cache_creation_tokens: get_int("cache_creation_tokens")
+                .and_then(|i| u64::try_from(i).ok()),
+            created_at: get_str("created_at"),
+            raw: get_str("raw").and_then(|s| serde_json::from_str(&s).ok()),
+        }
+    }
+}
+
+// === Errors ===
+
+/// Whether 500 responses carry the underlying error. Off when the server
+/// is bound to an address other machines can reach.
+static EXPOSE_ERROR_DETAILS: AtomicBool = AtomicBool::new(true);
+
+/// A failed request: its status code and a `{"error": "..."}` body.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    fn missing_project_path() -> Self {
+        Self::bad_request("project_path is required")
+    }
+
+    /// A read-only request for a project whose database isn't loaded.
+    fn unknown_project() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "No database loaded for this project")
+    }
+
+    fn fork_not_found(fork_id: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("Fork {fork_id} not found"))
+    }
+
+    /// A fork ID prefix that more than one fork starts with.
+    fn ambiguous_fork(prefix: &str, matches: &[String]) -> Self {
+        Self::bad_request(format!(
+            "Fork ID prefix {prefix} matches {} forks: {}",
+            matches.len(),
+            matches.join(", ")
+        ))
+    }
+}
+
+/// Anything else is a server error. The full chain is logged, and returned
+/// to the caller unless error details are hidden.
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(err: E) -> Self {
+        let err = err.into();
+        tracing::error!("{err:#}");
+        let message = if EXPOSE_ERROR_DETAILS.load(Ordering::Relaxed) {
+            format!("{err:#}")
+        } else {
+            "Internal server error".to_string()
+        };
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.message })),
+        )
+            .into_response()
+    }
 }
 
 // === Request/Response Types ===
@@ -170,8 +552,21 @@ pub struct IngestRequest {
 
 #[derive(Debug, Serialize)]
 pub struct IngestResponse {
+    /// Events newly stored; events already stored are not counted.
     pub stored: usize,
     pub errors: usize,
+    /// Positions in the request's `events` array that were rejected.
+    pub failed_indices: Vec<usize>,
+    /// Details for each rejected event, in the same order.
+    pub failures: Vec<IngestFailure>,
+}
+
+/// Why a single event in an ingest request was rejected.
+#[derive(Debug, Serialize)]
+pub struct IngestFailure {
+    pub index: usize,
+    pub uuid: Option<String>,
+    pub error: String,
 }
 
 /// Request to create a fork.
@@ -180,8 +575,22 @@ pub struct CreateForkRequest {
     pub project_path: String,
     pub fork_id: String,
     pub parent_session_id: Option<String>,
+    /// The message the fork was spawned with.
     pub job_description: Option<String>,
     pub fork_name: Option<String>,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub worktree: bool,
+    pub worktree_path: Option<String>,
+    pub worktree_branch: Option<String>,
+    /// Commit the worktree branch started from.
+    pub worktree_base: Option<String>,
+    /// AI CLI the fork runs on (defaults to claude).
+    pub ai_provider: Option<String>,
+    /// `fork_id` of the fork this one retries.
+    pub retry_of: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -195,8 +604,16 @@ pub struct CreateForkResponse {
 #[derive(Debug, Deserialize)]
 pub struct UpdateForkRequest {
     pub project_path: String,
-    pub status: String,
+    pub status: ForkStatus,
     pub session_id: Option<String>,
+    /// Why the fork ended up in this status (e.g. timeout).
+    pub reason: Option<String>,
+    /// Subtype of the final result event (e.g. `error_max_turns`).
+    pub result_subtype: Option<String>,
+    /// Model the fork actually ran on, as reported by its events.
+    pub model: Option<String>,
+    /// What the fork reported it did, from `forky done`.
+    pub summary: Option<String>,
 }
 
 /// Fork summary for listing.
@@ -208,111 +625,655 @@ pub struct ForkSummary {
     pub session_id: Option<String>,
     pub parent_session_id: Option<String>,
     pub status: String,
+    /// Why the fork ended the way it did (e.g. a timeout), if recorded.
+    pub reason: Option<String>,
     pub event_count: usize,
     pub created_at: Option<String>,
+    /// What the fork was asked to do.
+    pub description: Option<String>,
+    pub model: Option<String>,
+    pub worktree: bool,
+    pub worktree_path: Option<String>,
+    pub worktree_branch: Option<String>,
+    pub worktree_base: Option<String>,
+    pub ai_provider: String,
+    pub tags: Vec<String>,
+}
+
+impl ForkSummary {
+    /// Build a summary from a stored Fork entity.
+    pub fn from_entity(
+        project_path: &std::path::Path,
+        entity: &manifoldb_core::Entity,
+        event_count: usize,
+    ) -> Self {
+        use manifoldb_core::Value;
+
+        let get_str = |key: &str| -> Option<String> {
+            entity.properties.get(key).and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                _ => None,
+            })
+        };
+
+        Self {
+            project_path: project_path.to_string_lossy().to_string(),
+            fork_id: get_str("fork_id").unwrap_or_default(),
+            fork_name: get_str("fork_name"),
+            session_id: get_str("session_id"),
+            parent_session_id: get_str("parent_session_id"),
+            status: get_str("status").unwrap_or_else(|| "unknown".to_string()),
+            reason: get_str("reason"),
+            event_count,
+            created_at: get_str("created_at"),
+            description: get_str("description"),
+            model: get_str("model"),
+            worktree: matches!(entity.properties.get("worktree"), Some(Value::Bool(true))),
+            worktree_path: get_str("worktree_path"),
+            worktree_branch: get_str("worktree_branch"),
+            worktree_base: get_str("worktree_base"),
+            ai_provider: get_str("ai_provider").unwrap_or_else(|| "claude".to_string()),
+            tags: fork_tags(entity),
+        }
+    }
+}
+
+/// Session summary for listing.
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub session_id: Option<String>,
+    /// Fork that owns this session (via `HAS_SESSION`).
+    pub fork_id: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// Job summary for listing.
+#[derive(Debug, Serialize)]
+pub struct JobSummary {
+    pub job_id: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<String>,
+    /// Fork that owns this job (via `HAS_JOB`).
+    pub fork_id: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// Token usage summed over events.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TokenTotals {
+    pub input: u64,
+    pub output: u64,
+    pub cache_read: u64,
+    pub cache_creation: u64,
+}
+
+impl TokenTotals {
+    const fn add(&mut self, other: &Self) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_read += other.cache_read;
+        self.cache_creation += other.cache_creation;
+    }
+}
+
+/// Aggregated statistics for a project.
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectStats {
+    pub project_path: String,
+    pub fork_count: usize,
+    /// Number of forks in each status.
+    pub forks_by_status: HashMap<String, usize>,
+    pub event_count: usize,
+    pub total_cost_usd: f64,
+    /// Cost of events created today (local time).
+    pub cost_today_usd: f64,
+    pub total_turns: u64,
+    pub total_duration_ms: u64,
+    pub avg_cost_per_fork: f64,
+    /// Token usage across the whole project.
+    pub tokens: TokenTotals,
+    /// Token usage per fork ID.
+    pub tokens_by_fork: HashMap<String, TokenTotals>,
+}
+
+/// Cost of one turn: the events from a prompt up to its result.
+#[derive(Debug, Serialize)]
+pub struct TurnCost {
+    /// 1-based turn number.
+    pub turn: usize,
+    pub started_at: Option<String>,
+    pub cost_usd: f64,
+    /// Cost of this turn and every turn before it.
+    pub cumulative_cost_usd: f64,
+    pub tokens: TokenTotals,
+    /// Events from sub-agents the turn spawned, counted in its cost and tokens.
+    pub subagent_events: usize,
+}
+
+/// Where a fork's cost went.
+#[derive(Debug, Default, Serialize)]
+pub struct CostBreakdown {
+    pub fork_id: String,
+    pub turns: Vec<TurnCost>,
+    /// Token usage per model ("unknown" for events without one).
+    pub tokens_by_model: HashMap<String, TokenTotals>,
+    pub total_cost_usd: f64,
+}
+
+impl CostBreakdown {
+    /// Split a fork's events, oldest first, into turns. Each event comes
+    /// with the ids of the `tool_use` blocks it contains.
+    ///
+    /// A turn ends at a top-level result event. Sub-agent events count
+    /// towards the turn whose `tool_use` spawned them, even if they are
+    /// stored after it ended.
+    fn from_events(fork_id: &str, events: &[(StoredEvent, Vec<String>)]) -> Self {
+        let mut breakdown = Self {
+            fork_id: fork_id.to_string(),
+            ..Self::default()
+        };
+        let mut turn_of_tool_use: HashMap<&str, usize> = HashMap::new();
+        // Claude repeats a message's usage on every content-block event
+        let mut counted_messages = HashSet::new();
+        let mut turn_open = false;
+
+        for (event, tool_use_ids) in events {
+            let parent_turn = event
+                .parent_tool_use_id
+                .as_deref()
+                .and_then(|id| turn_of_tool_use.get(id).copied());
+            if parent_turn.is_none() && !turn_open {
+                breakdown.turns.push(TurnCost {
+                    turn: breakdown.turns.len() + 1,
+                    started_at: event.created_at.clone(),
+                    cost_usd: 0.0,
+                    cumulative_cost_usd: 0.0,
+                    tokens: TokenTotals::default(),
+                    subagent_events: 0,
+                });
+                turn_open = true;
+            }
+            let index = parent_turn.unwrap_or(breakdown.turns.len() - 1);
+            for id in tool_use_ids {
+                turn_of_tool_use.insert(id.as_str(), index);
+            }
+
+            let turn = &mut breakdown.turns[index];
+            // total_cost_usd is authoritative when present (result events)
+            turn.cost_usd += event.total_cost_usd.or(event.cost_usd).unwrap_or(0.0);
+            if event.parent_tool_use_id.is_some() {
+                turn.subagent_events += 1;
+            }
+
+            let first_sighting = event
+                .message_id
+                .as_ref()
+                .is_none_or(|id| counted_messages.insert(id.clone()));
+            // Prompts, tool results and result events carry no usage
+            let has_usage = event.input_tokens.is_some() || event.model.is_some();
+            if first_sighting && has_usage {
+                let tokens = TokenTotals {
+                    input: event.input_tokens.unwrap_or(0),
+                    output: event.output_tokens.unwrap_or(0),
+                    cache_read: event.cache_read_tokens.unwrap_or(0),
+                    cache_creation: event.cache_creation_tokens.unwrap_or(0),
+                };
+                turn.tokens.add(&tokens);
+                let model = event.model.as_deref().unwrap_or("unknown");
+                breakdown
+                    .tokens_by_model
+                    .entry(model.to_string())
+                    .or_default()
+                    .add(&tokens);
+            }
+
+            if event.event_type == "result" && event.parent_tool_use_id.is_none() {
+                turn_open = false;
+            }
+        }
+
+        for turn in &mut breakdown.turns {
+            breakdown.total_cost_usd += turn.cost_usd;
+            turn.cumulative_cost_usd = breakdown.total_cost_usd;
+        }
+        breakdown
+    }
 }
 
 /// Query parameters for events/forks.
 #[derive(Debug, Deserialize)]
 pub struct QueryParams {
     pub project_path: Option<String>,
+    /// Only events whose session ID starts with this (see `session_match`).
     pub session: Option<String>,
+    /// How `session` is compared: "prefix" (default) or "exact".
+    pub session_match: Option<String>,
     pub fork_id: Option<String>,
     pub limit: Option<usize>,
+    /// Only events with this `type` (e.g. "result").
+    pub event_type: Option<String>,
+    /// Only events created at or after this RFC3339 time.
+    pub since: Option<String>,
+    /// Only events created at or before this RFC3339 time.
+    pub until: Option<String>,
+    /// Sort order by `created_at`: "desc" (default, newest first) or "asc".
+    pub order: Option<String>,
+    /// Number of events to skip (applied after `cursor`).
+    pub offset: Option<usize>,
+    /// UUID of the last event already seen; results start after it.
+    pub cursor: Option<String>,
+    /// Text to look for (search only).
+    pub q: Option<String>,
+    /// Comma-separated tags a fork must all carry (fork listing only).
+    pub tag: Option<String>,
+}
+
+/// Characters of context kept on each side of a search match.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// An event whose content matched a search.
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    /// Which property matched: "message", "thinking" or "result".
+    pub field: String,
+    /// Text around the match, with the match wrapped in `**`.
+    pub snippet: String,
+    pub event: StoredEvent,
+}
+
+/// Results of a search, newest first.
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchHit>,
+}
+
+/// A page of events from a query.
+#[derive(Debug, Serialize)]
+pub struct EventsPage {
+    pub events: Vec<StoredEvent>,
+    /// Absolute offset of the next page, if more events exist.
+    pub next_offset: Option<usize>,
+    /// UUID of the last event in this page, if more events exist.
+    pub next_cursor: Option<String>,
+}
+
+/// Parse an optional RFC3339 query parameter.
+fn parse_time_param(value: Option<&str>) -> Result<Option<DateTime<FixedOffset>>, ApiError> {
+    value
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map_err(|e| ApiError::bad_request(format!("Invalid timestamp '{v}': {e}")))
+        })
+        .transpose()
 }
 
 // === Server Lifecycle ===
 
-/// Start the server.
-pub async fn start_server(port: u16, open_browser: bool) -> Result<()> {
+/// Start the server, listening on `host:port`.
+///
+/// Clients (the CLI and the spawned daemon) always talk to 127.0.0.1,
+/// so `host` only controls who else can reach the dashboard. When
+/// `auth_token` is set, API and WebSocket requests must present it.
+/// With `scan_ports`, a port that is already in use is skipped for the
+/// next free one; clients find the chosen port in the port file.
+pub async fn start_server(
+    host: IpAddr,
+    port: u16,
+    scan_ports: bool,
+    open_browser: bool,
+    auth_token: Option<String>,
+) -> Result<()> {
     let server_dir = get_server_dir()?;
     std::fs::create_dir_all(&server_dir)?;
 
-    let pid = std::process::id();
-    std::fs::write(server_dir.join(PID_FILE), pid.to_string())?;
-    std::fs::write(server_dir.join(PORT_FILE), port.to_string())?;
-
-    let (tx, _rx) = broadcast::channel(1000);
+    EXPOSE_ERROR_DETAILS.store(host.is_loopback(), Ordering::Relaxed);
+
+    let config = Config::load().unwrap_or_default();
+    let (tx, _rx) = broadcast::channel(
+        config
+            .broadcast_buffer
+            .unwrap_or(DEFAULT_BROADCAST_BUFFER)
+            .max(1),
+    );
+
+    let webhook = config.webhook_url.map(|url| Webhook {
+        url,
+        client: reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .unwrap_or_default(),
+    });
 
     let state = Arc::new(ServerState {
         db_manager: RwLock::new(DatabaseManager::new()),
         tx,
+        auth_token,
+        webhook,
+        pool: ProcessPool::new(
+            config
+                .max_concurrent
+                .unwrap_or(DEFAULT_MAX_CONCURRENT)
+                .max(1),
+        ),
+        slots: Mutex::new(SlotTable::default()),
     });
+    let shutdown_state = state.clone();
 
     let app = Router::new()
-        .route("/", get(index_handler))
         .route("/api/events", post(ingest_events))
         .route("/api/events", get(query_events))
+        .route("/api/search", get(search))
         .route("/api/forks", post(create_fork))
         .route("/api/forks", get(list_forks))
         .route("/api/forks/{fork_id}", patch(update_fork))
         .route("/api/forks/{fork_id}", get(get_fork))
+        .route("/api/forks/{fork_id}", delete(delete_fork))
+        .route("/api/forks/{fork_id}/tree", get(get_fork_tree))
+        .route("/api/forks/{fork_id}/lineage", get(get_fork_lineage))
+        .route("/api/forks/{fork_id}/cost", get(get_fork_cost))
+        .route("/api/forks/{fork_id}/read", post(mark_fork_read))
+        .route("/api/forks/read-all", post(mark_all_forks_read))
+        .route("/api/forks/{fork_id}/slot", post(request_slot))
+        .route("/api/pool", get(pool_metrics))
+        .route("/api/sessions", get(list_sessions))
+        .route("/api/jobs", get(list_jobs))
+        .route("/api/stats", get(project_stats))
+        .route("/api/maintenance/compact", post(compact_database))
+        .route("/api/maintenance/relink", post(relink_orphans))
         .route("/api/projects", get(list_projects))
+        .route("/api/stream", get(event_stream))
         .route("/ws", get(websocket_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        // Routes below stay open even when a token is configured
+        .route("/", get(index_handler))
+        .route("/api/health", get(health))
+        .layer(middleware::from_fn(trace_request))
         .with_state(state);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = bind_listener(host, port, scan_ports).await?;
+    let addr = listener.local_addr()?;
+    let port = addr.port();
+
+    // Only advertise the server once it can accept connections
+    let pid = std::process::id();
+    std::fs::write(server_dir.join(PID_FILE), pid.to_string())?;
+    std::fs::write(server_dir.join(PORT_FILE), port.to_string())?;
+
     println!("Forky server starting on http://{addr}");
     println!("Managing databases for all projects");
 
     if open_browser {
-        let _ = open::that(format!("http://{addr}"));
+        // A wildcard bind address isn't browsable; use loopback instead
+        let browse_host = if host.is_unspecified() {
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        } else {
+            host
+        };
+        let _ = open::that(format!("http://{}", SocketAddr::new(browse_host, port)));
     }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await.context("Server error")?;
+    let served = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await;
+
+    // Wait for any in-flight write to finish before the databases are dropped
+    drop(shutdown_state.db_manager.write().await);
 
     let _ = std::fs::remove_file(server_dir.join(PID_FILE));
     let _ = std::fs::remove_file(server_dir.join(PORT_FILE));
 
+    served.context("Server error")?;
+    println!("Forky server stopped");
+
     Ok(())
 }
 
-fn get_server_dir() -> Result<PathBuf> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
-    Ok(home.join(SERVER_DIR))
+/// Bind `host:port`, or with `scan_ports` the first free port above it.
+async fn bind_listener(
+    host: IpAddr,
+    port: u16,
+    scan_ports: bool,
+) -> Result<tokio::net::TcpListener> {
+    let attempts = if scan_ports { PORT_SCAN_ATTEMPTS } else { 1 };
+    let mut last = port;
+    for candidate in (port..=u16::MAX).take(attempts) {
+        last = candidate;
+        let addr = SocketAddr::new(host, candidate);
+        match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && scan_ports => {
+                tracing::warn!(port = candidate, "Port in use, trying the next one");
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to listen on {addr}")),
+        }
+    }
+    anyhow::bail!("Ports {port} to {last} are all in use")
 }
 
-pub fn get_server_port() -> Option<u16> {
-    let server_dir = get_server_dir().ok()?;
-    let pid_file = server_dir.join(PID_FILE);
-    let port_file = server_dir.join(PORT_FILE);
+/// Resolve when the process receives SIGINT (Ctrl-C) or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
 
-    if let Ok(pid_str) = std::fs::read_to_string(&pid_file) {
-        if let Ok(pid) = pid_str.trim().parse::<u32>() {
-            #[cfg(unix)]
-            {
-                use std::process::Command;
-                let output = Command::new("kill").args(["-0", &pid.to_string()]).output();
-                if output.map(|o| o.status.success()).unwrap_or(false) {
-                    if let Ok(port_str) = std::fs::read_to_string(&port_file) {
-                        return port_str.trim().parse().ok();
-                    }
-                }
-            }
-            #[cfg(not(unix))]
-            {
-                if let Ok(port_str) = std::fs::read_to_string(&port_file) {
-                    return port_str.trim().parse().ok();
-                }
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
             }
+            Err(_) => std::future::pending::<()>().await,
         }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
     }
-    None
 }
 
-pub fn spawn_server_daemon(port: u16) -> Result<()> {
-    use std::process::{Command, Stdio};
+fn get_server_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not find home directory")?;
+    Ok(home.join(SERVER_DIR))
+}
 
-    let exe = std::env::current_exe()?;
+/// How long to wait for the server port to accept a connection.
+const PORT_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
 
+/// How long a connected server gets to answer a health check.
+const HEALTH_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How long a freshly spawned server gets to pass its first health check.
+const SERVER_STARTUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Wait between health checks while the server starts.
+const SERVER_STARTUP_POLL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How long a stopped server gets to finish in-flight writes and exit.
+const SERVER_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+pub fn get_server_port() -> Option<u16> {
+    let pid: u32 = read_server_file(PID_FILE)?;
+    if !process_alive(pid) {
+        return None;
+    }
+    let port: u16 = read_server_file(PORT_FILE)?;
+
+    // A live PID isn't enough - the server may still be starting or be wedged
+    server_healthy(port).then_some(port)
+}
+
+/// What the PID and port files say about the background server.
+#[derive(Debug, Serialize)]
+pub struct ServerStatus {
+    pub pid: Option<u32>,
+    pub port: Option<u16>,
+    /// Whether the recorded PID belongs to a running process
+    pub alive: bool,
+    /// Whether the server answers health checks on `port`
+    pub healthy: bool,
+}
+
+/// Report on the background server without starting it.
+pub fn server_status() -> ServerStatus {
+    let pid: Option<u32> = read_server_file(PID_FILE);
+    let port: Option<u16> = read_server_file(PORT_FILE);
+    let alive = pid.is_some_and(process_alive);
+    ServerStatus {
+        pid,
+        port,
+        alive,
+        healthy: alive && port.is_some_and(server_healthy),
+    }
+}
+
+/// Stop the background server and remove its PID and port files.
+///
+/// Sends SIGTERM and waits for the process to exit, so in-flight writes
+/// finish first. A clean shutdown removes the files itself; this also clears
+/// ones left behind by a crash. Returns the PID that was stopped, or `None`
+/// if no server was running.
+///
+/// Only a PID that the server on the recorded port reports as its own is
+/// signalled. After a crash the PID may have been reused by an unrelated
+/// process, so an unverified PID just has its files removed.
+pub fn stop_server() -> Result<Option<u32>> {
+    let server_dir = get_server_dir()?;
+    let pid = read_server_file::<u32>(PID_FILE)
+        .filter(|&pid| read_server_file::<u16>(PORT_FILE).and_then(server_pid) == Some(pid));
+
+    if let Some(pid) = pid {
+        terminate_server(pid)?;
+    }
+
+    let _ = std::fs::remove_file(server_dir.join(PID_FILE));
+    let _ = std::fs::remove_file(server_dir.join(PORT_FILE));
+    Ok(pid)
+}
+
+/// Send SIGTERM to the server and wait for it to exit.
+#[cfg(unix)]
+fn terminate_server(pid: u32) -> Result<()> {
+    let signalled = std::process::Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()
+        .context("Failed to run kill")?;
+    anyhow::ensure!(signalled.success(), "Failed to signal server (PID {pid})");
+
+    let deadline = std::time::Instant::now() + SERVER_STOP_TIMEOUT;
+    while process_alive(pid) {
+        if std::time::Instant::now() >= deadline {
+            anyhow::bail!(
+                "Server (PID {pid}) did not exit within {}s",
+                SERVER_STOP_TIMEOUT.as_secs()
+            );
+        }
+        std::thread::sleep(SERVER_STARTUP_POLL);
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate_server(pid: u32) -> Result<()> {
+    anyhow::bail!("Stopping the server is only supported on Unix (PID {pid})")
+}
+
+/// Parse one of the files the server keeps in `~/.forky`.
+fn read_server_file<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let path = get_server_dir().ok()?.join(name);
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Check whether a process with this PID exists. Always true off Unix,
+/// where the health check has to stand in.
+fn process_alive(pid: u32) -> bool {
     #[cfg(unix)]
     {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+/// Check that the server answers `GET /api/health` with a 200.
+///
+/// An open port only shows the listener is bound, not that requests are
+/// being served. This is plain HTTP over a std socket so sync callers can
+/// use it without a runtime.
+fn server_healthy(port: u16) -> bool {
+    use std::io::Read;
+
+    let Some(mut stream) = send_health_check(port) else {
+        return false;
+    };
+    let mut status_line = [0u8; 12];
+    stream.read_exact(&mut status_line).is_ok() && status_line.ends_with(b" 200")
+}
+
+/// The PID the server on `port` reports from a healthy `GET /api/health`.
+fn server_pid(port: u16) -> Option<u32> {
+    use std::io::Read;
+
+    let mut response = String::new();
+    send_health_check(port)?
+        .read_to_string(&mut response)
+        .ok()?;
+    let (head, body) = response.split_once("\r\n\r\n")?;
+    if !head.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    let body: serde_json::Value = serde_json::from_str(body).ok()?;
+    body.get("pid")?.as_u64()?.try_into().ok()
+}
+
+/// Connect to the server on `port` and send `GET /api/health`, returning the
+/// stream to read the response from.
+fn send_health_check(port: u16) -> Option<std::net::TcpStream> {
+    use std::io::Write;
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = std::net::TcpStream::connect_timeout(&addr, PORT_PROBE_TIMEOUT).ok()?;
+    let _ = stream.set_read_timeout(Some(HEALTH_CHECK_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(HEALTH_CHECK_TIMEOUT));
+
+    let request = "GET /api/health HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n";
+    stream.write_all(request.as_bytes()).ok()?;
+    Some(stream)
+}
+
+/// Start `forky serve` in the background. It picks its own port (from the
+/// config file, skipping busy ones) and records it in the port file.
+pub fn spawn_server_daemon() -> Result<()> {
+    use std::process::{Command, Stdio};
+
+    let exe = std::env::current_exe()?;
+
+    #[cfg(unix)]
+    {
+        // Keep the daemon's diagnostics; RUST_LOG is inherited from this process
+        let server_dir = get_server_dir()?;
+        std::fs::create_dir_all(&server_dir)?;
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(server_dir.join(LOG_FILE))
+            .context("Failed to open server log")?;
+
         Command::new(&exe)
-            .args(["serve", "--port", &port.to_string()])
+            .arg("serve")
             .stdin(Stdio::null())
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
+            .stderr(log)
             .spawn()
             .context("Failed to spawn server daemon")?;
     }
@@ -320,12 +1281,11 @@ pub fn spawn_server_daemon(port: u16) -> Result<()> {
     #[cfg(not(unix))]
     {
         Command::new(&exe)
-            .args(["serve", "--port", &port.to_string()])
+            .arg("serve")
             .spawn()
             .context("Failed to spawn server daemon")?;
     }
 
-    std::thread::sleep(std::time::Duration::from_millis(500));
     Ok(())
 }
 
@@ -334,21 +1294,125 @@ pub fn ensure_server_running() -> Result<u16> {
         return Ok(port);
     }
 
-    let port = 58231;
-    spawn_server_daemon(port)?;
+    spawn_server_daemon()?;
 
-    for _ in 0..20 {
+    // Hand out the port only once the server is answering requests
+    let deadline = std::time::Instant::now() + SERVER_STARTUP_TIMEOUT;
+    while std::time::Instant::now() < deadline {
         if let Some(p) = get_server_port() {
             return Ok(p);
         }
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        std::thread::sleep(SERVER_STARTUP_POLL);
     }
 
-    anyhow::bail!("Server failed to start")
+    let log = get_server_dir()?.join(LOG_FILE);
+    anyhow::bail!(
+        "Server did not become ready within {}s (see {})",
+        SERVER_STARTUP_TIMEOUT.as_secs(),
+        log.display()
+    )
 }
 
 // === Handlers ===
 
+/// Reject requests without the configured bearer token.
+///
+/// Browsers can't set headers on a WebSocket upgrade, so a `token` query
+/// parameter is accepted as well.
+async fn require_auth(
+    State(state): State<Arc<ServerState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(ref expected) = state.auth_token else {
+        return Ok(next.run(req).await);
+    };
+
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = req.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .and_then(|token| urlencoding::decode(token).ok())
+    });
+
+    // Constant-time, so response timing doesn't reveal how much of a guessed
+    // token was right
+    let valid = |token: &str| bool::from(token.as_bytes().ct_eq(expected.as_bytes()));
+    if bearer.is_some_and(valid) || query.as_deref().is_some_and(valid) {
+        Ok(next.run(req).await)
+    } else {
+        Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid auth token",
+        ))
+    }
+}
+
+/// Run each request inside a span carrying its project and fork.
+///
+/// `project_path` and `fork_id` are taken from the query string and path
+/// here; handlers that receive them in a JSON body record them on the span.
+async fn trace_request(req: Request, next: Next) -> Response {
+    let uri = req.uri().clone();
+    let query_param = |name: &str| {
+        uri.query().and_then(|query| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                .and_then(|value| urlencoding::decode(value).ok())
+        })
+    };
+    let fork_id = uri
+        .path()
+        .strip_prefix("/api/forks/")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|id| *id != "read-all")
+        .map(std::borrow::Cow::Borrowed)
+        .or_else(|| query_param("fork_id"));
+
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        path = uri.path(),
+        project_path = tracing::field::Empty,
+        fork_id = tracing::field::Empty,
+    );
+    if let Some(project_path) = query_param("project_path") {
+        span.record("project_path", project_path.as_ref());
+    }
+    if let Some(fork_id) = fork_id {
+        span.record("fork_id", fork_id.as_ref());
+    }
+
+    let started = Instant::now();
+    let resp = next.run(req).instrument(span.clone()).await;
+    let _entered = span.enter();
+    let status = resp.status();
+    let elapsed_ms = started.elapsed().as_millis();
+    if status.is_server_error() {
+        tracing::error!(%status, elapsed_ms, "request failed");
+    } else if status.is_client_error() {
+        tracing::warn!(%status, elapsed_ms, "request rejected");
+    } else {
+        tracing::debug!(%status, elapsed_ms, "request handled");
+    }
+    resp
+}
+
+/// Record where a request body says it belongs on the current request span.
+fn record_request_target(project_path: &str, fork_id: Option<&str>) {
+    let span = tracing::Span::current();
+    span.record("project_path", project_path);
+    if let Some(fork_id) = fork_id {
+        span.record("fork_id", fork_id);
+    }
+}
+
 async fn index_handler() -> Html<&'static str> {
     Html(include_str!("ui.html"))
 }
@@ -356,64 +1420,122 @@ async fn index_handler() -> Html<&'static str> {
 async fn ingest_events(
     State(state): State<Arc<ServerState>>,
     Json(req): Json<IngestRequest>,
-) -> Result<Json<IngestResponse>, StatusCode> {
+) -> Result<Json<IngestResponse>, ApiError> {
+    record_request_target(&req.project_path, req.fork_id.as_deref());
     let project_path = PathBuf::from(&req.project_path);
     let mut db_manager = state.db_manager.write().await;
 
     let db = db_manager
         .get_or_create(&project_path)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .context("Failed to open database")?;
 
     let mut stored = 0;
-    let mut errors = 0;
+    let mut failures = Vec::new();
     let fork_id = req.fork_id.as_deref();
 
-    for event_json in &req.events {
+    // Parse everything up front so the whole batch goes in one transaction
+    let mut batch = Vec::new();
+    let mut batch_positions = Vec::new();
+    for (index, event_json) in req.events.iter().enumerate() {
+        let uuid = event_json
+            .get("uuid")
+            .and_then(serde_json::Value::as_str)
+            .map(String::from);
         let json_str = serde_json::to_string(event_json).unwrap_or_default();
         if let Some(event) = ClaudeEvent::parse(&json_str) {
-            match db.store_event(&event, fork_id) {
-                Ok(_) => {
-                    stored += 1;
-                    let _ = state.tx.send(EventBroadcast {
-                        project_path: req.project_path.clone(),
-                        event: StoredEvent::from_event(&event, fork_id),
-                        fork_id: req.fork_id.clone(),
-                    });
+            batch.push((event, fork_id));
+            batch_positions.push((index, uuid));
+        } else {
+            tracing::warn!(index, uuid = uuid.as_deref(), "Failed to parse event");
+            failures.push(IngestFailure {
+                index,
+                uuid,
+                error: "Failed to parse event".to_string(),
+            });
+        }
+    }
+
+    match db.store_events(&batch) {
+        Ok(entity_ids) => {
+            stored = entity_ids.iter().flatten().count();
+            for ((event, _), entity_id) in batch.iter().zip(entity_ids) {
+                // Events skipped as duplicates were already broadcast
+                if entity_id.is_none() {
+                    continue;
                 }
-                Err(_) => errors += 1,
+                let _ = state.tx.send(EventBroadcast {
+                    project_path: req.project_path.clone(),
+                    event: StoredEvent::from_event(event, fork_id),
+                    fork_id: req.fork_id.clone(),
+                });
             }
-        } else {
-            errors += 1;
+        }
+        Err(e) => {
+            tracing::error!(events = batch.len(), "Failed to store events: {e:#}");
+            // The transaction was rolled back, so every parsed event failed
+            for (index, uuid) in batch_positions {
+                failures.push(IngestFailure {
+                    index,
+                    uuid,
+                    error: format!("Failed to store event: {e}"),
+                });
+            }
+            failures.sort_by_key(|f| f.index);
         }
     }
 
-    Ok(Json(IngestResponse { stored, errors }))
+    tracing::debug!(stored, failed = failures.len(), "Ingested events");
+    Ok(Json(IngestResponse {
+        stored,
+        errors: failures.len(),
+        failed_indices: failures.iter().map(|f| f.index).collect(),
+        failures,
+    }))
 }
 
 async fn create_fork(
     State(state): State<Arc<ServerState>>,
     Json(req): Json<CreateForkRequest>,
-) -> Result<Json<CreateForkResponse>, StatusCode> {
+) -> Result<Json<CreateForkResponse>, ApiError> {
     use crate::names::generate_name;
 
+    record_request_target(&req.project_path, Some(&req.fork_id));
+
     let project_path = PathBuf::from(&req.project_path);
     let mut db_manager = state.db_manager.write().await;
 
-    let db = db_manager
-        .get_or_create(&project_path)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let db = db_manager.get_or_create(&project_path)?;
 
     // Generate a name if not provided
     let fork_name = req.fork_name.unwrap_or_else(|| generate_name().full_name);
 
+    // An unknown fork to retry is the caller's mistake, not ours
+    if let Some(ref original) = req.retry_of {
+        if db.get_fork(original)?.is_none() {
+            return Err(ApiError::fork_not_found(original));
+        }
+    }
+
+    let launch = ForkLaunch {
+        description: req.job_description,
+        model: req.model,
+        worktree: req.worktree,
+        worktree_path: req.worktree_path,
+        worktree_branch: req.worktree_branch,
+        worktree_base: req.worktree_base,
+        ai_provider: req.ai_provider,
+        retry_of: req.retry_of,
+        tags: req.tags,
+    };
+
     // Create fork entity in the graph
     db.create_fork(
         &req.fork_id,
         req.parent_session_id.as_deref(),
         "running",
         Some(&fork_name),
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        &launch,
+    )?;
 
     Ok(Json(CreateForkResponse {
         fork_id: req.fork_id,
@@ -426,65 +1548,386 @@ async fn update_fork(
     State(state): State<Arc<ServerState>>,
     Path(fork_id): Path<String>,
     Json(req): Json<UpdateForkRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    record_request_target(&req.project_path, None);
     let project_path = PathBuf::from(&req.project_path);
     let db_manager = state.db_manager.read().await;
 
     let db = db_manager
         .get(&project_path)
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .ok_or_else(ApiError::unknown_project)?;
+
+    let update = ForkUpdate {
+        session_id: req.session_id,
+        reason: req.reason,
+        result_subtype: req.result_subtype,
+        model: req.model,
+        summary: req.summary,
+    };
+    let previous = db
+        .update_fork_status(&fork_id, req.status, &update)?
+        .ok_or_else(|| ApiError::fork_not_found(&fork_id))?;
+
+    if req.status.is_finished() {
+        let mut slots = state.slots.lock().await;
+        slots.waiting.remove(&fork_id);
+        slots.held.remove(&fork_id);
+    }
+
+    if let Some(ref webhook) = state.webhook {
+        // A fork is often marked finished twice (by `forky done` and when its
+        // process exits), so only the first transition notifies
+        if req.status.is_finished() && !previous.is_finished() {
+            let name = db.get_fork(&fork_id).ok().flatten().and_then(|fork| {
+                match fork.properties.get("fork_name") {
+                    Some(manifoldb_core::Value::String(name)) => Some(name.clone()),
+                    _ => None,
+                }
+            });
+            webhook.fire(WebhookPayload {
+                fork_id,
+                name,
+                status: req.status,
+                project_path: req.project_path,
+                summary: update.summary.or(update.reason),
+            });
+        }
+    }
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+async fn delete_fork(
+    State(state): State<Arc<ServerState>>,
+    Path(fork_id): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let events_deleted = db
+        .delete_fork(&fork_id)?
+        .ok_or_else(|| ApiError::fork_not_found(&fork_id))?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "events_deleted": events_deleted,
+    })))
+}
+
+async fn mark_fork_read(
+    State(state): State<Arc<ServerState>>,
+    Path(fork_id): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
 
-    db.update_fork_status(&fork_id, &req.status, req.session_id.as_deref())
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    db.get_fork(&fork_id)?
+        .ok_or_else(|| ApiError::fork_not_found(&fork_id))?;
+    db.mark_fork_read(&fork_id)?;
 
     Ok(Json(serde_json::json!({"success": true})))
 }
 
+async fn mark_all_forks_read(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let marked = db.mark_all_forks_read()?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "marked": marked,
+    })))
+}
+
+/// Compact a project's database. Holds the write lock, so no writes are in
+/// flight while the file is rewritten.
+async fn compact_database(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let reclaimed = db_manager
+        .compact(&project_path)
+        .context("Failed to compact database")?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "reclaimed_bytes": reclaimed,
+    })))
+}
+
+async fn relink_orphans(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let linked = db.relink_orphans().context("Failed to relink events")?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "linked": linked,
+    })))
+}
+
 async fn get_fork(
     State(state): State<Arc<ServerState>>,
     Path(fork_id): Path<String>,
     Query(params): Query<QueryParams>,
-) -> Result<Json<Option<ForkSummary>>, StatusCode> {
-    use manifoldb_core::Value;
+) -> Result<Json<Option<ForkSummary>>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    // Write lock so the project's database can be loaded from disk
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+    let fork_id = resolve_fork_id(db, &fork_id)?;
+
+    let fork = db.get_fork(&fork_id)?;
+    let event_count = db.count_events_for_fork(&fork_id)?;
+
+    let summary = fork.map(|f| ForkSummary::from_entity(&project_path, &f, event_count));
+
+    Ok(Json(summary))
+}
+
+/// Expand a fork ID prefix to the full ID of the one fork it matches.
+///
+/// Full IDs, and prefixes that match nothing, come back unchanged so the
+/// caller reports the fork as missing.
+fn resolve_fork_id(db: &GraphDatabase, reference: &str) -> Result<String, ApiError> {
+    let mut matches = db.fork_ids_with_prefix(reference);
+    if matches.len() > 1 && !matches.iter().any(|id| id == reference) {
+        return Err(ApiError::ambiguous_fork(reference, &matches));
+    }
+    if matches.len() == 1 {
+        return Ok(matches.remove(0));
+    }
+    Ok(reference.to_string())
+}
 
+async fn get_fork_tree(
+    State(state): State<Arc<ServerState>>,
+    Path(fork_id): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<EventNode>, ApiError> {
     let project_path = params
         .project_path
         .as_ref()
         .map(PathBuf::from)
-        .ok_or(StatusCode::BAD_REQUEST)?;
+        .ok_or_else(ApiError::missing_project_path)?;
 
     let db_manager = state.db_manager.read().await;
-    let db = db_manager.get(&project_path).ok_or(StatusCode::NOT_FOUND)?;
+    let db = db_manager
+        .get(&project_path)
+        .ok_or_else(ApiError::unknown_project)?;
+    let fork_id = resolve_fork_id(db, &fork_id)?;
 
-    let fork = db.get_fork(&fork_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if db.get_fork(&fork_id)?.is_none() {
+        return Err(ApiError::fork_not_found(&fork_id));
+    }
 
-    let summary = fork.map(|f| {
-        let get_str = |key: &str| -> Option<String> {
-            f.properties.get(key).and_then(|v| match v {
-                Value::String(s) => Some(s.clone()),
-                _ => None,
-            })
-        };
+    let tree = db.get_event_tree(&fork_id)?;
 
-        ForkSummary {
-            project_path: project_path.to_string_lossy().to_string(),
-            fork_id: get_str("fork_id").unwrap_or_default(),
-            fork_name: get_str("fork_name"),
-            session_id: get_str("session_id"),
-            parent_session_id: get_str("parent_session_id"),
-            status: get_str("status").unwrap_or_else(|| "unknown".to_string()),
-            event_count: 0,
-            created_at: get_str("created_at"),
+    Ok(Json(tree))
+}
+
+/// Queue a fork for a run slot, or report where it is in the queue.
+///
+/// The CLI polls this until `granted`, then keeps polling to renew the slot.
+/// It is held until the fork reports a finished status through
+/// `PATCH /api/forks/:id`, or goes `SLOT_LEASE_TTL` without renewing it.
+async fn request_slot(
+    State(state): State<Arc<ServerState>>,
+    Path(fork_id): Path<String>,
+) -> Json<SlotStatus> {
+    let mut slots = state.slots.lock().await;
+    let now = Instant::now();
+
+    // Dropping a stale request or lease hands its place to the next fork
+    slots
+        .waiting
+        .retain(|_, (_, polled)| now.duration_since(*polled) < SLOT_REQUEST_TTL);
+    slots
+        .held
+        .retain(|_, (_, renewed)| now.duration_since(*renewed) < SLOT_LEASE_TTL);
+
+    if let Some((_, renewed)) = slots.held.get_mut(&fork_id) {
+        *renewed = now;
+        return Json(SlotStatus {
+            granted: true,
+            position: 0,
+        });
+    }
+
+    let (request, polled) = slots
+        .waiting
+        .entry(fork_id.clone())
+        .or_insert_with(|| (state.pool.request_slot(), now));
+    *polled = now;
+
+    if let Some(slot) = request.try_take() {
+        slots.waiting.remove(&fork_id);
+        slots.held.insert(fork_id, (slot, now));
+        return Json(SlotStatus {
+            granted: true,
+            position: 0,
+        });
+    }
+
+    let position = request.position().unwrap_or(0);
+    drop(slots);
+    Json(SlotStatus {
+        granted: false,
+        position,
+    })
+}
+
+/// Run slot usage shared by every project on this server.
+async fn pool_metrics(State(state): State<Arc<ServerState>>) -> Json<PoolMetrics> {
+    Json(state.pool.metrics())
+}
+
+async fn get_fork_lineage(
+    State(state): State<Arc<ServerState>>,
+    Path(fork_id): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<Vec<ForkSummary>>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let db_manager = state.db_manager.read().await;
+    let db = db_manager
+        .get(&project_path)
+        .ok_or_else(ApiError::unknown_project)?;
+    let fork_id = resolve_fork_id(db, &fork_id)?;
+
+    let lineage = db.get_fork_lineage(&fork_id)?;
+    if lineage.is_empty() {
+        return Err(ApiError::fork_not_found(&fork_id));
+    }
+    let event_counts = db.count_events_by_fork()?;
+
+    // A root Session that no fork owns has nothing to summarise
+    let summaries = lineage
+        .iter()
+        .filter(|e| e.labels.iter().any(|l| l.as_str() == LABEL_FORK))
+        .map(|e| {
+            let fork = ForkSummary::from_entity(&project_path, e, 0);
+            let event_count = event_counts.get(&fork.fork_id).copied().unwrap_or(0);
+            ForkSummary {
+                event_count,
+                ..fork
+            }
+        })
+        .collect();
+
+    Ok(Json(summaries))
+}
+
+async fn get_fork_cost(
+    State(state): State<Arc<ServerState>>,
+    Path(fork_id): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<CostBreakdown>, ApiError> {
+    use manifoldb_core::Value;
+    use manifoldb_graph::store::NodeStore;
+    use manifoldb_storage::StorageEngine;
+
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    // Write lock so the project's database can be loaded from disk
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+    let fork_id = resolve_fork_id(db, &fork_id)?;
+
+    if db.get_fork(&fork_id)?.is_none() {
+        return Err(ApiError::fork_not_found(&fork_id));
+    }
+
+    let tx = db.engine().begin_read()?;
+    let wanted = Value::String(fork_id.clone());
+    let mut events: Vec<((String, u64), StoredEvent, Vec<String>)> = Vec::new();
+
+    NodeStore::for_each(&tx, |entity| {
+        let is_event = entity.labels.iter().any(|l| l.as_str() == "Event");
+        if !is_event || entity.properties.get("fork_id") != Some(&wanted) {
+            return true;
         }
-    });
 
-    Ok(Json(summary))
+        let event = StoredEvent::from_entity(entity);
+        let tool_use_ids = match entity.properties.get("tool_use_ids") {
+            Some(Value::String(json)) => serde_json::from_str(json).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let sort_key = (
+            event.created_at.clone().unwrap_or_default(),
+            entity.id.as_u64(),
+        );
+        events.push((sort_key, event, tool_use_ids));
+        true
+    })?;
+
+    events.sort_by(|a, b| a.0.cmp(&b.0));
+    let events: Vec<_> = events
+        .into_iter()
+        .map(|(_, event, ids)| (event, ids))
+        .collect();
+
+    Ok(Json(CostBreakdown::from_events(&fork_id, &events)))
 }
 
 async fn list_forks(
     State(state): State<Arc<ServerState>>,
     Query(params): Query<QueryParams>,
-) -> Result<Json<Vec<ForkSummary>>, StatusCode> {
+) -> Result<Json<Vec<ForkSummary>>, ApiError> {
     use manifoldb_core::Value;
     use manifoldb_graph::store::NodeStore;
     use manifoldb_storage::StorageEngine;
@@ -504,7 +1947,9 @@ async fn list_forks(
             vec![]
         }
     } else {
-        let db_manager = state.db_manager.read().await;
+        // Write lock so projects known from before a restart can be loaded
+        let mut db_manager = state.db_manager.write().await;
+        db_manager.load_known_projects();
         db_manager.list_projects()
     };
 
@@ -514,10 +1959,7 @@ async fn list_forks(
             continue;
         };
 
-        let tx = db
-            .engine()
-            .begin_read()
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let tx = db.engine().begin_read()?;
 
         // First, collect Fork entities
         let mut forks_map: HashMap<String, ForkSummary> = HashMap::new();
@@ -525,50 +1967,34 @@ async fn list_forks(
         NodeStore::for_each(&tx, |entity| {
             let is_fork = entity.labels.iter().any(|l| l.as_str() == "Fork");
             if is_fork {
-                let get_str = |key: &str| -> Option<String> {
-                    entity.properties.get(key).and_then(|v| match v {
-                        Value::String(s) => Some(s.clone()),
-                        _ => None,
-                    })
-                };
-
-                if let Some(fork_id) = get_str("fork_id") {
+                if let Some(Value::String(fork_id)) = entity.properties.get("fork_id") {
                     forks_map.insert(
                         fork_id.clone(),
-                        ForkSummary {
-                            project_path: project_path.to_string_lossy().to_string(),
-                            fork_id,
-                            fork_name: get_str("fork_name"),
-                            session_id: get_str("session_id"),
-                            parent_session_id: get_str("parent_session_id"),
-                            status: get_str("status").unwrap_or_else(|| "running".to_string()),
-                            event_count: 0,
-                            created_at: get_str("created_at"),
-                        },
+                        ForkSummary::from_entity(&project_path, entity, 0),
                     );
                 }
             }
             true
-        })
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        })?;
 
         // Count events per fork
-        NodeStore::for_each(&tx, |entity| {
-            let is_event = entity.labels.iter().any(|l| l.as_str() == "Event");
-            if is_event {
-                if let Some(Value::String(fork_id)) = entity.properties.get("fork_id") {
-                    if let Some(fork) = forks_map.get_mut(fork_id) {
-                        fork.event_count += 1;
-                    }
-                }
-            }
-            true
-        })
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let counts = db.count_events_by_fork()?;
+        for (fork_id, fork) in &mut forks_map {
+            fork.event_count = counts.get(fork_id).copied().unwrap_or(0);
+        }
 
         all_forks.extend(forks_map.into_values());
     }
 
+    if let Some(ref tag) = params.tag {
+        let wanted: Vec<&str> = tag
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        all_forks.retain(|fork| wanted.iter().all(|w| fork.tags.iter().any(|t| t == w)));
+    }
+
     // Sort by created_at descending
     all_forks.sort_by(|a, b| {
         let a_time = a.created_at.as_deref().unwrap_or("");
@@ -579,11 +2005,72 @@ async fn list_forks(
     Ok(Json(all_forks))
 }
 
+/// Filters applied by `GET /api/events`.
+struct EventFilter<'a> {
+    fork_id: Option<&'a str>,
+    session: Option<&'a str>,
+    /// Match `session` exactly rather than as a prefix.
+    exact_session: bool,
+    event_type: Option<&'a str>,
+    since: Option<DateTime<FixedOffset>>,
+    until: Option<DateTime<FixedOffset>>,
+}
+
+impl EventFilter<'_> {
+    /// Whether an entity is an event that passes every filter.
+    fn matches(&self, entity: &manifoldb_core::Entity) -> bool {
+        use manifoldb_core::Value;
+
+        let is_event = entity.labels.iter().any(|l| l.as_str() == "Event");
+        if !is_event {
+            return false;
+        }
+        let string_property = |key: &str| match entity.properties.get(key) {
+            Some(Value::String(s)) => Some(s.as_str()),
+            _ => None,
+        };
+
+        if self.fork_id.is_some() && string_property("fork_id") != self.fork_id {
+            return false;
+        }
+
+        if let Some(filter) = self.session {
+            let session = string_property("session_id");
+            let is_match = if self.exact_session {
+                session == Some(filter)
+            } else {
+                session.is_some_and(|s| s.starts_with(filter))
+            };
+            if !is_match {
+                return false;
+            }
+        }
+
+        if self.event_type.is_some() && string_property("type") != self.event_type {
+            return false;
+        }
+
+        // Events without a timestamp never match a time range
+        if self.since.is_some() || self.until.is_some() {
+            let created_at =
+                string_property("created_at").and_then(|s| DateTime::parse_from_rfc3339(s).ok());
+            let Some(created_at) = created_at else {
+                return false;
+            };
+            if self.since.is_some_and(|t| created_at < t)
+                || self.until.is_some_and(|t| created_at > t)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 async fn query_events(
     State(state): State<Arc<ServerState>>,
     Query(params): Query<QueryParams>,
-) -> Result<Json<Vec<StoredEvent>>, StatusCode> {
-    use manifoldb_core::Value;
+) -> Result<Json<EventsPage>, ApiError> {
     use manifoldb_graph::store::NodeStore;
     use manifoldb_storage::StorageEngine;
 
@@ -591,99 +2078,396 @@ async fn query_events(
         .project_path
         .as_ref()
         .map(PathBuf::from)
-        .ok_or(StatusCode::BAD_REQUEST)?;
+        .ok_or_else(ApiError::missing_project_path)?;
 
     let db_manager = state.db_manager.read().await;
-    let db = db_manager.get(&project_path).ok_or(StatusCode::NOT_FOUND)?;
+    let db = db_manager
+        .get(&project_path)
+        .ok_or_else(ApiError::unknown_project)?;
+    let fork_filter = params
+        .fork_id
+        .as_deref()
+        .map(|reference| resolve_fork_id(db, reference))
+        .transpose()?;
+
+    let tx = db.engine().begin_read()?;
+
+    let exact_session = match params.session_match.as_deref() {
+        None | Some("prefix") => false,
+        Some("exact") => true,
+        Some(other) => {
+            return Err(ApiError::bad_request(format!(
+                "session_match must be \"prefix\" or \"exact\", not \"{other}\""
+            )))
+        }
+    };
 
-    let tx = db
-        .engine()
-        .begin_read()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let filter = EventFilter {
+        fork_id: fork_filter.as_deref(),
+        session: params.session.as_deref(),
+        exact_session,
+        event_type: params.event_type.as_deref(),
+        since: parse_time_param(params.since.as_deref())?,
+        until: parse_time_param(params.until.as_deref())?,
+    };
 
     let limit = params.limit.unwrap_or(100);
-    let mut events = Vec::new();
+    // (created_at, entity id) gives a stable order that survives concurrent inserts
+    let mut matched: Vec<((String, u64), StoredEvent)> = Vec::new();
 
     NodeStore::for_each(&tx, |entity| {
-        let is_event = entity.labels.iter().any(|l| l.as_str() == "Event");
-        if !is_event {
+        if !filter.matches(entity) {
             return true;
         }
 
-        // Apply fork_id filter
-        if let Some(ref filter) = params.fork_id {
-            let fork = entity.properties.get("fork_id").and_then(|v| match v {
-                Value::String(s) => Some(s.as_str()),
-                _ => None,
-            });
-            if fork != Some(filter.as_str()) {
-                return true;
-            }
+        let event = StoredEvent::from_entity(entity);
+
+        let sort_key = (
+            event.created_at.clone().unwrap_or_default(),
+            entity.id.as_u64(),
+        );
+        matched.push((sort_key, event));
+        true
+    })?;
+
+    match params.order.as_deref() {
+        None | Some("desc") => matched.sort_by(|a, b| b.0.cmp(&a.0)),
+        Some("asc") => matched.sort_by(|a, b| a.0.cmp(&b.0)),
+        Some(other) => {
+            return Err(ApiError::bad_request(format!(
+                "order must be \"asc\" or \"desc\", not \"{other}\""
+            )))
         }
+    }
 
-        // Apply session filter
-        if let Some(ref filter) = params.session {
-            let session = entity.properties.get("session_id").and_then(|v| match v {
-                Value::String(s) => Some(s.as_str()),
-                _ => None,
-            });
-            if session.map(|s| !s.starts_with(filter)).unwrap_or(true) {
-                return true;
-            }
+    // Resume after the cursor event if it is known, then apply the offset
+    let after_cursor = params
+        .cursor
+        .as_deref()
+        .and_then(|cursor| {
+            matched
+                .iter()
+                .position(|(_, e)| e.uuid.as_deref() == Some(cursor))
+        })
+        .map_or(0, |pos| pos + 1);
+    let start = after_cursor + params.offset.unwrap_or(0);
+    let total = matched.len();
+
+    let events: Vec<StoredEvent> = matched
+        .into_iter()
+        .skip(start)
+        .take(limit)
+        .map(|(_, e)| e)
+        .collect();
+
+    let end = start + events.len();
+    let has_more = end < total;
+
+    Ok(Json(EventsPage {
+        next_offset: has_more.then_some(end),
+        next_cursor: if has_more {
+            events.last().and_then(|e| e.uuid.clone())
+        } else {
+            None
+        },
+        events,
+    }))
+}
+
+async fn search(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<SearchResults>, ApiError> {
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+    let query = params
+        .q
+        .as_deref()
+        .filter(|q| !q.is_empty())
+        .ok_or_else(|| ApiError::bad_request("q is required"))?;
+
+    // `forky search` may be the first request after the server starts, so
+    // open the project rather than requiring it to be loaded
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let results = search_events(db, query, params.limit.unwrap_or(20))?;
+
+    Ok(Json(SearchResults { results }))
+}
+
+/// Scan every event for `query` in its message, thinking or result.
+fn search_events(db: &GraphDatabase, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+    use manifoldb_graph::store::NodeStore;
+    use manifoldb_storage::StorageEngine;
+
+    let needle = query.to_lowercase();
+    let tx = db.engine().begin_read()?;
+
+    let mut hits: Vec<((String, u64), SearchHit)> = Vec::new();
+    NodeStore::for_each(&tx, |entity| {
+        if !entity.labels.iter().any(|l| l.as_str() == "Event") {
+            return true;
         }
 
-        let get_str = |key: &str| -> Option<String> {
-            entity.properties.get(key).and_then(|v| match v {
-                Value::String(s) => Some(s.clone()),
-                _ => None,
-            })
-        };
-        let get_int = |key: &str| -> Option<i64> {
-            entity.properties.get(key).and_then(|v| match v {
-                Value::Int(i) => Some(*i),
-                _ => None,
-            })
-        };
-        let get_float = |key: &str| -> Option<f64> {
-            entity.properties.get(key).and_then(|v| match v {
-                Value::Float(f) => Some(*f),
-                _ => None,
-            })
-        };
+        let event = StoredEvent::from_entity(entity);
+        let matched = [
+            ("message", &event.message),
+            ("thinking", &event.thinking),
+            ("result", &event.result),
+        ]
+        .into_iter()
+        .find_map(|(field, text)| {
+            let text = text.as_deref()?;
+            let pos = find_ignore_case(text, &needle)?;
+            Some((field, snippet(text, pos, needle.chars().count())))
+        });
+
+        if let Some((field, snippet)) = matched {
+            let sort_key = (
+                event.created_at.clone().unwrap_or_default(),
+                entity.id.as_u64(),
+            );
+            hits.push((
+                sort_key,
+                SearchHit {
+                    field: field.to_string(),
+                    snippet,
+                    event,
+                },
+            ));
+        }
+        true
+    })?;
 
-        let event = StoredEvent {
-            fork_id: get_str("fork_id"),
-            uuid: get_str("uuid"),
-            session_id: get_str("session_id"),
-            parent_tool_use_id: get_str("parent_tool_use_id"),
-            event_type: get_str("type").unwrap_or_else(|| "unknown".to_string()),
-            subtype: get_str("subtype"),
-            message: get_str("message"),
-            thinking: get_str("thinking"),
-            result: get_str("result"),
-            model: get_str("model"),
-            message_id: get_str("message_id"),
-            role: get_str("role"),
-            tool_uses: get_str("tool_uses").and_then(|s| serde_json::from_str(&s).ok()),
-            tool_results: get_str("tool_results").and_then(|s| serde_json::from_str(&s).ok()),
-            cost_usd: get_float("cost_usd"),
-            total_cost_usd: get_float("total_cost_usd"),
-            duration_ms: get_int("duration_ms").map(|i| i as u64),
-            num_turns: get_int("num_turns").map(|i| i as u32),
-            raw: get_str("raw").and_then(|s| serde_json::from_str(&s).ok()),
-        };
+    hits.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(hits.into_iter().take(limit).map(|(_, hit)| hit).collect())
+}
 
-        events.push(event);
-        events.len() < limit
-    })
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// Byte offset of a lowercase `needle` in `text`, ignoring case.
+fn find_ignore_case(text: &str, needle: &str) -> Option<usize> {
+    let lower = text.to_lowercase();
+    let pos = lower.find(needle)?;
+    // Lowercasing can change byte lengths, so only trust the offset if it didn't
+    if lower.len() == text.len() && text.is_char_boundary(pos) {
+        return Some(pos);
+    }
+    text.char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| text[i..].to_lowercase().starts_with(needle))
+}
 
-    Ok(Json(events))
+/// Cut a snippet of `text` around the match of `match_chars` characters at `pos`.
+fn snippet(text: &str, pos: usize, match_chars: usize) -> String {
+    let end = text[pos..]
+        .char_indices()
+        .nth(match_chars)
+        .map_or(text.len(), |(i, _)| pos + i);
+
+    let before: Vec<char> = text[..pos].chars().rev().take(SNIPPET_CONTEXT).collect();
+    let after: String = text[end..].chars().take(SNIPPET_CONTEXT).collect();
+    let start = pos - before.iter().map(|c| c.len_utf8()).sum::<usize>();
+
+    let lead = if start > 0 { "..." } else { "" };
+    let trail = if end + after.len() < text.len() {
+        "..."
+    } else {
+        ""
+    };
+
+    format!(
+        "{lead}{}**{}**{after}{trail}",
+        &text[start..pos],
+        &text[pos..end]
+    )
+}
+
+async fn list_sessions(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<Vec<SessionSummary>>, ApiError> {
+    use manifoldb_core::Value;
+
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let sessions = db
+        .list_sessions()?
+        .into_iter()
+        .map(|(entity, fork_id)| {
+            let get_str = |key: &str| -> Option<String> {
+                entity.properties.get(key).and_then(|v| match v {
+                    Value::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+            };
+            SessionSummary {
+                session_id: get_str("session_id"),
+                fork_id,
+                created_at: get_str("created_at"),
+            }
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+async fn list_jobs(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<Vec<JobSummary>>, ApiError> {
+    use manifoldb_core::Value;
+
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let jobs = db
+        .list_jobs()?
+        .into_iter()
+        .map(|(entity, fork_id)| {
+            let get_str = |key: &str| -> Option<String> {
+                entity.properties.get(key).and_then(|v| match v {
+                    Value::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+            };
+            JobSummary {
+                job_id: get_str("job_id"),
+                description: get_str("description"),
+                status: get_str("status"),
+                fork_id,
+                created_at: get_str("created_at"),
+            }
+        })
+        .collect();
+
+    Ok(Json(jobs))
+}
+
+async fn project_stats(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<QueryParams>,
+) -> Result<Json<ProjectStats>, ApiError> {
+    use manifoldb_core::Value;
+    use manifoldb_graph::store::NodeStore;
+    use manifoldb_storage::StorageEngine;
+
+    let project_path = params
+        .project_path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(ApiError::missing_project_path)?;
+
+    // Write lock so the project's database can be loaded from disk
+    let mut db_manager = state.db_manager.write().await;
+    let db = db_manager.get_or_create(&project_path)?;
+
+    let tx = db.engine().begin_read()?;
+
+    let mut summary = ProjectStats {
+        project_path: project_path.to_string_lossy().to_string(),
+        ..ProjectStats::default()
+    };
+    // Claude repeats a message's usage on every content-block event, so
+    // each message is only counted once
+    let mut counted_messages = HashSet::new();
+    let today = chrono::Local::now().date_naive();
+
+    NodeStore::for_each(&tx, |entity| {
+        let is_fork = entity.labels.iter().any(|l| l.as_str() == "Fork");
+        let is_event = entity.labels.iter().any(|l| l.as_str() == "Event");
+
+        if is_fork {
+            let status = match entity.properties.get("status") {
+                Some(Value::String(s)) => s.clone(),
+                _ => "running".to_string(),
+            };
+            summary.fork_count += 1;
+            *summary.forks_by_status.entry(status).or_insert(0) += 1;
+        } else if is_event {
+            summary.event_count += 1;
+
+            // total_cost_usd is authoritative when present (result events)
+            let cost = match (
+                entity.properties.get("total_cost_usd"),
+                entity.properties.get("cost_usd"),
+            ) {
+                (Some(Value::Float(total)), _) => *total,
+                (_, Some(Value::Float(cost))) => *cost,
+                _ => 0.0,
+            };
+            summary.total_cost_usd += cost;
+
+            let created_today = match entity.properties.get("created_at") {
+                Some(Value::String(ts)) => DateTime::parse_from_rfc3339(ts)
+                    .is_ok_and(|ts| ts.with_timezone(&chrono::Local).date_naive() == today),
+                _ => false,
+            };
+            if created_today {
+                summary.cost_today_usd += cost;
+            }
+
+            if let Some(Value::Int(turns)) = entity.properties.get("num_turns") {
+                summary.total_turns += u64::try_from(*turns).unwrap_or(0);
+            }
+            if let Some(Value::Int(duration)) = entity.properties.get("duration_ms") {
+                summary.total_duration_ms += u64::try_from(*duration).unwrap_or(0);
+            }
+
+            let get_tokens = |key: &str| match entity.properties.get(key) {
+                Some(Value::Int(n)) => u64::try_from(*n).unwrap_or(0),
+                _ => 0,
+            };
+            let tokens = TokenTotals {
+                input: get_tokens("input_tokens"),
+                output: get_tokens("output_tokens"),
+                cache_read: get_tokens("cache_read_tokens"),
+                cache_creation: get_tokens("cache_creation_tokens"),
+            };
+            let first_sighting = match entity.properties.get("message_id") {
+                Some(Value::String(id)) => counted_messages.insert(id.clone()),
+                _ => true,
+            };
+            if first_sighting {
+                summary.tokens.add(&tokens);
+                if let Some(Value::String(fork_id)) = entity.properties.get("fork_id") {
+                    summary
+                        .tokens_by_fork
+                        .entry(fork_id.clone())
+                        .or_default()
+                        .add(&tokens);
+                }
+            }
+        }
+        true
+    })?;
+
+    if summary.fork_count > 0 {
+        summary.avg_cost_per_fork = summary.total_cost_usd
+            / f64::from(u32::try_from(summary.fork_count).unwrap_or(u32::MAX));
+    }
+
+    Ok(Json(summary))
 }
 
 async fn list_projects(
     State(state): State<Arc<ServerState>>,
-) -> Result<Json<Vec<String>>, StatusCode> {
+) -> Result<Json<Vec<String>>, ApiError> {
     let db_manager = state.db_manager.read().await;
     let projects: Vec<String> = db_manager
         .list_projects()
@@ -693,23 +2477,335 @@ async fn list_projects(
     Ok(Json(projects))
 }
 
+/// Report the server's PID, so `stop_server` only signals a verified process.
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok", "pid": std::process::id() }))
+}
+
+/// Stream broadcasts as Server-Sent Events, for clients that can't use a WebSocket.
+///
+/// Each event is a `data:` frame holding the same JSON as `/ws`; a client
+/// that falls behind gets an `event: lagged` frame instead.
+async fn event_stream(
+    State(state): State<Arc<ServerState>>,
+    Query(filter): Query<StreamFilter>,
+) -> Sse<impl futures_util::Stream<Item = Result<SseEvent, std::convert::Infallible>>> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let rx = state.tx.subscribe();
+    let stream = futures_util::stream::unfold((rx, filter), |(mut rx, filter)| async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(broadcast) if filter.matches(&broadcast) => {
+                    match SseEvent::default().json_data(&broadcast) {
+                        Ok(event) => event,
+                        Err(_) => continue,
+                    }
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => SseEvent::default()
+                    .event("lagged")
+                    .data(serde_json::json!({ "missed": missed }).to_string()),
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), (rx, filter)));
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<ServerState>>,
+    Query(filter): Query<StreamFilter>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_websocket(socket, state))
+    ws.on_upgrade(|socket| handle_websocket(socket, state, filter))
 }
 
-async fn handle_websocket(mut socket: axum::extract::ws::WebSocket, state: Arc<ServerState>) {
+async fn handle_websocket(
+    mut socket: axum::extract::ws::WebSocket,
+    state: Arc<ServerState>,
+    filter: StreamFilter,
+) {
     use axum::extract::ws::Message;
 
+    use tokio::sync::broadcast::error::RecvError;
+
     let mut rx = state.tx.subscribe();
 
-    while let Ok(broadcast) = rx.recv().await {
-        if let Ok(json) = serde_json::to_string(&broadcast) {
+    loop {
+        let message = match rx.recv().await {
+            Ok(broadcast) if filter.matches(&broadcast) => serde_json::to_string(&broadcast),
+            Ok(_) => continue,
+            // A slow client fell behind the buffer: tell it to re-fetch
+            // rather than dropping the connection
+            Err(RecvError::Lagged(missed)) => {
+                serde_json::to_string(&serde_json::json!({"type": "lagged", "missed": missed}))
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if let Ok(json) = message {
             if socket.send(Message::Text(json.into())).await.is_err() {
                 break;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = "/projects/demo";
+
+    /// Server state with one in-memory project database and no registry file.
+    fn test_state() -> Arc<ServerState> {
+        test_state_with_webhook(None)
+    }
+
+    fn test_state_with_webhook(webhook: Option<Webhook>) -> Arc<ServerState> {
+        let project = PathBuf::from(PROJECT);
+        let db_manager = DatabaseManager {
+            databases: HashMap::from([(project.clone(), GraphDatabase::open_memory().unwrap())]),
+            known_projects: BTreeSet::from([project]),
+            registry_path: None,
+        };
+        let (tx, _) = broadcast::channel(16);
+        Arc::new(ServerState {
+            db_manager: RwLock::new(db_manager),
+            tx,
+            auth_token: None,
+            webhook,
+            pool: ProcessPool::new(1),
+            slots: Mutex::new(SlotTable::default()),
+        })
+    }
+
+    fn query(params: serde_json::Value) -> Query<QueryParams> {
+        Query(serde_json::from_value(params).unwrap())
+    }
+
+    async fn create(state: &Arc<ServerState>, fork_id: &str) {
+        let req = serde_json::from_value(serde_json::json!({
+            "project_path": PROJECT,
+            "fork_id": fork_id,
+            "fork_name": fork_id,
+        }))
+        .unwrap();
+        let Json(created) = create_fork(State(state.clone()), Json(req)).await.unwrap();
+        assert!(created.success);
+    }
+
+    /// Answer one connection on a local port with `response`.
+    fn canned_server(response: &'static str) -> u16 {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        port
+    }
+
+    #[test]
+    fn test_server_pid() {
+        let port = canned_server(
+            "HTTP/1.1 200 OK\r\ncontent-length: 27\r\n\r\n{\"pid\":4242,\"status\":\"ok\"}",
+        );
+        assert_eq!(server_pid(port), Some(4242));
+
+        // Something else on the port, or a server too old to report its PID
+        let port = canned_server("HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n");
+        assert_eq!(server_pid(port), None);
+        let port =
+            canned_server("HTTP/1.1 200 OK\r\ncontent-length: 15\r\n\r\n{\"status\":\"ok\"}");
+        assert_eq!(server_pid(port), None);
+    }
+
+    #[tokio::test]
+    async fn test_unrenewed_slot_expires() {
+        let state = test_state();
+        let request = |fork_id: &str| request_slot(State(state.clone()), Path(fork_id.to_string()));
+
+        assert!(request("a").await.granted);
+        assert!(!request("b").await.granted);
+
+        // Renewing keeps the slot
+        assert!(request("a").await.granted);
+        assert!(!request("b").await.granted);
+
+        // A lease left to lapse goes to the next fork in the queue
+        let lapsed = Instant::now().checked_sub(SLOT_LEASE_TTL).unwrap();
+        state.slots.lock().await.held.get_mut("a").unwrap().1 = lapsed;
+        assert!(request("b").await.granted);
+        assert!(!request("a").await.granted);
+    }
+
+    /// Accept webhook calls on a local port, sending each request body on.
+    fn webhook_receiver() -> (Webhook, std::sync::mpsc::Receiver<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(Result::ok) {
+                let mut request = Vec::new();
+                let mut buf = [0; 4096];
+                while !request.ends_with(b"}") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+                let request = String::from_utf8_lossy(&request);
+                let body = request.split("\r\n\r\n").nth(1).unwrap_or_default();
+                let _ = tx.send(body.to_string());
+            }
+        });
+        let webhook = Webhook {
+            url,
+            client: reqwest::Client::new(),
+        };
+        (webhook, rx)
+    }
+
+    // The webhook is posted from a spawned task, which needs a worker thread
+    // while the test blocks waiting for it
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_webhook_fires_once_per_finished_fork() {
+        let (webhook, calls) = webhook_receiver();
+        let state = test_state_with_webhook(Some(webhook));
+        create(&state, "abc-1").await;
+
+        let update = |fork_id: &str, body: serde_json::Value| {
+            let req = serde_json::from_value(body).unwrap();
+            update_fork(State(state.clone()), Path(fork_id.to_string()), Json(req))
+        };
+
+        // `forky done`, then the fork's process exiting
+        let bodies = [
+            serde_json::json!({
+                "project_path": PROJECT,
+                "status": "completed",
+                "summary": "Fixed the parser",
+            }),
+            serde_json::json!({ "project_path": PROJECT, "status": "completed" }),
+        ];
+        for body in bodies {
+            let Json(updated) = update("abc-1", body).await.unwrap();
+            assert_eq!(updated["success"], true);
+        }
+
+        let err = update(
+            "no-such-fork",
+            serde_json::json!({ "project_path": PROJECT, "status": "completed" }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let timeout = std::time::Duration::from_secs(5);
+        let body: serde_json::Value =
+            serde_json::from_str(&calls.recv_timeout(timeout).unwrap()).unwrap();
+        assert_eq!(body["fork_id"], "abc-1");
+        assert_eq!(body["summary"], "Fixed the parser");
+
+        let quiet = std::time::Duration::from_millis(300);
+        assert!(calls.recv_timeout(quiet).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_opens_unloaded_project() {
+        let state = test_state();
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().to_string_lossy().to_string();
+
+        let Json(found) = search(
+            State(state.clone()),
+            query(serde_json::json!({ "project_path": project, "q": "parser" })),
+        )
+        .await
+        .unwrap();
+        assert!(found.results.is_empty());
+        assert!(state
+            .db_manager
+            .read()
+            .await
+            .get(&PathBuf::from(project))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_fork_by_prefix() {
+        let state = test_state();
+        create(&state, "abc-1").await;
+        create(&state, "abd-1").await;
+        let params = serde_json::json!({ "project_path": PROJECT });
+
+        let Json(fork) = get_fork(
+            State(state.clone()),
+            Path("abc".to_string()),
+            query(params.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fork.unwrap().fork_id, "abc-1");
+
+        let Json(fork) = get_fork(
+            State(state.clone()),
+            Path("x".to_string()),
+            query(params.clone()),
+        )
+        .await
+        .unwrap();
+        assert!(fork.is_none());
+
+        let err = get_fork(State(state), Path("ab".to_string()), query(params))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_query_events_by_fork_prefix() {
+        let state = test_state();
+        create(&state, "abc-1").await;
+        let body = serde_json::json!({
+            "project_path": PROJECT,
+            "fork_id": "abc-1",
+            "events": [{"type": "assistant", "uuid": "e1", "session_id": "s1"}],
+        });
+        for expected in [1, 0] {
+            let req = serde_json::from_value(body.clone()).unwrap();
+            let Json(ingested) = ingest_events(State(state.clone()), Json(req))
+                .await
+                .unwrap();
+            // A retried batch does not count its duplicates as stored
+            assert_eq!(ingested.stored, expected);
+        }
+
+        let Json(page) = query_events(
+            State(state.clone()),
+            query(serde_json::json!({ "project_path": PROJECT, "fork_id": "abc" })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].fork_id.as_deref(), Some("abc-1"));
+
+        // Read-only queries don't open databases that aren't loaded
+        let err = query_events(
+            State(state),
+            query(serde_json::json!({ "project_path": "/projects/other" })),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+}
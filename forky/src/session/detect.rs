@@ -2,11 +2,21 @@
 
 use anyhow::Result;
 use serde::Deserialize;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
+/// Legacy, machine-wide session file.
 const FORKY_SESSION_FILE: &str = "/tmp/.forky-session";
+
+/// Name of the session file inside `$XDG_RUNTIME_DIR`.
+const RUNTIME_SESSION_FILE: &str = ".forky-session";
+
+/// Environment variable naming an explicit session file.
+const SESSION_FILE_ENV: &str = "FORKY_SESSION_FILE";
 const CLAUDE_SESSION_FILE: &str = ".claude/current-session.json";
 
+/// Environment variables checked for a session ID, most specific first.
+const SESSION_ENV_VARS: &[&str] = &["FORKY_SESSION_ID", "CLAUDE_SESSION_ID"];
+
 /// Structure of the Claude current-session.json file.
 #[derive(Debug, Deserialize)]
 struct ClaudeSessionFile {
@@ -17,15 +27,21 @@ struct ClaudeSessionFile {
 /// Detect the current Claude session ID.
 ///
 /// Priority order:
-/// 1. Read from `/tmp/.forky-session` (hook-injected)
-/// 2. Walk up directories looking for `.claude/current-session.json`
+/// 1. `FORKY_SESSION_ID`, then `CLAUDE_SESSION_ID` environment variables
+/// 2. Read the hook-injected session file (see [`session_file_candidates`])
+/// 3. Walk up directories looking for `.claude/current-session.json`
 pub fn detect_session_id() -> Result<Option<String>> {
-    // Priority 1: Check /tmp/.forky-session
+    // Priority 1: Environment, which survives shared or wiped /tmp
+    if let Some(session_id) = read_session_env() {
+        return Ok(Some(session_id));
+    }
+
+    // Priority 2: Check the hook-injected session file
     if let Some(session_id) = read_forky_session_file()? {
         return Ok(Some(session_id));
     }
 
-    // Priority 2: Walk up directories looking for .claude/current-session.json
+    // Priority 3: Walk up directories looking for .claude/current-session.json
     if let Some(session_id) = find_claude_session_file()? {
         return Ok(Some(session_id));
     }
@@ -33,21 +49,71 @@ pub fn detect_session_id() -> Result<Option<String>> {
     Ok(None)
 }
 
-/// Read session ID from /tmp/.forky-session.
+/// Read session ID from the first non-empty session environment variable.
+fn read_session_env() -> Option<String> {
+    SESSION_ENV_VARS.iter().find_map(|var| {
+        std::env::var(var)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+/// Read session ID from the first session file that exists and isn't empty.
 fn read_forky_session_file() -> Result<Option<String>> {
-    let path = Path::new(FORKY_SESSION_FILE);
-    if !path.exists() {
-        return Ok(None);
+    let candidates = session_file_candidates(
+        std::env::var_os(SESSION_FILE_ENV).map(PathBuf::from),
+        std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from),
+        parent_pid(),
+    );
+
+    for path in candidates {
+        if !path.exists() {
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let session_id = content.trim().to_string();
+        if !session_id.is_empty() {
+            return Ok(Some(session_id));
+        }
     }
 
-    let content = std::fs::read_to_string(path)?;
-    let session_id = content.trim().to_string();
+    Ok(None)
+}
 
-    if session_id.is_empty() {
-        return Ok(None);
+/// Session file locations, most specific first:
+/// 1. `$FORKY_SESSION_FILE`
+/// 2. `$XDG_RUNTIME_DIR/.forky-session-<parent pid>` (this shell only)
+/// 3. `$XDG_RUNTIME_DIR/.forky-session` (this user only)
+/// 4. `/tmp/.forky-session` (legacy, shared by everyone on the machine)
+fn session_file_candidates(
+    env_override: Option<PathBuf>,
+    runtime_dir: Option<PathBuf>,
+    parent_pid: Option<u32>,
+) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    candidates.extend(env_override);
+    if let Some(dir) = runtime_dir {
+        if let Some(pid) = parent_pid {
+            candidates.push(dir.join(format!("{RUNTIME_SESSION_FILE}-{pid}")));
+        }
+        candidates.push(dir.join(RUNTIME_SESSION_FILE));
     }
+    candidates.push(PathBuf::from(FORKY_SESSION_FILE));
+    candidates
+}
 
-    Ok(Some(session_id))
+/// PID of the shell that launched forky, or `None` if that shell has exited
+/// and forky was re-parented to init.
+#[cfg(unix)]
+fn parent_pid() -> Option<u32> {
+    Some(std::os::unix::process::parent_id()).filter(|&pid| pid > 1)
+}
+
+#[cfg(not(unix))]
+const fn parent_pid() -> Option<u32> {
+    None
 }
 
 /// Walk up directories looking for .claude/current-session.json.
@@ -88,3 +154,29 @@ fn read_claude_session_file(path: &PathBuf) -> Result<Option<String>> {
     let session_file: ClaudeSessionFile = serde_json::from_str(&content)?;
     Ok(session_file.session_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_file_candidates_order() {
+        let candidates = session_file_candidates(
+            Some(PathBuf::from("/custom/session")),
+            Some(PathBuf::from("/run/user/1000")),
+            Some(42),
+        );
+        assert_eq!(
+            candidates,
+            vec![
+                PathBuf::from("/custom/session"),
+                PathBuf::from("/run/user/1000/.forky-session-42"),
+                PathBuf::from("/run/user/1000/.forky-session"),
+                PathBuf::from(FORKY_SESSION_FILE),
+            ]
+        );
+
+        let legacy_only = session_file_candidates(None, None, Some(42));
+        assert_eq!(legacy_only, vec![PathBuf::from(FORKY_SESSION_FILE)]);
+    }
+}